@@ -14,6 +14,12 @@ use smart_default::SmartDefault;
 use strum::{Display, EnumString};
 use utoipa::{schema, ToSchema};
 
+/// Fields holding their default value are left out of the stored JSON to
+/// keep rows small, and are restored with `#[serde(default)]` when decoded
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, Deserialize, Default)]
 pub struct Index {
     // TODO FIXME: Add the Index ID
@@ -23,6 +29,9 @@ pub struct Index {
     pub schema: String,
     pub extraction_policy: String,
     pub extractor: String,
+    // generation of the index, a new generation is created when reindexing
+    #[serde(default)]
+    pub generation: u64,
 }
 
 impl Index {
@@ -50,6 +59,7 @@ impl From<Index> for indexify_coordinator::Index {
             extractor: value.extractor,
             extraction_policy: value.extraction_policy,
             namespace: value.namespace,
+            generation: value.generation,
         }
     }
 }
@@ -63,6 +73,7 @@ impl From<indexify_coordinator::Index> for Index {
             extractor: value.extractor,
             extraction_policy: value.extraction_policy,
             namespace: value.namespace,
+            generation: value.generation,
         }
     }
 }
@@ -94,6 +105,10 @@ pub struct ExtractorDescription {
     pub input_params: serde_json::Value,
     pub outputs: HashMap<String, OutputSchema>,
     pub input_mime_types: Vec<String>,
+    /// Seconds within which the tasks of the extractor are expected to
+    /// complete after they are created
+    #[serde(default)]
+    pub sla_secs: Option<u64>,
 }
 
 impl From<ExtractorDescription> for indexify_coordinator::Extractor {
@@ -121,6 +136,7 @@ impl From<ExtractorDescription> for indexify_coordinator::Extractor {
             embedding_schemas,
             input_mime_types: value.input_mime_types,
             metadata_schemas,
+            sla_secs: value.sla_secs.unwrap_or_default(),
         }
     }
 }
@@ -176,6 +192,7 @@ impl From<indexify_coordinator::Extractor> for ExtractorDescription {
             input_params: serde_json::from_str(&value.input_params).unwrap(),
             outputs: output_schema,
             input_mime_types: value.input_mime_types,
+            sla_secs: (value.sla_secs > 0).then_some(value.sla_secs),
         }
     }
 }
@@ -271,6 +288,7 @@ pub enum TaskOutcome {
     Unknown,
     Success,
     Failed,
+    Cancelled,
 }
 
 impl From<indexify_coordinator::TaskOutcome> for TaskOutcome {
@@ -279,6 +297,7 @@ impl From<indexify_coordinator::TaskOutcome> for TaskOutcome {
             indexify_coordinator::TaskOutcome::Unknown => TaskOutcome::Unknown,
             indexify_coordinator::TaskOutcome::Success => TaskOutcome::Success,
             indexify_coordinator::TaskOutcome::Failed => TaskOutcome::Failed,
+            indexify_coordinator::TaskOutcome::Cancelled => TaskOutcome::Cancelled,
         }
     }
 }
@@ -289,6 +308,7 @@ impl From<TaskOutcome> for indexify_coordinator::TaskOutcome {
             TaskOutcome::Unknown => indexify_coordinator::TaskOutcome::Unknown,
             TaskOutcome::Success => indexify_coordinator::TaskOutcome::Success,
             TaskOutcome::Failed => indexify_coordinator::TaskOutcome::Failed,
+            TaskOutcome::Cancelled => indexify_coordinator::TaskOutcome::Cancelled,
         }
     }
 }
@@ -309,19 +329,102 @@ pub struct Task {
     pub id: String,
     pub extractor: String,
     pub extraction_policy_id: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub output_index_table_mapping: HashMap<String, String>,
     pub namespace: String,
     pub content_metadata: ContentMetadata,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
     pub input_params: serde_json::Value,
     #[schema(value_type = internal_api::TaskOutcome)]
     pub outcome: TaskOutcome,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub index_tables: Vec<String>, // list of index tables that this content may be present in
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub cancel_requested: bool,
+    /// Seconds since the epoch at which the task was created
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub created_at: u64,
+    /// Relative cost of running the task, weighing the load of the executor
+    /// it is assigned to
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub cost: u64,
+    /// Seconds since the epoch at which the executor started the task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
 }
 
+/// Bytes of content adding one unit to the cost of a task
+const TASK_COST_BYTES: u64 = 1 << 20;
+
 impl Task {
+    /// Creates the task applying an extraction policy to a content. The id of
+    /// the task is derived from the policy and the content, so the same task
+    /// is created every time the policy is applied to the content. The index
+    /// tables are the tables of the embeddings the policy writes
+    pub fn new(
+        extraction_policy: &ExtractionPolicy,
+        extractor: &ExtractorDescription,
+        content: &ContentMetadata,
+        created_at: u64,
+    ) -> Self {
+        let mut output_index_table_mapping = HashMap::new();
+        let mut index_tables = Vec::new();
+        for (name, schema) in &extractor.outputs {
+            let table_name = extraction_policy
+                .output_index_name_mapping
+                .get(name)
+                .and_then(|index_name| extraction_policy.index_name_table_mapping.get(index_name));
+            if let Some(table_name) = table_name {
+                output_index_table_mapping.insert(name.clone(), table_name.clone());
+                if let OutputSchema::Embedding(_) = schema {
+                    index_tables.push(table_name.clone());
+                }
+            }
+        }
+        let mut hasher = DefaultHasher::new();
+        extraction_policy.name.hash(&mut hasher);
+        extraction_policy.namespace.hash(&mut hasher);
+        content.id.hash(&mut hasher);
+        Self {
+            id: format!("{:x}", hasher.finish()),
+            extractor: extraction_policy.extractor.clone(),
+            extraction_policy_id: extraction_policy.id.clone(),
+            output_index_table_mapping,
+            namespace: extraction_policy.namespace.clone(),
+            content_metadata: content.clone(),
+            input_params: extraction_policy.input_params.clone(),
+            outcome: TaskOutcome::Unknown,
+            index_tables,
+            cancel_requested: false,
+            created_at,
+            cost: 1 + content.size_bytes / TASK_COST_BYTES,
+            started_at: None,
+        }
+    }
+
     pub fn terminal_state(&self) -> bool {
         self.outcome != TaskOutcome::Unknown
     }
+
+    /// Cost of the task counted towards executor load. Tasks without a cost
+    /// count as one unit
+    pub fn weight(&self) -> u64 {
+        self.cost.max(1)
+    }
+
+    /// Seconds the task waited between its creation and its start, if it
+    /// has started
+    pub fn queue_latency(&self) -> Option<u64> {
+        self.started_at
+            .map(|started_at| started_at.saturating_sub(self.created_at))
+    }
+
+    /// Seconds the task ran between its start and the time it finished at,
+    /// if it has started
+    pub fn completion_latency(&self, finished_at: u64) -> Option<u64> {
+        self.started_at
+            .map(|started_at| finished_at.saturating_sub(started_at))
+    }
 }
 
 impl Display for Task {
@@ -347,6 +450,10 @@ impl From<Task> for indexify_coordinator::Task {
             output_index_mapping: value.output_index_table_mapping,
             outcome: outcome as i32,
             index_tables: value.index_tables,
+            cancel_requested: value.cancel_requested,
+            created_at: value.created_at,
+            cost: value.cost,
+            started_at: value.started_at,
         }
     }
 }
@@ -368,6 +475,10 @@ impl TryFrom<indexify_coordinator::Task> for Task {
             output_index_table_mapping: value.output_index_mapping,
             outcome,
             index_tables: value.index_tables,
+            cancel_requested: value.cancel_requested,
+            created_at: value.created_at,
+            cost: value.cost,
+            started_at: value.started_at,
         })
     }
 }
@@ -464,6 +575,11 @@ pub struct ExtractionPolicy {
     // The source of the content - ingestion, name of some extractor binding
     // which produces the content by invoking an extractor
     pub content_source: String,
+
+    // Tasks of policies with a higher priority are created first when
+    // several policies apply to the same content
+    #[serde(default)]
+    pub priority: u32,
 }
 
 impl std::hash::Hash for ExtractionPolicy {
@@ -487,6 +603,7 @@ impl From<ExtractionPolicy> for indexify_coordinator::ExtractionPolicy {
             filters,
             input_params: value.input_params.to_string(),
             content_source: value.content_source,
+            priority: value.priority,
         }
     }
 }
@@ -572,14 +689,34 @@ pub struct ContentMetadata {
     pub namespace: String,
     pub name: String,
     pub content_type: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub labels: HashMap<String, String>,
     pub storage_url: String,
     pub created_at: i64,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub source: String,
     pub size_bytes: u64,
+    #[serde(default, skip_serializing_if = "is_default")]
     pub tombstoned: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub hash: String,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub extraction_policy_ids: HashMap<String, u64>,
+    /// Position of the chunk within the content it was chunked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<u32>,
+    /// The content this chunk was chunked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_content_id: Option<ContentMetadataId>,
+    /// Time in seconds the content was last written
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub last_modified_at: u64,
+    /// Immutable content can't be overwritten by newly created content
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub immutable: bool,
+    /// Tags the content can be listed by
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl From<ContentMetadata> for indexify_coordinator::ContentMetadata {
@@ -597,6 +734,8 @@ impl From<ContentMetadata> for indexify_coordinator::ContentMetadata {
             size_bytes: value.size_bytes,
             hash: value.hash,
             extraction_policy_ids: value.extraction_policy_ids,
+            chunk_index: value.chunk_index,
+            source_content_id: value.source_content_id.map(|id| id.id),
         }
     }
 }
@@ -625,6 +764,13 @@ impl TryFrom<indexify_coordinator::ContentMetadata> for ContentMetadata {
             tombstoned: false,
             hash: value.hash,
             extraction_policy_ids: value.extraction_policy_ids,
+            chunk_index: value.chunk_index,
+            source_content_id: value
+                .source_content_id
+                .map(|id| ContentMetadataId { id, version: 1 }),
+            last_modified_at: 0,
+            immutable: false,
+            tags: vec![],
         })
     }
 }
@@ -653,6 +799,11 @@ impl Default for ContentMetadata {
             extraction_policy_ids: HashMap::new(),
             tombstoned: false,
             hash: "test_hash".to_string(),
+            chunk_index: None,
+            source_content_id: None,
+            last_modified_at: 0,
+            immutable: false,
+            tags: vec![],
         }
     }
 }
@@ -662,7 +813,10 @@ pub struct ExecutorMetadata {
     pub id: String,
     pub last_seen: u64,
     pub addr: String,
-    pub extractor: ExtractorDescription,
+    pub extractors: Vec<ExtractorDescription>,
+    /// Labels describing where the executor runs, such as its zone
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -759,6 +913,9 @@ pub struct StateChange {
     pub change_type: ChangeType,
     pub created_at: u64,
     pub processed_at: Option<u64>,
+    // position of the change in the order it was applied to the state machine
+    #[serde(default)]
+    pub sequence: u64,
 }
 
 impl Default for StateChange {
@@ -769,6 +926,7 @@ impl Default for StateChange {
             change_type: ChangeType::NewContent,
             created_at: 0,
             processed_at: None,
+            sequence: 0,
         }
     }
 }
@@ -781,6 +939,7 @@ impl StateChange {
             change_type,
             created_at,
             processed_at: None,
+            sequence: 0,
         }
     }
 }
@@ -802,6 +961,7 @@ impl TryFrom<indexify_coordinator::StateChange> for StateChange {
             change_type,
             created_at: value.created_at,
             processed_at: Some(value.processed_at),
+            sequence: 0,
         })
     }
 }
@@ -969,4 +1129,60 @@ mod test {
             );"
         );
     }
+
+    #[test]
+    fn test_default_fields_skipped_when_encoded() {
+        let content = ContentMetadata {
+            labels: HashMap::new(),
+            source: "".to_string(),
+            hash: "".to_string(),
+            ..Default::default()
+        };
+        let task = Task {
+            id: "task".to_string(),
+            content_metadata: content.clone(),
+            ..Default::default()
+        };
+
+        //  Rows written before default fields were skipped hold every field
+        let mut legacy_content = serde_json::to_value(&content).unwrap();
+        for (field, value) in [
+            ("labels", serde_json::json!({})),
+            ("source", serde_json::json!("")),
+            ("tombstoned", serde_json::json!(false)),
+            ("hash", serde_json::json!("")),
+            ("extraction_policy_ids", serde_json::json!({})),
+            ("chunk_index", serde_json::json!(null)),
+            ("source_content_id", serde_json::json!(null)),
+            ("last_modified_at", serde_json::json!(0)),
+            ("immutable", serde_json::json!(false)),
+            ("tags", serde_json::json!([])),
+        ] {
+            legacy_content
+                .as_object_mut()
+                .unwrap()
+                .insert(field.to_string(), value);
+        }
+        let mut legacy_task = serde_json::to_value(&task).unwrap();
+        for (field, value) in [
+            ("output_index_table_mapping", serde_json::json!({})),
+            ("input_params", serde_json::json!(null)),
+            ("index_tables", serde_json::json!([])),
+            ("cancel_requested", serde_json::json!(false)),
+            ("created_at", serde_json::json!(0)),
+            ("cost", serde_json::json!(0)),
+            ("content_metadata", legacy_content),
+        ] {
+            legacy_task
+                .as_object_mut()
+                .unwrap()
+                .insert(field.to_string(), value);
+        }
+        let legacy_row = serde_json::to_vec(&legacy_task).unwrap();
+        let row = serde_json::to_vec(&task).unwrap();
+
+        assert!(row.len() * 3 < legacy_row.len() * 2);
+        assert_eq!(serde_json::from_slice::<Task>(&row).unwrap(), task);
+        assert_eq!(serde_json::from_slice::<Task>(&legacy_row).unwrap(), task);
+    }
 }
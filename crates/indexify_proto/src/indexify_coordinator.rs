@@ -144,6 +144,8 @@ pub struct Index {
     pub extraction_policy: ::prost::alloc::string::String,
     #[prost(string, tag = "6")]
     pub extractor: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "7")]
+    pub generation: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -292,6 +294,18 @@ pub struct Task {
     /// list of all tables that the content may belong to
     #[prost(string, repeated, tag = "9")]
     pub index_tables: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// set when the task should be cancelled by the executor running it
+    #[prost(bool, tag = "10")]
+    pub cancel_requested: bool,
+    /// seconds since the epoch at which the task was created
+    #[prost(uint64, tag = "11")]
+    pub created_at: u64,
+    /// relative cost of running the task
+    #[prost(uint64, tag = "12")]
+    pub cost: u64,
+    /// seconds since the epoch at which the executor started the task
+    #[prost(uint64, optional, tag = "13")]
+    pub started_at: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -323,6 +337,9 @@ pub struct Extractor {
     >,
     #[prost(string, repeated, tag = "6")]
     pub input_mime_types: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// seconds within which tasks are expected to complete, 0 means no sla
+    #[prost(uint64, tag = "7")]
+    pub sla_secs: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -412,6 +429,8 @@ pub struct ExtractionPolicy {
     >,
     #[prost(string, tag = "6")]
     pub content_source: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "7")]
+    pub priority: u32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -433,6 +452,8 @@ pub struct ExtractionPolicyRequest {
     pub content_source: ::prost::alloc::string::String,
     #[prost(int64, tag = "7")]
     pub created_at: i64,
+    #[prost(uint32, tag = "8")]
+    pub priority: u32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -488,6 +509,12 @@ pub struct ContentMetadata {
         ::prost::alloc::string::String,
         u64,
     >,
+    /// position of the chunk within the content it was chunked from
+    #[prost(uint32, optional, tag = "13")]
+    pub chunk_index: ::core::option::Option<u32>,
+    /// id of the content this chunk was chunked from
+    #[prost(string, optional, tag = "14")]
+    pub source_content_id: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(serde::Deserialize, serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -656,6 +683,7 @@ pub enum TaskOutcome {
     Unknown = 0,
     Failed = 1,
     Success = 2,
+    Cancelled = 3,
 }
 impl TaskOutcome {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -667,6 +695,7 @@ impl TaskOutcome {
             TaskOutcome::Unknown => "UNKNOWN",
             TaskOutcome::Failed => "FAILED",
             TaskOutcome::Success => "SUCCESS",
+            TaskOutcome::Cancelled => "CANCELLED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -675,6 +704,7 @@ impl TaskOutcome {
             "UNKNOWN" => Some(Self::Unknown),
             "FAILED" => Some(Self::Failed),
             "SUCCESS" => Some(Self::Success),
+            "CANCELLED" => Some(Self::Cancelled),
             _ => None,
         }
     }
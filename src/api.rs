@@ -24,6 +24,8 @@ pub struct ExtractionPolicy {
     pub filters_eq: Option<HashMap<String, String>>,
     pub input_params: Option<serde_json::Value>,
     pub content_source: Option<String>,
+    #[serde(default)]
+    pub priority: u32,
 }
 
 impl From<ExtractionPolicy> for indexify_coordinator::ExtractionPolicy {
@@ -38,6 +40,7 @@ impl From<ExtractionPolicy> for indexify_coordinator::ExtractionPolicy {
                 .map(|v| v.to_string())
                 .unwrap_or("{}".to_string()),
             content_source: value.content_source.unwrap_or("ingestion".to_string()),
+            priority: value.priority,
         }
     }
 }
@@ -61,6 +64,7 @@ impl TryFrom<indexify_coordinator::Namespace> for DataNamespace {
                 filters_eq: Some(policy.filters),
                 input_params: Some(serde_json::from_str(&policy.input_params)?),
                 content_source: Some(policy.content_source),
+                priority: policy.priority,
             });
         }
         Ok(Self {
@@ -136,6 +140,9 @@ pub struct ExtractionPolicyRequest {
     pub filters_eq: Option<HashMap<String, String>>,
     pub input_params: Option<serde_json::Value>,
     pub content_source: Option<String>,
+    /// Tasks of policies with a higher priority are created first
+    #[serde(default)]
+    pub priority: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
@@ -196,7 +196,7 @@ impl Coordinator {
     ) -> Result<()> {
         let _ = self
             .shared_state
-            .register_executor(addr, executor_id, extractor)
+            .register_executor(addr, executor_id, vec![extractor])
             .await;
         Ok(())
     }
@@ -593,6 +593,8 @@ mod tests {
                 size_bytes: 100,
                 hash: "".to_string(),
                 extraction_policy_ids: HashMap::new(),
+                chunk_index: None,
+                source_content_id: None,
             }])
             .await?;
 
@@ -629,6 +631,7 @@ mod tests {
                         "test_namespace.test.test_output".to_string(),
                     )]),
                     content_source: "ingestion".to_string(),
+                    priority: 0,
                 },
                 mock_extractor(),
             )
@@ -666,6 +669,8 @@ mod tests {
                 size_bytes: 100,
                 hash: "".to_string(),
                 extraction_policy_ids: HashMap::new(),
+                chunk_index: None,
+                source_content_id: None,
             }])
             .await?;
         coordinator.run_scheduler().await?;
@@ -1953,4 +1958,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_policy_priority_orders_tasks() -> Result<(), anyhow::Error> {
+        let (coordinator, shared_state) = setup_coordinator().await;
+        coordinator.create_namespace(DEFAULT_TEST_NAMESPACE).await?;
+
+        //  The embedding policy is created first but depends on the output of OCR
+        for (policy, priority) in [("embedding", 0), ("ocr", 10)] {
+            let extractor = indexify_internal_api::ExtractorDescription {
+                name: policy.into(),
+                input_mime_types: vec!["*/*".into()],
+                ..Default::default()
+            };
+            coordinator
+                .register_executor(policy, &format!("{}_executor", policy), extractor)
+                .await?;
+            let extraction_policy = indexify_internal_api::ExtractionPolicy {
+                id: policy.into(),
+                name: policy.into(),
+                namespace: DEFAULT_TEST_NAMESPACE.into(),
+                extractor: policy.into(),
+                content_source: "ingestion".into(),
+                priority,
+                ..Default::default()
+            };
+            shared_state
+                .create_extraction_policy(extraction_policy, None)
+                .await?;
+        }
+        coordinator.run_scheduler().await?;
+
+        let content_metadata = indexify_coordinator::ContentMetadata {
+            id: "content_id".to_string(),
+            namespace: DEFAULT_TEST_NAMESPACE.to_string(),
+            file_name: "name".into(),
+            mime: "*/*".into(),
+            source: "ingestion".into(),
+            ..Default::default()
+        };
+        coordinator
+            .create_content_metadata(vec![content_metadata])
+            .await?;
+
+        let state_change = shared_state
+            .unprocessed_state_change_events()
+            .await?
+            .into_iter()
+            .find(|change| change.change_type == internal_api::ChangeType::NewContent)
+            .unwrap();
+        let tasks = coordinator.scheduler.create_new_tasks(state_change).await?;
+        let policies = tasks
+            .iter()
+            .map(|task| task.extraction_policy_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(policies, vec!["ocr", "embedding"]);
+        Ok(())
+    }
 }
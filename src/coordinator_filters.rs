@@ -231,3 +231,70 @@ mod test_extractor_mimetype_filter {
         assert!(!res);
     }
 }
+
+/// Error from validating the label filters of an extraction policy. The key
+/// and value are the ones of the invalid filter, as given by the user
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid filter {key:?} = {value:?}: {reason}")]
+pub struct SelectorError {
+    pub key: String,
+    pub value: String,
+    pub reason: String,
+}
+
+/// Validates the label filters of an extraction policy, which select the
+/// content whose labels match every key and value. An empty value matches an
+/// empty label value, and no filters match all content. The filters are
+/// checked in key order, so the same invalid filter is always reported
+pub fn validate_selector(filters: &HashMap<String, String>) -> Result<(), SelectorError> {
+    let mut filters: Vec<_> = filters.iter().collect();
+    filters.sort();
+    for (key, value) in filters {
+        let err = |reason: String| SelectorError {
+            key: key.clone(),
+            value: value.clone(),
+            reason,
+        };
+        crate::api_utils::validate_label_key(key).map_err(|e| err(e.to_string()))?;
+        crate::api_utils::validate_label_value(value).map_err(|e| err(e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_validate_selector {
+    use super::*;
+
+    #[test]
+    fn test_valid_selector() {
+        assert!(validate_selector(&HashMap::new()).is_ok());
+        let filters = HashMap::from([
+            ("key1".to_string(), "value1".to_string()),
+            ("key2".to_string(), "".to_string()),
+        ]);
+        assert!(validate_selector(&filters).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_selector() {
+        let filters = HashMap::from([
+            ("key1".to_string(), "value1".to_string()),
+            ("key:2".to_string(), "value2".to_string()),
+        ]);
+        let err = validate_selector(&filters).unwrap_err();
+        assert_eq!(err.key, "key:2");
+        assert_eq!(err.value, "value2");
+        assert!(err.reason.starts_with("label key invalid"));
+
+        let filters = HashMap::from([
+            ("b".to_string(), "a,b".to_string()),
+            ("a".to_string(), "-value".to_string()),
+        ]);
+        let err = validate_selector(&filters).unwrap_err();
+        assert_eq!(err.key, "a");
+        assert_eq!(err.value, "-value");
+        assert!(err
+            .reason
+            .contains("must begin with an alphanumeric character"));
+    }
+}
@@ -196,6 +196,7 @@ impl CoordinatorService for CoordinatorServiceServer {
             output_index_name_mapping: output_index_name_mapping.clone(),
             index_name_table_mapping: index_name_table_mapping.clone(),
             content_source: request.content_source,
+            priority: request.priority,
         };
         let _ = self
             .coordinator
@@ -156,6 +156,7 @@ impl DataManager {
             created_at: SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)?
                 .as_secs() as i64,
+            priority: ep_req.priority,
         };
         let response = self
             .coordinator_client
@@ -210,6 +211,7 @@ impl DataManager {
                 schema: serde_json::to_value(schema).unwrap().to_string(),
                 extraction_policy: extraction_policy.to_string(),
                 extractor: extractor.to_string(),
+                generation: 0,
             }),
         };
         let req = GrpcHelper::into_req(index);
@@ -522,6 +524,8 @@ impl DataManager {
             size_bytes: res.size_bytes,
             hash: content_hash,
             extraction_policy_ids: HashMap::new(),
+            chunk_index: None,
+            source_content_id: None,
         })
     }
 
@@ -197,6 +197,8 @@ impl IngestExtractedContentState {
                     created_at: frame_state.created_at,
                     hash: content_hash,
                     extraction_policy_ids: HashMap::new(),
+                    chunk_index: None,
+                    source_content_id: None,
                 };
                 self.state
                     .data_manager
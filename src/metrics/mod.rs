@@ -389,6 +389,7 @@ pub mod coordinator {
         pub content_bytes_uploaded: ObservableCounter<u64>,
         pub content_extracted: ObservableCounter<u64>,
         pub content_extracted_bytes: ObservableCounter<u64>,
+        pub state_changes_dropped: ObservableCounter<u64>,
     }
 
     impl Metrics {
@@ -560,6 +561,24 @@ pub mod coordinator {
                 .with_description("Number of bytes extracted")
                 .init();
 
+            let prev_value = Arc::new(Mutex::new(0));
+            let state_changes_dropped = meter
+                .u64_observable_counter("indexify.coordinator.state_changes_dropped")
+                .with_callback({
+                    let app = app.clone();
+                    let prev_value = prev_value.clone();
+                    move |observer| {
+                        let mut prev_value = prev_value.lock().unwrap();
+                        let value = app.compressed_state_changes_dropped();
+                        observer.observe(value - *prev_value, &[]);
+                        *prev_value = value;
+                    }
+                })
+                .with_description(
+                    "Number of state changes dropped for subscribers which fell behind",
+                )
+                .init();
+
             Metrics {
                 registry,
                 provider,
@@ -571,6 +590,7 @@ pub mod coordinator {
                 content_bytes_uploaded,
                 content_extracted,
                 content_extracted_bytes,
+                state_changes_dropped,
             }
         }
     }
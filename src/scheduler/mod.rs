@@ -1,7 +1,4 @@
-use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    hash::{Hash, Hasher},
-};
+use std::collections::HashMap;
 
 use anyhow::{anyhow, Ok, Result};
 use indexify_internal_api as internal_api;
@@ -12,6 +9,7 @@ use tracing::info;
 use crate::{
     state::SharedState,
     task_allocator::{planner::plan::TaskAllocationPlan, TaskAllocator},
+    utils::timestamp_secs,
 };
 
 pub struct Scheduler {
@@ -104,12 +102,15 @@ impl Scheduler {
                     .await?
             }
             internal_api::ChangeType::NewContent => {
-                let extraction_policies = self
+                let mut extraction_policies = self
                     .shared_state
                     .filter_extraction_policy_for_content(
                         &state_change.object_id.clone().try_into()?,
                     )
                     .await?;
+                //  Tasks of prerequisite policies are created before the ones depending on
+                // them
+                extraction_policies.sort_by_key(|policy| std::cmp::Reverse(policy.priority));
                 let content = self
                     .shared_state
                     .get_content_metadata_with_version(&state_change.object_id.clone().try_into()?)
@@ -156,11 +157,16 @@ impl Scheduler {
                 .get_executor_by_id(&state_change.object_id)
                 .await
                 .map_err(|e| anyhow!("redistribute_tasks: {}", e))?;
-            return self
-                .task_allocator
-                .reallocate_all_tasks_matching_extractor(&executor.extractor.name)
-                .await
-                .map_err(|e| anyhow!("redistribute_tasks: {}", e));
+            let mut allocation_plan = TaskAllocationPlan(HashMap::new());
+            for extractor in &executor.extractors {
+                let plan = self
+                    .task_allocator
+                    .reallocate_all_tasks_matching_extractor(&extractor.name)
+                    .await
+                    .map_err(|e| anyhow!("redistribute_tasks: {}", e))?;
+                allocation_plan.0.extend(plan.0);
+            }
+            return Ok(allocation_plan);
         }
         Ok(TaskAllocationPlan(HashMap::new()))
     }
@@ -201,36 +207,10 @@ impl Scheduler {
             .shared_state
             .extractor_with_name(&extraction_policy.extractor)
             .await?;
-        let mut output_mapping: HashMap<String, String> = HashMap::new();
-
-        // Just store the mapping
-        for name in extractor.outputs.keys() {
-            let index_name = extraction_policy
-                .output_index_name_mapping
-                .get(name)
-                .unwrap();
-            let table_name = extraction_policy
-                .index_name_table_mapping
-                .get(index_name)
-                .unwrap();
-            output_mapping.insert(name.clone(), table_name.clone());
-        }
-        let mut hasher = DefaultHasher::new();
-        extraction_policy.name.hash(&mut hasher);
-        extraction_policy.namespace.hash(&mut hasher);
-        content.id.hash(&mut hasher);
-        let id = format!("{:x}", hasher.finish());
-        let task = internal_api::Task {
-            id,
-            extractor: extraction_policy.extractor.clone(),
-            extraction_policy_id: extraction_policy.id.clone(),
-            output_index_table_mapping: output_mapping.clone(),
-            namespace: extraction_policy.namespace.clone(),
-            content_metadata: content.clone(),
-            input_params: extraction_policy.input_params.clone(),
-            outcome: internal_api::TaskOutcome::Unknown,
-            index_tables: index_tables.to_vec(),
-        };
+        let mut task =
+            internal_api::Task::new(&extraction_policy, &extractor, content, timestamp_secs());
+        //  The task writes to the tables of all the policies applied to the content
+        task.index_tables = index_tables.to_vec();
         info!("created task: {:?}", task);
         Ok(task)
     }
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fmt,
     fs,
     net::{AddrParseError, IpAddr, Ipv4Addr, SocketAddr},
@@ -35,6 +36,14 @@ fn default_raft_port() -> u64 {
     8970
 }
 
+fn default_state_change_retention_secs() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_state_change_broadcast_capacity() -> usize {
+    100
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, strum::Display)]
 #[strum(serialize_all = "kebab-case")]
 pub enum MetadataStoreKind {
@@ -354,12 +363,107 @@ pub struct ServerCacheConfig {
 pub struct StateStoreConfig {
     /// path is the path to the sled database.
     pub path: Option<String>,
+    /// state_change_retention_secs is how long processed state changes are
+    /// kept before they are dropped during compaction.
+    #[serde(default = "default_state_change_retention_secs")]
+    pub state_change_retention_secs: u64,
+    /// wal_dir is the directory of the RocksDB write ahead log. It defaults to
+    /// the database directory.
+    pub wal_dir: Option<String>,
+    /// state_changes_path is the path of a separate database for the state
+    /// changes. They are kept in the main database when it isn't set. State
+    /// changes already in the main database aren't moved over.
+    pub state_changes_path: Option<String>,
+    /// level0_slowdown_writes_trigger is the number of level 0 files at which
+    /// RocksDB starts slowing down writes.
+    pub level0_slowdown_writes_trigger: Option<i32>,
+    /// level0_stop_writes_trigger is the number of level 0 files at which
+    /// RocksDB stops writes.
+    pub level0_stop_writes_trigger: Option<i32>,
+    /// soft_pending_compaction_bytes_limit is the estimated number of bytes
+    /// pending compaction at which RocksDB starts slowing down writes.
+    pub soft_pending_compaction_bytes_limit: Option<usize>,
+    /// hard_pending_compaction_bytes_limit is the estimated number of bytes
+    /// pending compaction at which RocksDB stops writes.
+    pub hard_pending_compaction_bytes_limit: Option<usize>,
+    /// background_reverse_index_updates applies the in-memory reverse index
+    /// updates of a write on a background thread, so the write returns right
+    /// after it's committed.
+    #[serde(default)]
+    pub background_reverse_index_updates: bool,
+    /// log_reverse_index_mutations logs at debug level every change that a
+    /// write makes to the in-memory reverse indexes. It snapshots the reverse
+    /// indexes around every write, so it's only meant for debugging.
+    #[serde(default)]
+    pub log_reverse_index_mutations: bool,
+    /// content_bloom_filter_bits_per_key enables a bloom filter with the
+    /// given number of bits per key on the content column family, which
+    /// speeds up the lookups of content that doesn't exist.
+    pub content_bloom_filter_bits_per_key: Option<f64>,
+    /// async_commits buffers the WAL writes of commits in memory instead of
+    /// writing them out on every commit. They are only durable once the WAL
+    /// is flushed, see wal_flush_interval_ms.
+    #[serde(default)]
+    pub async_commits: bool,
+    /// wal_flush_interval_ms is the interval at which a background task
+    /// flushes and syncs the WAL to disk. It is disabled when unset.
+    pub wal_flush_interval_ms: Option<u64>,
+    /// compaction_styles is the compaction style of the state machine column
+    /// families, by column family name. Column families which aren't listed
+    /// use level compaction. The style of a column family can't be changed
+    /// once the store is created.
+    #[serde(default)]
+    pub compaction_styles: HashMap<String, CompactionStyle>,
+    /// cf_ttl_secs is the time to live of the time series column families, by
+    /// column family name. They use FIFO compaction, which drops their files
+    /// once the newest entry in them is older than the TTL. Only the append
+    /// mostly column families StateChanges and ArchivedTasks support a TTL.
+    /// A TTL can't be added to or removed from an existing store.
+    #[serde(default)]
+    pub cf_ttl_secs: HashMap<String, u64>,
+    /// state_change_broadcast_capacity is the number of state changes
+    /// buffered for remote subscribers. The oldest state changes are dropped
+    /// for the subscribers which fall behind by more than the capacity. It's
+    /// rounded up to a power of two.
+    #[serde(default = "default_state_change_broadcast_capacity")]
+    pub state_change_broadcast_capacity: usize,
+}
+
+/// CompactionStyle is the RocksDB compaction style of a column family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStyle {
+    /// Level compaction, which favors reads.
+    #[default]
+    Level,
+
+    /// Universal (tiered) compaction, which favors writes.
+    Universal,
+
+    /// FIFO compaction, which drops the oldest files. It is used by the column
+    /// families with a TTL, see cf_ttl_secs, and can't be configured directly.
+    Fifo,
 }
 
 impl Default for StateStoreConfig {
     fn default() -> Self {
         Self {
             path: Some("/tmp/indexify-state".to_string()),
+            state_change_retention_secs: default_state_change_retention_secs(),
+            wal_dir: None,
+            state_changes_path: None,
+            level0_slowdown_writes_trigger: None,
+            level0_stop_writes_trigger: None,
+            soft_pending_compaction_bytes_limit: None,
+            hard_pending_compaction_bytes_limit: None,
+            background_reverse_index_updates: false,
+            log_reverse_index_mutations: false,
+            content_bloom_filter_bits_per_key: None,
+            async_commits: false,
+            wal_flush_interval_ms: None,
+            compaction_styles: HashMap::new(),
+            cf_ttl_secs: HashMap::new(),
+            state_change_broadcast_capacity: default_state_change_broadcast_capacity(),
         }
     }
 }
@@ -50,9 +50,10 @@ impl ForwardableRaft {
             return self.network.forward(&leader_address.addr, request).await;
         }
 
-        self.raft.client_write(request).await?;
+        let write_response = self.raft.client_write(request).await?;
         let response = StateMachineUpdateResponse {
             handled_by: self.id,
+            value: write_response.data.value,
         };
         Ok(response)
     }
@@ -78,6 +78,7 @@ impl RaftGrpcServer {
         if nodes_in_cluster.contains_key(&node_id) {
             let response = StateMachineUpdateResponse {
                 handled_by: self.id,
+                value: None,
             };
             return GrpcHelper::ok_response(response);
         }
@@ -114,6 +115,7 @@ impl RaftGrpcServer {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         self.raft
             .client_write(state_machine_req)
@@ -130,6 +132,7 @@ impl RaftGrpcServer {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         self.raft
             .client_write(state_machine_req)
@@ -140,6 +143,7 @@ impl RaftGrpcServer {
 
         let response = StateMachineUpdateResponse {
             handled_by: self.id,
+            value: None,
         };
         GrpcHelper::ok_response(response)
     }
@@ -158,13 +162,15 @@ impl RaftGrpcServer {
         &self,
         request: StateMachineUpdateRequest,
     ) -> Result<tonic::Response<RaftReply>, Status> {
-        let response = StateMachineUpdateResponse {
-            handled_by: self.id,
-        };
-        self.raft
+        let write_response = self
+            .raft
             .client_write(request)
             .await
             .map_err(|e| GrpcHelper::internal_err(e.to_string()))?;
+        let response = StateMachineUpdateResponse {
+            handled_by: self.id,
+            value: write_response.data.value,
+        };
         GrpcHelper::ok_response(response)
     }
 }
@@ -303,6 +309,7 @@ impl RaftApi for RaftGrpcServer {
                 },
                 new_state_changes: vec![],
                 state_changes_processed: vec![],
+                correlation_id: None,
             })
             .map_err(|e| GrpcHelper::internal_err(e.to_string()))?;
 
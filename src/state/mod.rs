@@ -25,7 +25,8 @@ use openraft::{
 };
 use serde::Serialize;
 use store::{
-    requests::{RequestPayload, StateChangeProcessed, StateMachineUpdateRequest},
+    requests::{RequestPayload, SchemaTransform, StateChangeProcessed, StateMachineUpdateRequest},
+    state_machine_objects::HeartbeatOutcome,
     ExecutorId,
     ExecutorIdRef,
     Response,
@@ -173,7 +174,8 @@ impl App {
         let db_path: &Path = Path::new(&db_path_str);
         let sm_blob_store_path: &Path = Path::new(&sm_blob_store_path_str);
 
-        let (log_store, state_machine) = new_storage(db_path, sm_blob_store_path).await;
+        let (log_store, state_machine) =
+            new_storage(db_path, sm_blob_store_path, &server_config.state_store).await;
         let state_change_rx = state_machine.state_change_rx.clone();
 
         let raft_client = Arc::new(RaftClient::new());
@@ -359,6 +361,7 @@ impl App {
             payload: RequestPayload::MarkStateChangesProcessed { state_changes },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(())
@@ -531,7 +534,8 @@ impl App {
 
     pub async fn unassigned_tasks(&self) -> Result<Vec<internal_api::Task>> {
         let mut tasks = vec![];
-        for task_id in self.state_machine.get_unassigned_tasks().await.iter() {
+        //  Tasks of paused namespaces are left out so they aren't assigned
+        for task_id in self.state_machine.assignable_tasks().await?.iter() {
             let task = self
                 .state_machine
                 .get_from_cf::<internal_api::Task, _>(StateMachineColumns::Tasks, task_id)
@@ -571,6 +575,18 @@ impl App {
         self.state_machine.get_executor_running_task_count().await
     }
 
+    pub async fn get_executor_weighted_loads(&self) -> HashMap<ExecutorId, u64> {
+        self.state_machine.executor_weighted_loads().await
+    }
+
+    /// Returns the cost of each of the given tasks
+    pub async fn get_task_weights(
+        &self,
+        task_ids: &HashSet<TaskId>,
+    ) -> Result<HashMap<TaskId, u64>> {
+        self.state_machine.task_weights(task_ids).await
+    }
+
     pub async fn unfinished_tasks_by_extractor(
         &self,
         extractor: &str,
@@ -613,6 +629,7 @@ impl App {
                 timestamp_secs(),
             )],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self
             .forwardable_raft
@@ -622,6 +639,45 @@ impl App {
         Ok(())
     }
 
+    /// Removes in a single write the executors which haven't sent a heartbeat
+    /// within `ttl_secs` and requeues their tasks. Returns the ids of the
+    /// executors found stale
+    pub async fn reap_stale_executors(&self, ttl_secs: u64) -> Result<Vec<ExecutorId>> {
+        let now = timestamp_secs();
+        //  The state machine reaps the executors which are stale when the write is
+        // applied, the removal events are sent for the ones found stale here
+        let stale_executor_ids: Vec<ExecutorId> = self
+            .get_executors()
+            .await?
+            .into_iter()
+            .filter(|executor| executor.last_seen.saturating_add(ttl_secs) < now)
+            .map(|executor| executor.id)
+            .collect();
+        if stale_executor_ids.is_empty() {
+            return Ok(stale_executor_ids);
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::ReapStaleExecutors {
+                now_secs: now,
+                ttl_secs,
+            },
+            new_state_changes: stale_executor_ids
+                .iter()
+                .map(|executor_id| {
+                    StateChange::new(
+                        executor_id.to_string(),
+                        internal_api::ChangeType::ExecutorRemoved,
+                        now,
+                    )
+                })
+                .collect(),
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(stale_executor_ids)
+    }
+
     pub async fn create_extraction_policy(
         &self,
         extraction_policy: ExtractionPolicy,
@@ -666,6 +722,7 @@ impl App {
                 timestamp_secs(),
             )],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(())
@@ -677,6 +734,68 @@ impl App {
         executor_id: Option<String>,
         content_meta_list: Vec<internal_api::ContentMetadata>,
     ) -> Result<()> {
+        self.update_task_with_artifact(task, executor_id, content_meta_list, None)
+            .await
+    }
+
+    /// Finishes a batch of tasks completed by an executor in a single write,
+    /// along with the content produced by each of them
+    pub async fn finish_tasks(
+        &self,
+        executor_id: &str,
+        mut finished: Vec<(
+            internal_api::Task,
+            Option<Vec<internal_api::ContentMetadata>>,
+        )>,
+    ) -> Result<()> {
+        let now = timestamp_secs();
+        let mut state_changes = vec![];
+        for (task, content_meta_list) in &mut finished {
+            for content in content_meta_list.iter_mut().flatten() {
+                content.last_modified_at = now;
+                state_changes.push(StateChange::new(
+                    content.id.to_string(),
+                    internal_api::ChangeType::NewContent,
+                    now,
+                ));
+            }
+            if task.outcome == internal_api::TaskOutcome::Success {
+                state_changes.push(StateChange::new(
+                    task.id.clone(),
+                    internal_api::ChangeType::TaskCompleted {
+                        content_id: task.content_metadata.id.clone(),
+                    },
+                    now,
+                ));
+            }
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::FinishTasks {
+                executor_id: executor_id.to_string(),
+                finished,
+                update_time: SystemTime::now(),
+            },
+            new_state_changes: state_changes,
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Updates a task along with the reference to its output in an external
+    /// artifact store. Only the reference is stored in the state machine
+    pub async fn update_task_with_artifact(
+        &self,
+        task: internal_api::Task,
+        executor_id: Option<String>,
+        mut content_meta_list: Vec<internal_api::ContentMetadata>,
+        artifact_ref: Option<String>,
+    ) -> Result<()> {
+        let now = timestamp_secs();
+        for content in &mut content_meta_list {
+            content.last_modified_at = now;
+        }
         let mut state_changes = vec![];
         for content in &content_meta_list {
             state_changes.push(StateChange::new(
@@ -695,20 +814,63 @@ impl App {
                 timestamp_secs(),
             ));
         }
+        let cancelled = task.outcome == internal_api::TaskOutcome::Cancelled;
+        if cancelled {
+            //  The state machine drops the cancellation of an unknown task
+            self.task_with_id(&task.id).await?;
+        }
         let req = StateMachineUpdateRequest {
             payload: RequestPayload::UpdateTask {
                 task: task.clone(),
                 executor_id: executor_id.clone(),
                 content_metadata: content_meta_list.clone(),
                 update_time: SystemTime::now(),
+                cancelled,
+                artifact_ref,
             },
             new_state_changes: state_changes,
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(())
     }
 
+    /// Asks the executor running a task to cancel it. The cancellation is
+    /// finalized once the executor acknowledges it, unless the task finishes
+    /// first
+    pub async fn request_task_cancel(&self, task_id: &str) -> Result<()> {
+        //  The state machine drops the cancellation of an unknown task
+        self.task_with_id(task_id).await?;
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::RequestTaskCancel {
+                task_id: task_id.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Moves the tasks which finished at least `older_than_secs` ago to the
+    /// archived tasks, keeping only their summary. Returns the number of
+    /// archived tasks
+    pub async fn archive_finished_tasks(&self, older_than_secs: u64) -> Result<usize> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::ArchiveFinishedTasks {
+                older_than_secs,
+                now_secs: timestamp_secs(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let resp = self.forwardable_raft.client_write(req).await?;
+        resp.outcome()
+    }
+
     pub async fn create_gc_tasks(
         &self,
         gc_tasks: Vec<indexify_internal_api::GarbageCollectionTask>,
@@ -717,6 +879,7 @@ impl App {
             payload: RequestPayload::CreateOrAssignGarbageCollectionTask { gc_tasks },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         self.forwardable_raft.client_write(request).await?;
         Ok(())
@@ -731,6 +894,7 @@ impl App {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         self.forwardable_raft.client_write(req).await?;
         Ok(())
@@ -778,6 +942,34 @@ impl App {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Stops the tasks of a namespace from being assigned until it's unpaused
+    pub async fn pause_namespace(&self, namespace: &str) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::PauseNamespace {
+                namespace: namespace.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    pub async fn unpause_namespace(&self, namespace: &str) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::UnpauseNamespace {
+                namespace: namespace.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(())
@@ -824,12 +1016,62 @@ impl App {
         self.state_machine.get_namespace(namespace).await
     }
 
+    /// Fails when an extractor is registered with a description differing
+    /// from the stored one while other executors serve it, since the state
+    /// machine rejects such redefinitions
+    async fn check_extractor_redefinitions(
+        &self,
+        executor_id: &str,
+        extractors: &[internal_api::ExtractorDescription],
+    ) -> Result<()> {
+        let extractor_executors = self.state_machine.get_extractor_executors_table().await;
+        for extractor in extractors {
+            let stored_extractor = self
+                .state_machine
+                .get_from_cf::<internal_api::ExtractorDescription, _>(
+                    StateMachineColumns::Extractors,
+                    &extractor.name,
+                )
+                .await?;
+            let served_by_others = extractor_executors
+                .get(&extractor.name)
+                .map_or(false, |executor_ids| {
+                    executor_ids.iter().any(|id| id != executor_id)
+                });
+            match stored_extractor {
+                Some(stored_extractor) if stored_extractor != *extractor && served_by_others => {
+                    return Err(anyhow!(
+                        "extractor {} is already registered with a different description",
+                        extractor.name
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     pub async fn register_executor(
         &self,
         addr: &str,
         executor_id: &str,
-        extractor: internal_api::ExtractorDescription,
+        extractors: Vec<internal_api::ExtractorDescription>,
+    ) -> Result<String> {
+        self.register_executor_with_labels(addr, executor_id, extractors, HashMap::new())
+            .await
+    }
+
+    /// Registers an executor along with labels describing where it runs, such
+    /// as its zone
+    pub async fn register_executor_with_labels(
+        &self,
+        addr: &str,
+        executor_id: &str,
+        extractors: Vec<internal_api::ExtractorDescription>,
+        labels: HashMap<String, String>,
     ) -> Result<String> {
+        self.check_extractor_redefinitions(executor_id, &extractors)
+            .await?;
         let state_change = StateChange::new(
             executor_id.to_string(),
             internal_api::ChangeType::ExecutorAdded,
@@ -839,16 +1081,53 @@ impl App {
             payload: RequestPayload::RegisterExecutor {
                 addr: addr.to_string(),
                 executor_id: executor_id.to_string(),
-                extractor,
+                extractors,
                 ts_secs: timestamp_secs(),
+                labels,
             },
             new_state_changes: vec![state_change.clone()],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(state_change.id)
     }
 
+    /// Registers an executor and assigns it up to `max` of the unassigned
+    /// tasks of its extractors in the same write. Returns the ids of the
+    /// claimed tasks
+    pub async fn register_executor_and_claim(
+        &self,
+        addr: &str,
+        executor_id: &str,
+        extractors: Vec<internal_api::ExtractorDescription>,
+        labels: HashMap<String, String>,
+        max: usize,
+    ) -> Result<Vec<TaskId>> {
+        self.check_extractor_redefinitions(executor_id, &extractors)
+            .await?;
+        let state_change = StateChange::new(
+            executor_id.to_string(),
+            internal_api::ChangeType::ExecutorAdded,
+            timestamp_secs(),
+        );
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::RegisterExecutorAndClaim {
+                addr: addr.to_string(),
+                executor_id: executor_id.to_string(),
+                extractors,
+                ts_secs: timestamp_secs(),
+                max,
+                labels,
+            },
+            new_state_changes: vec![state_change],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let resp = self.forwardable_raft.client_write(req).await?;
+        resp.outcome()
+    }
+
     pub async fn list_extractors(&self) -> Result<Vec<internal_api::ExtractorDescription>> {
         let extractors: Vec<internal_api::ExtractorDescription> = self
             .state_machine
@@ -900,15 +1179,83 @@ impl App {
                 state_change_id: state_change_id.to_string(),
                 processed_at: timestamp_secs(),
             }],
+            correlation_id: None,
         };
         self.forwardable_raft.client_write(req).await?;
         Ok(())
     }
 
+    /// Assigns a task to an executor, failing with a conflict when the task is
+    /// already assigned to an executor
+    pub async fn assign_task_to(&self, task_id: &str, executor_id: &str) -> Result<()> {
+        self.task_with_id(task_id).await?;
+        self.get_executor_by_id(executor_id).await?;
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::AssignTaskTo {
+                task_id: task_id.to_string(),
+                executor_id: executor_id.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        self.forwardable_raft.client_write(req).await?;
+
+        //  The state machine drops the assignment of an assigned task, so the
+        // outcome is read back
+        match self.state_machine.task_executor(task_id).await? {
+            Some(assigned_to) if assigned_to == executor_id => Ok(()),
+            Some(assigned_to) => Err(anyhow!(
+                "conflict assigning task {}: assigned to executor {}",
+                task_id,
+                assigned_to
+            )),
+            None => Err(anyhow!("task {} wasn't assigned", task_id)),
+        }
+    }
+
+    /// Records the time at which an executor started a task, failing when the
+    /// task isn't assigned to the executor
+    pub async fn mark_task_started(&self, task_id: &str, executor_id: &str) -> Result<()> {
+        match self.state_machine.task_executor(task_id).await? {
+            Some(assigned_to) if assigned_to == executor_id => {}
+            assigned_to => {
+                return Err(anyhow!(
+                    "task {} isn't assigned to executor {}: assigned to executor {:?}",
+                    task_id,
+                    executor_id,
+                    assigned_to
+                ))
+            }
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::MarkTaskStarted {
+                task_id: task_id.to_string(),
+                executor_id: executor_id.to_string(),
+                ts_secs: timestamp_secs(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        self.forwardable_raft.client_write(req).await?;
+
+        //  The state machine drops the start when the task was reassigned in the
+        // meantime, so the outcome is read back
+        match self.task_with_id(task_id).await?.started_at {
+            Some(_) => Ok(()),
+            None => Err(anyhow!("start of task {} was dropped", task_id)),
+        }
+    }
+
     pub async fn create_content_batch(
         &self,
-        content_metadata: Vec<internal_api::ContentMetadata>,
+        mut content_metadata: Vec<internal_api::ContentMetadata>,
     ) -> Result<()> {
+        let now = timestamp_secs();
+        for content in &mut content_metadata {
+            content.last_modified_at = now;
+        }
         let content_ids: Vec<String> = content_metadata.iter().map(|c| c.id.id.clone()).collect();
         let existing_content = self.get_content_metadata_batch(content_ids.clone()).await?;
         let existing_content_map: HashMap<String, internal_api::ContentMetadata> = existing_content
@@ -967,6 +1314,7 @@ impl App {
                     &content.parent_id.id,
                     latest_version_of_parent,
                 );
+                old_node.last_modified_at = now;
                 content_to_write.push(old_node);
             }
             let req = StateMachineUpdateRequest {
@@ -975,6 +1323,7 @@ impl App {
                 },
                 new_state_changes: vec![],
                 state_changes_processed: vec![],
+                correlation_id: None,
             };
             self.forwardable_raft.client_write(req).await.map_err(|e| {
                 anyhow!(
@@ -1028,12 +1377,33 @@ impl App {
             ));
         }
 
+        //  Overwriting immutable content is rejected by the state machine
+        let immutable_content = self
+            .state_machine
+            .get_content_from_ids_with_version(
+                updated_contents_to_write
+                    .iter()
+                    .map(|content| content.id.clone())
+                    .collect(),
+            )
+            .await?
+            .into_iter()
+            .find(|content| content.immutable);
+        if let Some(content) = immutable_content {
+            return Err(anyhow!(
+                "content {} is immutable and can't be overwritten",
+                content.id
+            ));
+        }
+
         let req = StateMachineUpdateRequest {
             payload: RequestPayload::CreateContent {
                 content_metadata: updated_contents_to_write,
+                expected_version: None,
             },
             new_state_changes: state_changes,
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _ = self.forwardable_raft.client_write(req).await.map_err(|e| {
             anyhow!(
@@ -1045,6 +1415,47 @@ impl App {
         Ok(())
     }
 
+    /// Writes the content only if its latest stored version is
+    /// `expected_version`, which is 0 for content that doesn't exist yet. The
+    /// written content gets the next version
+    pub async fn compare_and_swap_content(
+        &self,
+        mut content: internal_api::ContentMetadata,
+        expected_version: u64,
+    ) -> Result<()> {
+        content.id.version = expected_version + 1;
+        content.last_modified_at = timestamp_secs();
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: Some(expected_version),
+            },
+            new_state_changes: vec![StateChange::new(
+                content.id.to_string(),
+                internal_api::ChangeType::NewContent,
+                timestamp_secs(),
+            )],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let resp = self
+            .forwardable_raft
+            .client_write(req)
+            .await
+            .map_err(|e| anyhow!("unable to write content metadata: {}", e))?;
+
+        //  The state machine drops conflicting writes
+        let swapped: bool = resp.outcome()?;
+        if !swapped {
+            return Err(anyhow!(
+                "conflict writing content {}: expected version {}",
+                content.id.id,
+                expected_version
+            ));
+        }
+        Ok(())
+    }
+
     /// This method will accept a vector of content ids to tombstone. It will
     /// get the latest version of each content id and tombstone that one
     pub async fn tombstone_content_batch(
@@ -1120,6 +1531,7 @@ impl App {
             },
             new_state_changes: state_changes,
             state_changes_processed: vec![],
+            correlation_id: None,
         };
 
         self.forwardable_raft
@@ -1178,6 +1590,7 @@ impl App {
                 state_change_id: state_change_id.to_string(),
                 processed_at: timestamp_secs(),
             }],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(())
@@ -1271,12 +1684,39 @@ impl App {
     pub async fn get_index(&self, id: &str) -> Result<internal_api::Index> {
         let index = self
             .state_machine
-            .get_from_cf::<internal_api::Index, _>(StateMachineColumns::IndexTable, id)
+            .get_indexes_from_ids(HashSet::from([id.to_string()]))
             .await?
+            .pop()
             .ok_or_else(|| anyhow!("Index with id {} not found", id))?;
         Ok(index)
     }
 
+    /// Creates several indexes of a namespace atomically
+    pub async fn create_indexes(
+        &self,
+        namespace: &str,
+        indexes: Vec<(String, internal_api::Index)>,
+    ) -> Result<()> {
+        //  Duplicate ids are skipped by the state machine
+        let mut ids = HashSet::new();
+        for (id, _) in &indexes {
+            if !ids.insert(id) {
+                return Err(anyhow!("duplicate index id {} in batch", id));
+            }
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::CreateIndexes {
+                namespace: namespace.to_string(),
+                indexes,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
     pub async fn create_index(
         &self,
         namespace: &str,
@@ -1291,6 +1731,61 @@ impl App {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Creates an index together with the schema of its structured data. The
+    /// schema may only reference attributes produced by the index's extractor
+    pub async fn create_index_with_schema(
+        &self,
+        namespace: &str,
+        index: internal_api::Index,
+        id: String,
+        schema: internal_api::StructuredDataSchema,
+    ) -> Result<()> {
+        self.state_machine
+            .validate_index_schema(&index, &schema)
+            .await?;
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::CreateIndexWithSchema {
+                namespace: namespace.to_string(),
+                index,
+                id,
+                schema,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Makes a generation of an index serve reads. The generation must have
+    /// been created with create_index beforehand
+    pub async fn promote_index_generation(&self, index_id: &str, generation: u64) -> Result<()> {
+        if !self
+            .state_machine
+            .index_generation_exists(index_id, generation)
+            .await?
+        {
+            return Err(anyhow!(
+                "Generation {} of index {} not found",
+                generation,
+                index_id
+            ));
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::PromoteIndexGeneration {
+                index_id: index_id.to_string(),
+                generation,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
         };
         let _resp = self.forwardable_raft.client_write(req).await?;
         Ok(())
@@ -1336,6 +1831,349 @@ impl App {
         Ok(schemas)
     }
 
+    /// Replaces a schema with a new one. The transform is applied to the
+    /// existing content of the schema by run_schema_backfill
+    pub async fn replace_schema(
+        &self,
+        old_id: &str,
+        new_schema: StructuredDataSchema,
+        transform: SchemaTransform,
+    ) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::ReplaceSchema {
+                old_id: old_id.to_string(),
+                new_schema,
+                transform,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Writes a new schema version and makes it the active schema of the
+    /// namespace in the same write. Previous schema versions stay readable
+    pub async fn set_namespace_schema(
+        &self,
+        namespace: &str,
+        schema: StructuredDataSchema,
+    ) -> Result<()> {
+        if schema.namespace != namespace {
+            return Err(anyhow!(
+                "schema {} belongs to namespace {}, not {}",
+                schema.id,
+                schema.namespace,
+                namespace
+            ));
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::SetNamespaceSchema {
+                namespace: namespace.to_string(),
+                schema,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Runs the pending backfill of a schema. Returns the number of content
+    /// updated, which is 0 if no backfill is pending
+    pub async fn run_schema_backfill(&self, schema_id: &str) -> Result<usize> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::RunSchemaBackfill {
+                schema_id: schema_id.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let resp = self.forwardable_raft.client_write(req).await?;
+        resp.outcome()
+    }
+
+    /// Creates in a single write the tasks applying an extraction policy to
+    /// the existing content of its namespace which the policy wasn't applied
+    /// to yet
+    pub async fn backfill_policy(&self, namespace: &str, policy_id: &str) -> Result<()> {
+        let extraction_policy = self.get_extraction_policy(policy_id).await?;
+        if extraction_policy.namespace != namespace {
+            return Err(anyhow!(
+                "extraction policy {} doesn't belong to namespace {}",
+                policy_id,
+                namespace
+            ));
+        }
+        //  The state machine fails on a missing extractor, so it's checked here
+        self.extractor_with_name(&extraction_policy.extractor)
+            .await?;
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::BackfillPolicy {
+                namespace: namespace.to_string(),
+                policy_id: policy_id.to_string(),
+                created_at: timestamp_secs(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Marks an extraction policy applied on a content and, in the same write,
+    /// creates the tasks of the downstream policies for the content it
+    /// produced
+    pub async fn mark_policy_applied_and_spawn(
+        &self,
+        content_id: &ContentMetadataId,
+        policy_id: &str,
+    ) -> Result<()> {
+        //  The state machine fails on a missing policy or content, so they're
+        // checked here
+        self.get_extraction_policy(policy_id).await?;
+        if self
+            .get_content_metadata_with_version(content_id)
+            .await?
+            .is_empty()
+        {
+            return Err(anyhow!("content {} not found", content_id));
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::MarkPolicyAppliedAndSpawn {
+                content_id: content_id.clone(),
+                policy_id: policy_id.to_string(),
+                created_at: timestamp_secs(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Cancels the unfinished tasks whose content has been deleted
+    pub async fn cancel_orphaned_tasks(&self) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::CancelOrphanedTasks,
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Re-adds unfinished tasks that are neither assigned to an executor nor
+    /// queued as unassigned to the unassigned tasks. Returns the ids of the
+    /// re-added tasks
+    pub async fn reconcile_unassigned(&self) -> Result<Vec<TaskId>> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::ReconcileUnassignedTasks,
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let resp = self.forwardable_raft.client_write(req).await?;
+        resp.outcome()
+    }
+
+    /// Rewrites the task to executor entries which drifted from the executor
+    /// task assignments. Returns the ids of the repaired tasks
+    pub async fn repair_task_executor_index(&self) -> Result<Vec<TaskId>> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::RepairTaskExecutorIndex,
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let resp = self.forwardable_raft.client_write(req).await?;
+        resp.outcome()
+    }
+
+    /// Resets the running task count of an executor to the number of tasks
+    /// assigned to it. The count is recomputed on every node
+    pub async fn recompute_executor_load(&self, executor_id: &str) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::RecomputeExecutorLoad {
+                executor_id: executor_id.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Moves unassigned tasks of an extractor to another extractor, for
+    /// extractors which are renamed or split. Fails without moving any task
+    /// when one of them is assigned or belongs to another extractor
+    pub async fn retarget_tasks(
+        &self,
+        from_extractor: &str,
+        to_extractor: &str,
+        task_ids: Vec<TaskId>,
+    ) -> Result<()> {
+        for task_id in &task_ids {
+            let task = self.task_with_id(task_id).await?;
+            if task.extractor != from_extractor {
+                return Err(anyhow!(
+                    "task {} belongs to extractor {}, not {}",
+                    task_id,
+                    task.extractor,
+                    from_extractor
+                ));
+            }
+            if let Some(executor_id) = self.state_machine.task_executor(task_id).await? {
+                return Err(anyhow!(
+                    "task {} is assigned to executor {}",
+                    task_id,
+                    executor_id
+                ));
+            }
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::RetargetTasks {
+                from_extractor: from_extractor.to_string(),
+                to_extractor: to_extractor.to_string(),
+                task_ids,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Renames an extractor along with its unfinished tasks. Fails without
+    /// renaming anything when one of the tasks is assigned
+    pub async fn rename_extractor(&self, from_extractor: &str, to_extractor: &str) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::UpdateExtractor {
+                from_extractor: from_extractor.to_string(),
+                to_extractor: to_extractor.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Moves the entire workload of an executor to another executor in a
+    /// single write, e.g. to drain a node onto a hot standby. Fails when the
+    /// other executor can't run all the extractors of the moved tasks
+    pub async fn swap_executor_workloads(&self, from: &str, to: &str) -> Result<()> {
+        if from == to {
+            return Err(anyhow!(
+                "can't move the workload of executor {} to itself",
+                from
+            ));
+        }
+        self.get_executor_by_id(from).await?;
+        let to_executor = self.get_executor_by_id(to).await?;
+        let supported_extractors: HashSet<String> = to_executor
+            .extractors
+            .into_iter()
+            .map(|extractor| extractor.name)
+            .collect();
+        for task in self.tasks_for_executor(from, None).await? {
+            if !supported_extractors.contains(&task.extractor) {
+                return Err(anyhow!(
+                    "executor {} can't run extractor {} of task {}",
+                    to,
+                    task.extractor,
+                    task.id
+                ));
+            }
+        }
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::SwapExecutorWorkloads {
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Applies the status report sent by an executor on every heartbeat in a
+    /// single write: its last seen time, the tasks it completed since the
+    /// previous heartbeat and its running task count
+    pub async fn executor_heartbeat(
+        &self,
+        executor_id: &str,
+        completed_task_updates: Vec<internal_api::Task>,
+    ) -> Result<()> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::ExecutorHeartbeat {
+                executor_id: executor_id.to_string(),
+                ts_secs: timestamp_secs(),
+                completed_task_updates,
+                update_time: SystemTime::now(),
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        Ok(())
+    }
+
+    /// Updates the last seen time of an executor and returns whether it had
+    /// expired, i.e. its previous heartbeat was more than `ttl_secs` ago or it
+    /// was removed, so that it can be admitted again from scratch
+    pub async fn heartbeat(&self, executor_id: &str, ttl_secs: u64) -> Result<HeartbeatOutcome> {
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::Heartbeat {
+                executor_id: executor_id.to_string(),
+                ts_secs: timestamp_secs(),
+                ttl_secs,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        self.state_machine
+            .get_heartbeat_outcome(executor_id)
+            .await?
+            .ok_or_else(|| anyhow!("heartbeat outcome of executor {} not found", executor_id))
+    }
+
+    /// Reserves a contiguous block of task ids that are never handed out
+    /// again, so clients can reference tasks before creating them
+    pub async fn reserve_task_ids(&self, count: u64) -> Result<Vec<TaskId>> {
+        let reservation_id = nanoid::nanoid!();
+        let req = StateMachineUpdateRequest {
+            payload: RequestPayload::ReserveTaskIds {
+                reservation_id: reservation_id.clone(),
+                count,
+            },
+            new_state_changes: vec![],
+            state_changes_processed: vec![],
+            correlation_id: None,
+        };
+        let _resp = self.forwardable_raft.client_write(req).await?;
+        let reservation = self
+            .state_machine
+            .get_task_id_reservation(&reservation_id)
+            .await?
+            .ok_or_else(|| anyhow!("task id reservation {} not found", reservation_id))?;
+        Ok(reservation.task_ids())
+    }
+
     pub async fn get_unfinished_tasks_by_extractor(
         &self,
     ) -> HashMap<store::ExtractorName, HashSet<TaskId>> {
@@ -1401,6 +2239,13 @@ impl App {
         }
     }
 
+    /// Subscribes to the state changes in the compressed form used to forward
+    /// them to remote subscribers. Local subscribers use the raw state change
+    /// watcher
+    pub fn subscribe_to_compressed_state_changes(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.state_machine.subscribe_to_compressed_state_changes()
+    }
+
     pub async fn subscribe_to_gc_task_events(
         &self,
     ) -> broadcast::Receiver<indexify_internal_api::GarbageCollectionTask> {
@@ -1481,6 +2326,7 @@ mod tests {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let read_back = |node: Arc<App>| async move {
             match node.get_index("id").await {
@@ -1507,6 +2353,7 @@ mod tests {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
 
         let read_back = |node: Arc<App>| async move {
@@ -1604,6 +2451,7 @@ mod tests {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
 
         let read_back = {
@@ -1626,6 +2474,7 @@ mod tests {
             payload: RequestPayload::AssignTask { assignments },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
 
         let read_back = |node: Arc<App>| async move {
@@ -1672,7 +2521,7 @@ mod tests {
             ..Default::default()
         };
         let addr = "addr";
-        node.register_executor(addr, executor_id, extractor.clone())
+        node.register_executor(addr, executor_id, vec![extractor.clone()])
             .await?;
 
         //  Set an extraction policy for the content that will force task creation
@@ -1717,6 +2566,7 @@ mod tests {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let read_back = {
             move |node: Arc<App>| async move {
@@ -1738,6 +2588,7 @@ mod tests {
             payload: RequestPayload::AssignTask { assignments },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         };
         let read_back = |node: Arc<App>| async move {
             match node.tasks_for_executor("executor_id", None).await {
@@ -1794,7 +2645,7 @@ mod tests {
             ..Default::default()
         };
         let addr = "addr";
-        node.register_executor(addr, executor_id, extractor.clone())
+        node.register_executor(addr, executor_id, vec![extractor.clone()])
             .await?;
 
         //  Read the executors from multiple functions
@@ -1902,7 +2753,7 @@ mod tests {
             ..Default::default()
         };
         let addr = "addr";
-        node.register_executor(addr, executor_id, extractor.clone())
+        node.register_executor(addr, executor_id, vec![extractor.clone()])
             .await?;
 
         //  Create the extraction policy under the namespace of the content
@@ -2022,7 +2873,7 @@ mod tests {
             ..Default::default()
         };
         let addr = "addr";
-        node.register_executor(addr, executor_id, extractor.clone())
+        node.register_executor(addr, executor_id, vec![extractor.clone()])
             .await?;
 
         //  Create the extraction policy under the namespace of the content
@@ -116,6 +116,7 @@ impl Network {
             },
             new_state_changes: vec![],
             state_changes_processed: vec![],
+            correlation_id: None,
         })?
         .into_request();
 
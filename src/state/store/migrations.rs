@@ -0,0 +1,310 @@
+use std::{collections::HashSet, sync::Arc};
+
+use indexify_internal_api::{ContentMetadata, StateChange};
+use rocksdb::OptimisticTransactionDB;
+
+use super::{
+    serializer::{JsonEncode, JsonEncoder},
+    state_machine_objects::{content_by_modified_time_key, numeric_key, StateChangeSequenceEntry},
+    StateMachineColumns,
+    StateMachineError,
+    TaskId,
+};
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// A migration rewrites the stored data of the state machine from one schema
+/// version to the next. It runs within a transaction that also bumps the
+/// stored schema version
+pub type Migration = fn(
+    &Arc<OptimisticTransactionDB>,
+    &rocksdb::Transaction<OptimisticTransactionDB>,
+) -> Result<(), StateMachineError>;
+
+/// The ordered list of migrations. The migration at index `i` upgrades the
+/// store from schema version `i` to `i + 1`, so the current schema version is
+/// the number of migrations
+pub const MIGRATIONS: &[Migration] = &[
+    backfill_task_to_executor,
+    backfill_state_change_sequence,
+    executor_extractor_lists,
+    backfill_content_by_modified_time,
+    numeric_coordinator_address_keys,
+];
+
+/// Populates the TaskToExecutor column family from the task assignments of
+/// every executor
+fn backfill_task_to_executor(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+) -> Result<(), StateMachineError> {
+    let iter = txn.iterator_cf(
+        StateMachineColumns::TaskAssignments.cf(db),
+        rocksdb::IteratorMode::Start,
+    );
+    for item in iter {
+        let (executor_id, task_ids) =
+            item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        let executor_id = String::from_utf8(executor_id.to_vec())
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        let task_ids: HashSet<TaskId> = JsonEncoder::decode(&task_ids)?;
+        for task_id in task_ids {
+            txn.put_cf(
+                StateMachineColumns::TaskToExecutor.cf(db),
+                task_id,
+                JsonEncoder::encode(&executor_id)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Assigns sequence numbers to the existing state changes in the order they
+/// were created
+fn backfill_state_change_sequence(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+) -> Result<(), StateMachineError> {
+    let mut state_changes = Vec::new();
+    for item in txn.iterator_cf(
+        StateMachineColumns::StateChanges.cf(db),
+        rocksdb::IteratorMode::Start,
+    ) {
+        let (_, state_change) =
+            item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        let state_change: StateChange = JsonEncoder::decode(&state_change)?;
+        state_changes.push(state_change);
+    }
+    state_changes.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    for (i, mut state_change) in state_changes.into_iter().enumerate() {
+        state_change.sequence = i as u64 + 1;
+        txn.put_cf(
+            StateMachineColumns::StateChanges.cf(db),
+            &state_change.id,
+            JsonEncoder::encode(&state_change)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        let entry = StateChangeSequenceEntry {
+            state_change_id: state_change.id.clone(),
+            processed_at: state_change.processed_at,
+        };
+        txn.put_cf(
+            StateMachineColumns::StateChangeSequence.cf(db),
+            numeric_key(state_change.sequence),
+            JsonEncoder::encode(&entry)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Replaces the single extractor of every executor with a list of extractors
+fn executor_extractor_lists(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+) -> Result<(), StateMachineError> {
+    let cf = StateMachineColumns::Executors.cf(db);
+    for item in txn.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+        let (executor_id, executor) =
+            item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        let mut executor: serde_json::Value = JsonEncoder::decode(&executor)?;
+        if let Some(executor) = executor.as_object_mut() {
+            if let Some(extractor) = executor.remove("extractor") {
+                executor.insert("extractors".to_string(), serde_json::json!([extractor]));
+            }
+        }
+        txn.put_cf(cf, executor_id, JsonEncoder::encode(&executor)?)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Sets the last modified time of the existing content to its creation time
+/// and indexes the content by it
+fn backfill_content_by_modified_time(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+) -> Result<(), StateMachineError> {
+    let cf = StateMachineColumns::ContentTable.cf(db);
+    for item in txn.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+        let (key, content) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        let mut content: ContentMetadata = JsonEncoder::decode(&content)?;
+        content.last_modified_at = content.created_at.max(0) as u64;
+        txn.put_cf(cf, key, JsonEncoder::encode(&content)?)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        txn.put_cf(
+            StateMachineColumns::ContentByModifiedTime.cf(db),
+            content_by_modified_time_key(&content),
+            JsonEncoder::encode(&content.id)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Rewrites the node id keys of the CoordinatorAddress column family from
+/// their decimal string form to the big endian form, so the keys sort by node
+/// id
+fn numeric_coordinator_address_keys(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+) -> Result<(), StateMachineError> {
+    let cf = StateMachineColumns::CoordinatorAddress.cf(db);
+    //  The keys are read first since the transaction's iterator sees its own writes
+    let mut addrs = Vec::new();
+    for item in txn.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+        let (key, addr) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        addrs.push((key, addr));
+    }
+    for (key, addr) in addrs {
+        let node_id = std::str::from_utf8(&key)
+            .ok()
+            .and_then(|key| key.parse::<u64>().ok())
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!(
+                    "invalid coordinator address key {}",
+                    String::from_utf8_lossy(&key)
+                ))
+            })?;
+        txn.delete_cf(cf, &key)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        txn.put_cf(cf, numeric_key(node_id), addr)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+pub fn get_schema_version(db: &Arc<OptimisticTransactionDB>) -> Result<u32, StateMachineError> {
+    let version = db
+        .get_cf(StateMachineColumns::StoreMeta.cf(db), SCHEMA_VERSION_KEY)
+        .map_err(|e| {
+            StateMachineError::DatabaseError(format!("Error reading schema version: {}", e))
+        })?;
+    match version {
+        Some(version) => JsonEncoder::decode(&version),
+        None => Ok(0),
+    }
+}
+
+fn set_schema_version(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+    version: u32,
+) -> Result<(), StateMachineError> {
+    txn.put_cf(
+        StateMachineColumns::StoreMeta.cf(db),
+        SCHEMA_VERSION_KEY,
+        JsonEncoder::encode(&version)?,
+    )
+    .map_err(|e| StateMachineError::DatabaseError(format!("Error writing schema version: {}", e)))
+}
+
+/// Applies the migrations from the stored schema version up to the current
+/// one. The stored version is bumped in the same transaction as each
+/// migration, so a failed migration is retried on the next open. Returns the
+/// schema version of the store after the migrations have run
+pub fn run_migrations(
+    db: &Arc<OptimisticTransactionDB>,
+    migrations: &[Migration],
+) -> Result<u32, StateMachineError> {
+    let mut version = get_schema_version(db)?;
+    for migration in migrations.iter().skip(version as usize) {
+        tracing::info!("migrating store from schema version {}", version);
+        let txn = db.transaction();
+        migration(db, &txn)?;
+        set_schema_version(db, &txn, version + 1)?;
+        txn.commit()
+            .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+        version += 1;
+    }
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::state_machine_utils::open_test_db;
+
+    fn uppercase_namespaces(
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+    ) -> Result<(), StateMachineError> {
+        let cf = StateMachineColumns::Namespaces.cf(db);
+        for item in txn.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let name: String = JsonEncoder::decode(&value)?;
+            txn.put_cf(cf, key, JsonEncoder::encode(&name.to_uppercase())?)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_migrations() {
+        let (_dir, db) = open_test_db();
+        db.put_cf(
+            StateMachineColumns::Namespaces.cf(&db),
+            "default",
+            JsonEncoder::encode(&"default".to_string()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(get_schema_version(&db).unwrap(), 0);
+
+        let migrations: &[Migration] = &[uppercase_namespaces];
+        assert_eq!(run_migrations(&db, migrations).unwrap(), 1);
+        assert_eq!(get_schema_version(&db).unwrap(), 1);
+        let name: String = JsonEncoder::decode(
+            &db.get_cf(StateMachineColumns::Namespaces.cf(&db), "default")
+                .unwrap()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(name, "DEFAULT");
+
+        //  A store at the current version is left untouched
+        db.put_cf(
+            StateMachineColumns::Namespaces.cf(&db),
+            "other",
+            JsonEncoder::encode(&"other".to_string()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(run_migrations(&db, migrations).unwrap(), 1);
+        let name: String = JsonEncoder::decode(
+            &db.get_cf(StateMachineColumns::Namespaces.cf(&db), "other")
+                .unwrap()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(name, "other");
+    }
+
+    #[test]
+    fn test_numeric_coordinator_address_keys() {
+        let (_dir, db) = open_test_db();
+        for node_id in 1..=100u64 {
+            db.put_cf(
+                StateMachineColumns::CoordinatorAddress.cf(&db),
+                node_id.to_string(),
+                JsonEncoder::encode(&format!("node_{}", node_id)).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let migrations: &[Migration] = &[numeric_coordinator_address_keys];
+        run_migrations(&db, migrations).unwrap();
+
+        let state = crate::state::store::state_machine_objects::IndexifyState::default();
+        let addrs = state.coordinator_addrs(&db).unwrap();
+        assert_eq!(
+            addrs,
+            (1..=100u64)
+                .map(|node_id| (node_id, format!("node_{}", node_id)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            state.get_coordinator_addr(10, &db).unwrap(),
+            Some("node_10".to_string())
+        );
+    }
+}
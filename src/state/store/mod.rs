@@ -5,13 +5,24 @@ use std::{
     io::{BufReader, Cursor, Read, Write},
     ops::RangeBounds,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::Duration,
 };
 
 use anyhow::Result;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use flate2::bufread::ZlibDecoder;
-use indexify_internal_api::{ContentMetadata, ExecutorMetadata, StateChange, StructuredDataSchema};
+use indexify_internal_api::{
+    ContentMetadata,
+    ExecutorMetadata,
+    StateChange,
+    StructuredDataSchema,
+    TaskOutcome,
+};
 use openraft::{
     storage::{LogFlushed, LogState, RaftLogStorage, RaftStateMachine, Snapshot},
     AnyError,
@@ -30,7 +41,15 @@ use openraft::{
     StoredMembership,
     Vote,
 };
-use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, Direction, OptimisticTransactionDB, Options};
+use rocksdb::{
+    ColumnFamily,
+    ColumnFamilyDescriptor,
+    CompactionDecision,
+    DBCompactionStyle,
+    Direction,
+    OptimisticTransactionDB,
+    Options,
+};
 use serde::{de::DeserializeOwned, Deserialize};
 use strum::{AsRefStr, IntoEnumIterator};
 use thiserror::Error;
@@ -41,11 +60,36 @@ type Node = BasicNode;
 
 use self::{
     requests::RequestPayload,
-    serializer::{JsonEncode, JsonEncoder},
-    state_machine_objects::{IndexifyState, IndexifyStateSnapshot},
+    serializer::{encode_compressed_state_change, JsonEncode, JsonEncoder},
+    state_machine_objects::{
+        merge_content_extraction_policy_mappings,
+        ArchivedTask,
+        ArtifactStore,
+        AssignmentDelta,
+        BackpressureStatus,
+        CfDiff,
+        CfSnapshot,
+        ContentExtractionPolicyMapping,
+        ContentSummary,
+        ExtractorStatus,
+        HealthSummary,
+        HeartbeatOutcome,
+        IndexifyState,
+        IndexifyStateSnapshot,
+        QueueDepthSink,
+        ScanOptions,
+        ScanResult,
+        TagMatch,
+        TaskDetail,
+        TaskIdReservation,
+        CONTENT_POLICY_MAPPING_MERGE_OPERATOR,
+    },
 };
 use super::{typ, NodeId, SnapshotData, TypeConfig};
-use crate::utils::OptionInspectNone;
+use crate::{
+    server_config::{CompactionStyle, StateStoreConfig},
+    utils::{timestamp_secs, OptionInspectNone},
+};
 
 pub type NamespaceName = String;
 pub type TaskId = String;
@@ -59,6 +103,7 @@ pub type ExtractorName = String;
 pub type ContentType = String;
 pub type SchemaId = String;
 
+pub mod migrations;
 pub mod requests;
 pub mod serializer;
 pub mod state_machine_objects;
@@ -76,6 +121,9 @@ pub enum StateMachineError {
 
     #[error("External error: {0}")]
     ExternalError(#[from] anyhow::Error),
+
+    #[error("State change cursor {0} expired")]
+    CursorExpired(StateChangeId),
 }
 
 #[derive(AsRefStr, strum::Display, strum::EnumIter)]
@@ -91,8 +139,23 @@ pub enum StateMachineColumns {
     Namespaces,                         //  Namespaces
     IndexTable,                         //  String -> Index
     StructuredDataSchemas,              //  SchemaId -> StructuredDataSchema
-    ExtractionPoliciesAppliedOnContent, //  ContentId -> Vec<ExtractionPolicyIds>
-    CoordinatorAddress,                 //  NodeId -> Coordinator address
+    ExtractionPoliciesAppliedOnContent, //  ContentId::Version -> ContentExtractionPolicyMapping
+    CoordinatorAddress,                 //  NodeId (big endian) -> Coordinator address
+    StoreMeta,                          //  String -> Store metadata such as the schema version
+    TaskToExecutor,                     //  TaskId -> ExecutorId
+    ActiveIndexGenerations,             //  IndexId -> Generation serving reads
+    PendingSchemaBackfills,             //  SchemaId -> SchemaTransform
+    StateChangeSequence,                //  Sequence -> StateChangeSequenceEntry
+    TaskIdReservations,                 //  ReservationId -> TaskIdReservation
+    TaskArtifacts,                      //  TaskId -> Artifact reference
+    ContentByModifiedTime,              //  Namespace::ModifiedAt::ContentId -> ContentId
+    FinishedTasksByTime,                //  FinishedAt::TaskId -> TaskId
+    ArchivedTasks,                      //  TaskId -> ArchivedTask
+    PausedNamespaces,                   //  Namespace -> Namespace
+    TaskCreationSequence,               //  Sequence -> TaskId
+    HeartbeatOutcomes,                  //  ExecutorId -> HeartbeatOutcome
+    ActiveNamespaceSchemas,             //  Namespace -> SchemaId
+    ContentByTag,                       //  Namespace::Tag::ContentId -> ContentId
 }
 
 impl StateMachineColumns {
@@ -124,12 +187,23 @@ pub struct StateMachineData {
     pub last_membership: RwLock<StoredMembership<NodeId, Node>>,
 
     /// State built from applying the raft log
-    pub indexify_state: IndexifyState,
+    pub indexify_state: Arc<IndexifyState>,
 
     pub metrics: Mutex<crate::state::store::state_machine_objects::Metrics>,
 
     state_change_tx: Arc<tokio::sync::watch::Sender<StateChange>>,
 
+    /// State changes in the compressed form for remote subscribers. They are
+    /// only encoded while there are subscribers
+    compressed_state_change_tx: broadcast::Sender<Vec<u8>>,
+
+    /// Number of compressed state changes the broadcast channel holds
+    compressed_state_change_capacity: usize,
+
+    /// Number of compressed state changes dropped before all the subscribers
+    /// received them
+    compressed_state_changes_dropped: AtomicU64,
+
     gc_tasks_tx: broadcast::Sender<indexify_internal_api::GarbageCollectionTask>,
 }
 
@@ -140,6 +214,10 @@ pub struct StateMachineStore {
 
     db: Arc<OptimisticTransactionDB>,
 
+    /// Database holding the state changes. It's the main database unless a
+    /// separate one is configured
+    state_changes_db: Arc<OptimisticTransactionDB>,
+
     pub state_change_rx: tokio::sync::watch::Receiver<StateChange>,
 
     snapshot_file_path: PathBuf,
@@ -148,21 +226,30 @@ pub struct StateMachineStore {
 impl StateMachineStore {
     async fn new(
         db: Arc<OptimisticTransactionDB>,
+        state_changes_db: Arc<OptimisticTransactionDB>,
         snapshot_file_path: PathBuf,
+        state_change_broadcast_capacity: usize,
     ) -> Result<StateMachineStore, StorageError<NodeId>> {
         let (tx, rx) = tokio::sync::watch::channel(StateChange::default());
         let (gc_tasks_tx, _) = broadcast::channel(100);
+        let (compressed_state_change_tx, _) = broadcast::channel(state_change_broadcast_capacity);
         let sm = Self {
             data: StateMachineData {
                 last_applied_log_id: RwLock::new(None),
                 last_membership: RwLock::new(StoredMembership::default()),
-                indexify_state: IndexifyState::default(),
+                indexify_state: Arc::new(IndexifyState::default()),
                 metrics: Mutex::new(crate::state::store::state_machine_objects::Metrics::default()),
                 state_change_tx: Arc::new(tx),
+                compressed_state_change_tx,
+                //  The broadcast channel rounds its capacity up to a power of two
+                compressed_state_change_capacity: state_change_broadcast_capacity
+                    .next_power_of_two(),
+                compressed_state_changes_dropped: AtomicU64::new(0),
                 gc_tasks_tx,
             },
             snapshot_idx: Mutex::new(0),
             db,
+            state_changes_db,
             state_change_rx: rx,
             snapshot_file_path,
         };
@@ -282,6 +369,48 @@ impl StateMachineStore {
         Ok(())
     }
 
+    /// Register to the state changes in the compressed form meant for remote
+    /// subscribers, see decode_compressed_state_change
+    pub fn subscribe_to_compressed_state_changes(&self) -> broadcast::Receiver<Vec<u8>> {
+        self.data.compressed_state_change_tx.subscribe()
+    }
+
+    /// Number of compressed state changes dropped before all the subscribers
+    /// received them, because a subscriber fell behind by more than the
+    /// capacity of the broadcast channel
+    pub fn compressed_state_changes_dropped(&self) -> u64 {
+        self.data
+            .compressed_state_changes_dropped
+            .load(Ordering::Relaxed)
+    }
+
+    /// Sends a state change to the local and the remote subscribers. A
+    /// compressed state change sent while the broadcast channel is full
+    /// evicts the oldest one, which some subscriber hasn't received yet
+    fn broadcast_state_change(&self, change_event: StateChange) {
+        let compressed_state_change_tx = &self.data.compressed_state_change_tx;
+        if compressed_state_change_tx.receiver_count() > 0 {
+            match encode_compressed_state_change(&change_event) {
+                Ok(compressed) => {
+                    if compressed_state_change_tx.len() >=
+                        self.data.compressed_state_change_capacity
+                    {
+                        self.data
+                            .compressed_state_changes_dropped
+                            .fetch_add(1, Ordering::Relaxed);
+                    }
+                    let _ = compressed_state_change_tx.send(compressed);
+                }
+                Err(err) => {
+                    tracing::error!("error compressing state change event: {}", err);
+                }
+            }
+        }
+        if let Err(err) = self.data.state_change_tx.send(change_event) {
+            tracing::error!("error sending state change event: {}", err);
+        }
+    }
+
     /// Register to task deletion events
     pub async fn subscribe_to_gc_task_events(
         &self,
@@ -289,6 +418,16 @@ impl StateMachineStore {
         self.data.gc_tasks_tx.subscribe()
     }
 
+    /// The database holding a column family
+    fn db_for(&self, column: &StateMachineColumns) -> &Arc<OptimisticTransactionDB> {
+        match column {
+            StateMachineColumns::StateChanges | StateMachineColumns::StateChangeSequence => {
+                &self.state_changes_db
+            }
+            _ => &self.db,
+        }
+    }
+
     //  START FORWARD INDEX READER METHODS INTERFACES
     pub fn get_latest_version_of_content(&self, content_id: &str) -> Result<Option<u64>> {
         let txn = self.db.transaction();
@@ -308,7 +447,8 @@ impl StateMachineStore {
         T: DeserializeOwned,
         K: AsRef<[u8]>,
     {
-        self.data.indexify_state.get_from_cf(&self.db, column, key)
+        let db = self.db_for(&column);
+        self.data.indexify_state.get_from_cf(db, column, key)
     }
 
     pub async fn get_tasks_for_executor(
@@ -322,6 +462,146 @@ impl StateMachineStore {
             .map_err(|e| anyhow::anyhow!("Failed to get tasks for executor: {}", e))
     }
 
+    pub async fn assigned_tasks_ordered(&self, executor_id: &str) -> Result<Vec<TaskId>> {
+        self.data
+            .indexify_state
+            .assigned_tasks_ordered(&self.db, executor_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get ordered tasks of executor: {}", e))
+    }
+
+    pub async fn list_tasks_by_outcome(
+        &self,
+        outcome: TaskOutcome,
+        limit: usize,
+    ) -> Result<Vec<indexify_internal_api::Task>> {
+        self.data
+            .indexify_state
+            .list_tasks_by_outcome(&self.db, outcome, limit)
+            .map_err(|e| anyhow::anyhow!("Failed to list tasks by outcome: {}", e))
+    }
+
+    pub async fn task_executor(&self, task_id: &str) -> Result<Option<ExecutorId>> {
+        self.data
+            .indexify_state
+            .task_executor(&self.db, task_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get executor of task {}: {}", task_id, e))
+    }
+
+    pub async fn fetch_task_artifact(
+        &self,
+        task_id: &str,
+        artifact_store: &impl ArtifactStore,
+    ) -> Result<Option<Vec<u8>>> {
+        self.data
+            .indexify_state
+            .fetch_task_artifact(&self.db, task_id, artifact_store)
+            .map_err(|e| anyhow::anyhow!("Failed to fetch artifact of task {}: {}", task_id, e))
+    }
+
+    pub async fn assigned_task_counts(&self) -> Result<HashMap<ExecutorId, usize>> {
+        self.data
+            .indexify_state
+            .assigned_task_counts(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to count assigned tasks: {}", e))
+    }
+
+    pub async fn running_task_count_drift(&self) -> Result<Vec<ExecutorId>> {
+        self.reverse_indexes()
+            .running_task_count_drift(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to check running task counts: {}", e))
+    }
+
+    pub async fn executor_weighted_loads(&self) -> HashMap<ExecutorId, u64> {
+        self.reverse_indexes().executor_weighted_loads()
+    }
+
+    pub async fn task_weights(&self, task_ids: &HashSet<TaskId>) -> Result<HashMap<TaskId, u64>> {
+        self.data
+            .indexify_state
+            .task_weights(&self.db, task_ids)
+            .map_err(|e| anyhow::anyhow!("Failed to read task weights: {}", e))
+    }
+
+    pub async fn verify_task_executor_index(&self) -> Result<Vec<TaskId>> {
+        self.data
+            .indexify_state
+            .verify_task_executor_index(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to verify task executor index: {}", e))
+    }
+
+    pub async fn content_for_policy(
+        &self,
+        policy_name: &str,
+        namespace: &str,
+    ) -> Result<Vec<ContentId>> {
+        self.reverse_indexes()
+            .content_for_policy(&self.db, policy_name, namespace)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to get content of extraction policy {}: {}",
+                    policy_name,
+                    e
+                )
+            })
+    }
+
+    pub async fn get_content_extraction_policy_mapping(
+        &self,
+        content_id: &indexify_internal_api::ContentMetadataId,
+    ) -> Result<Option<ContentExtractionPolicyMapping>> {
+        self.data
+            .indexify_state
+            .get_content_extraction_policy_mapping(&self.db, content_id)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to get policies applied on content {}: {}",
+                    content_id,
+                    e
+                )
+            })
+    }
+
+    pub async fn get_archived_task(&self, task_id: &str) -> Result<Option<ArchivedTask>> {
+        self.data
+            .indexify_state
+            .get_archived_task(&self.db, task_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get archived task {}: {}", task_id, e))
+    }
+
+    pub async fn executors_grouped_by_label(
+        &self,
+        extractor: &str,
+        label_key: &str,
+    ) -> Result<HashMap<String, Vec<ExecutorId>>> {
+        self.reverse_indexes()
+            .executors_grouped_by_label(&self.db, extractor, label_key)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to group executors of {} by {}: {}",
+                    extractor,
+                    label_key,
+                    e
+                )
+            })
+    }
+
+    pub async fn task_detail(&self, task_id: &str) -> Result<TaskDetail> {
+        self.data
+            .indexify_state
+            .task_detail(&self.db, task_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get detail of task {}: {}", task_id, e))
+    }
+
+    pub async fn plan_assignment_delta(
+        &self,
+        desired: &HashMap<TaskId, ExecutorId>,
+    ) -> Result<AssignmentDelta> {
+        self.data
+            .indexify_state
+            .plan_assignment_delta(&self.db, desired)
+            .map_err(|e| anyhow::anyhow!("Failed to plan assignment delta: {}", e))
+    }
+
     pub async fn get_all_task_assignments(&self) -> Result<HashMap<TaskId, ExecutorId>> {
         self.data
             .indexify_state
@@ -329,6 +609,321 @@ impl StateMachineStore {
             .map_err(|e| anyhow::anyhow!("Failed to get task assignments: {}", e))
     }
 
+    pub async fn stale_executors_with_tasks(
+        &self,
+        now_secs: u64,
+        ttl_secs: u64,
+    ) -> Result<Vec<(ExecutorId, usize)>> {
+        self.data
+            .indexify_state
+            .stale_executors_with_tasks(&self.db, now_secs, ttl_secs)
+            .map_err(|e| anyhow::anyhow!("Failed to get stale executors: {}", e))
+    }
+
+    pub async fn unmatched_content(&self, namespace: &str) -> Result<Vec<ContentId>> {
+        self.data
+            .indexify_state
+            .unmatched_content(&self.db, namespace)
+            .map_err(|e| anyhow::anyhow!("Failed to get unmatched content: {}", e))
+    }
+
+    pub async fn tasks_in_deleted_namespaces(&self) -> Result<Vec<TaskId>> {
+        self.reverse_indexes()
+            .tasks_in_deleted_namespaces(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to get tasks in deleted namespaces: {}", e))
+    }
+
+    pub async fn namespace_extraction_progress(&self, namespace: &str) -> Result<f64> {
+        self.reverse_indexes()
+            .namespace_extraction_progress(&self.db, namespace)
+            .map_err(|e| anyhow::anyhow!("Failed to get extraction progress: {}", e))
+    }
+
+    pub async fn iter_state_changes(
+        &self,
+        start_after: Option<StateChangeId>,
+        limit: usize,
+    ) -> Result<Vec<StateChange>> {
+        self.data
+            .indexify_state
+            .iter_state_changes(&self.state_changes_db, start_after, limit)
+            .map_err(|e| anyhow::Error::new(e).context("Failed to iterate state changes"))
+    }
+
+    pub async fn content_timeline(
+        &self,
+        content_id: &str,
+        namespace: Option<&str>,
+    ) -> Result<Vec<StateChange>> {
+        self.reverse_indexes()
+            .content_timeline(&self.state_changes_db, content_id, namespace)
+            .map_err(|e| anyhow::anyhow!("Failed to get timeline of content {}: {}", content_id, e))
+    }
+
+    pub async fn validate_state_change_references(&self) -> Result<Vec<(StateChangeId, String)>> {
+        self.reverse_indexes()
+            .validate_state_change_references(&self.db, &self.state_changes_db)
+            .map_err(|e| anyhow::anyhow!("Failed to validate state change references: {}", e))
+    }
+
+    pub async fn list_content_summaries(&self, namespace: &str) -> Result<Vec<ContentSummary>> {
+        self.data
+            .indexify_state
+            .list_content_summaries(&self.db, namespace)
+            .map_err(|e| anyhow::anyhow!("Failed to list content summaries: {}", e))
+    }
+
+    pub async fn executor_running_task_count_verified(
+        &self,
+        executor_id: &ExecutorId,
+    ) -> Result<usize> {
+        self.reverse_indexes()
+            .executor_running_task_count_verified(&self.db, executor_id)
+            .map_err(|e| anyhow::anyhow!("Failed to verify running task count: {}", e))
+    }
+
+    pub async fn state_change_counts(&self) -> (usize, usize) {
+        self.reverse_indexes().state_change_counts()
+    }
+
+    pub async fn health_summary(&self) -> Result<HealthSummary> {
+        self.reverse_indexes()
+            .health_summary(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to get health summary: {}", e))
+    }
+
+    pub async fn health_summary_with_paused(
+        &self,
+        paused_extractors: &HashSet<String>,
+    ) -> Result<HealthSummary> {
+        self.reverse_indexes()
+            .health_summary_with_paused(&self.db, paused_extractors)
+            .map_err(|e| anyhow::anyhow!("Failed to get health summary: {}", e))
+    }
+
+    pub async fn extractors_not_served_by(&self, executor_id: &str) -> Result<Vec<ExtractorName>> {
+        self.data
+            .indexify_state
+            .extractors_not_served_by(&self.db, executor_id)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to get extractors not served by executor {}: {}",
+                    executor_id,
+                    e
+                )
+            })
+    }
+
+    pub async fn extractor_status(
+        &self,
+        extractor: &str,
+        paused_extractors: &HashSet<String>,
+    ) -> Result<ExtractorStatus> {
+        self.reverse_indexes()
+            .extractor_status(&self.db, extractor, paused_extractors)
+            .map_err(|e| anyhow::anyhow!("Failed to get status of extractor {}: {}", extractor, e))
+    }
+
+    pub async fn get_task_id_reservation(
+        &self,
+        reservation_id: &str,
+    ) -> Result<Option<TaskIdReservation>> {
+        self.data
+            .indexify_state
+            .get_task_id_reservation(&self.db, reservation_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get task id reservation: {}", e))
+    }
+
+    pub async fn get_heartbeat_outcome(
+        &self,
+        executor_id: &str,
+    ) -> Result<Option<HeartbeatOutcome>> {
+        self.data
+            .indexify_state
+            .get_heartbeat_outcome(&self.db, executor_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get heartbeat outcome: {}", e))
+    }
+
+    pub async fn content_exists(
+        &self,
+        content_id: &indexify_internal_api::ContentMetadataId,
+    ) -> Result<bool> {
+        self.data
+            .indexify_state
+            .content_exists(&self.db, content_id)
+            .map_err(|e| anyhow::anyhow!("Failed to check content exists: {}", e))
+    }
+
+    pub fn stream_all_content_ids(&self) -> impl Iterator<Item = Result<ContentId>> + '_ {
+        self.data
+            .indexify_state
+            .stream_all_content_ids(&self.db)
+            .map(|content_id| {
+                content_id.map_err(|e| anyhow::anyhow!("Failed to stream content ids: {}", e))
+            })
+    }
+
+    pub async fn content_modified_since(
+        &self,
+        namespace: &str,
+        since_secs: u64,
+    ) -> Result<Vec<indexify_internal_api::ContentMetadataId>> {
+        self.data
+            .indexify_state
+            .content_modified_since(&self.db, namespace, since_secs)
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to get content modified since {}: {}", since_secs, e)
+            })
+    }
+
+    pub async fn list_content_by_tags(
+        &self,
+        namespace: &str,
+        tags: &[String],
+        mode: TagMatch,
+    ) -> Result<Vec<ContentId>> {
+        self.data
+            .indexify_state
+            .list_content_by_tags(&self.db, namespace, tags, mode)
+            .map_err(|e| anyhow::anyhow!("Failed to list content by tags {:?}: {}", tags, e))
+    }
+
+    pub async fn tasks_in_creation_order(
+        &self,
+        extractor: Option<ExtractorName>,
+    ) -> Result<Vec<TaskId>> {
+        self.data
+            .indexify_state
+            .tasks_in_creation_order(&self.db, extractor)
+            .map_err(|e| anyhow::anyhow!("Failed to get tasks in creation order: {}", e))
+    }
+
+    pub async fn pending_work_for_new_executor(
+        &self,
+        extractor: &str,
+        max: usize,
+        paused_extractors: &HashSet<ExtractorName>,
+    ) -> Result<Vec<TaskId>> {
+        self.reverse_indexes()
+            .pending_work_for_new_executor(&self.db, extractor, max, paused_extractors)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to get pending work for extractor {}: {}",
+                    extractor,
+                    e
+                )
+            })
+    }
+
+    pub async fn assignable_tasks(&self) -> Result<HashSet<TaskId>> {
+        self.reverse_indexes()
+            .assignable_tasks(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to get assignable tasks: {}", e))
+    }
+
+    pub async fn paused_namespaces(&self) -> Result<HashSet<NamespaceName>> {
+        self.data
+            .indexify_state
+            .paused_namespaces(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to get paused namespaces: {}", e))
+    }
+
+    pub async fn state_fingerprint(&self) -> Result<u64> {
+        self.data
+            .indexify_state
+            .state_fingerprint(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to compute state fingerprint: {}", e))
+    }
+
+    pub async fn orphaned_schemas(&self, namespace: &str) -> Result<Vec<SchemaId>> {
+        self.reverse_indexes()
+            .orphaned_schemas(&self.db, namespace)
+            .map_err(|e| anyhow::anyhow!("Failed to get orphaned schemas of {}: {}", namespace, e))
+    }
+
+    pub async fn policies_for_schema(
+        &self,
+        schema_id: &str,
+    ) -> Result<Vec<indexify_internal_api::ExtractionPolicy>> {
+        self.reverse_indexes()
+            .policies_for_schema(&self.db, schema_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get policies of schema {}: {}", schema_id, e))
+    }
+
+    pub async fn list_chunks(
+        &self,
+        source_content_id: &indexify_internal_api::ContentMetadataId,
+    ) -> Result<Vec<ContentMetadata>> {
+        self.reverse_indexes()
+            .list_chunks(&self.db, source_content_id)
+            .map_err(|e| anyhow::anyhow!("Failed to list chunks: {}", e))
+    }
+
+    pub async fn policy_for_content(
+        &self,
+        content_id: &indexify_internal_api::ContentMetadataId,
+    ) -> Result<Option<String>> {
+        self.data
+            .indexify_state
+            .policy_for_content(&self.db, content_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get policy for content: {}", e))
+    }
+
+    pub async fn content_produced_by_policy(
+        &self,
+        policy_id: &str,
+    ) -> Result<Vec<indexify_internal_api::ContentMetadataId>> {
+        self.data
+            .indexify_state
+            .content_produced_by_policy(&self.db, policy_id)
+            .map_err(|e| anyhow::anyhow!("Failed to get content produced by policy: {}", e))
+    }
+
+    pub async fn distinct_task_extractors(&self) -> Result<HashSet<ExtractorName>> {
+        self.reverse_indexes()
+            .distinct_task_extractors(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to get distinct task extractors: {}", e))
+    }
+
+    pub async fn sla_breaches(&self, now_secs: u64) -> Result<Vec<(ExtractorName, Vec<TaskId>)>> {
+        self.reverse_indexes()
+            .sla_breaches(&self.db, now_secs)
+            .map_err(|e| anyhow::anyhow!("Failed to get sla breaches: {}", e))
+    }
+
+    pub async fn namespace_disk_usage(&self, namespace: &str) -> Result<u64> {
+        self.data
+            .indexify_state
+            .namespace_disk_usage(&self.db, namespace)
+            .map_err(|e| anyhow::anyhow!("Failed to get namespace disk usage: {}", e))
+    }
+
+    pub async fn capture_cf_snapshot(&self, column: StateMachineColumns) -> CfSnapshot {
+        self.data
+            .indexify_state
+            .capture_cf_snapshot(self.db_for(&column), column)
+    }
+
+    pub async fn diff_cf(
+        &self,
+        column: StateMachineColumns,
+        snapshot: &CfSnapshot,
+    ) -> Result<CfDiff> {
+        self.data
+            .indexify_state
+            .diff_cf(self.db_for(&column), column, snapshot)
+            .map_err(|e| anyhow::anyhow!("Failed to diff column family: {}", e))
+    }
+
+    pub async fn matching_policies_for_content(
+        &self,
+        content: &ContentMetadata,
+    ) -> Result<Vec<indexify_internal_api::ExtractionPolicy>> {
+        self.reverse_indexes()
+            .matching_policies_for_content(&self.db, content)
+            .map_err(|e| anyhow::anyhow!("Failed to match policies for content: {}", e))
+    }
+
     pub async fn get_indexes_from_ids(
         &self,
         task_ids: HashSet<String>,
@@ -339,6 +934,24 @@ impl StateMachineStore {
             .map_err(|e| anyhow::anyhow!(e))
     }
 
+    pub async fn index_generation_exists(&self, index_id: &str, generation: u64) -> Result<bool> {
+        self.data
+            .indexify_state
+            .index_generation_exists(&self.db, index_id, generation)
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    pub async fn validate_index_schema(
+        &self,
+        index: &indexify_internal_api::Index,
+        schema: &indexify_internal_api::StructuredDataSchema,
+    ) -> Result<()> {
+        self.data
+            .indexify_state
+            .validate_index_schema(&self.db, index, schema)
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
     pub async fn get_extraction_policies_from_ids(
         &self,
         extraction_policy_ids: HashSet<String>,
@@ -413,6 +1026,13 @@ impl StateMachineStore {
             .get_coordinator_addr(node_id, &self.db)
     }
 
+    pub async fn coordinator_addrs(&self) -> Result<Vec<(NodeId, String)>> {
+        self.data
+            .indexify_state
+            .coordinator_addrs(&self.db)
+            .map_err(|e| anyhow::anyhow!("Failed to get coordinator addresses: {}", e))
+    }
+
     /// Test utility method to get all key-value pairs from a column family
     pub async fn get_all_rows_from_cf<V>(
         &self,
@@ -421,62 +1041,132 @@ impl StateMachineStore {
     where
         V: DeserializeOwned,
     {
+        let db = self.db_for(&column);
+        self.data.indexify_state.get_all_rows_from_cf(column, db)
+    }
+
+    pub async fn get_all_rows_from_cf_with_options<V>(
+        &self,
+        column: StateMachineColumns,
+        options: &ScanOptions,
+    ) -> Result<Vec<(String, V)>, anyhow::Error>
+    where
+        V: DeserializeOwned,
+    {
+        let db = self.db_for(&column);
+        self.data
+            .indexify_state
+            .get_all_rows_from_cf_with_options(column, db, options)
+    }
+
+    pub async fn get_all_rows_from_cf_skip_corrupt<V>(
+        &self,
+        column: StateMachineColumns,
+    ) -> Result<ScanResult<V>, anyhow::Error>
+    where
+        V: DeserializeOwned,
+    {
+        let db = self.db_for(&column);
         self.data
             .indexify_state
-            .get_all_rows_from_cf(column, &self.db)
+            .get_all_rows_from_cf_skip_corrupt(column, db)
     }
 
     //  END FORWARD INDEX READER METHOD INTERFACES
 
     //  START REVERSE INDEX READER METHOD INTERFACES
+    /// Returns the state once the reverse index updates of the writes applied
+    /// so far are visible
+    fn reverse_indexes(&self) -> &IndexifyState {
+        self.data.indexify_state.reverse_index_barrier();
+        &self.data.indexify_state
+    }
+
     pub async fn get_unassigned_tasks(&self) -> HashSet<TaskId> {
-        self.data.indexify_state.get_unassigned_tasks()
+        self.reverse_indexes().get_unassigned_tasks()
+    }
+
+    pub async fn tasks_blocked_by_capacity(
+        &self,
+        executor_caps: &HashMap<ExecutorId, usize>,
+    ) -> HashMap<ExtractorName, HashSet<TaskId>> {
+        self.reverse_indexes()
+            .tasks_blocked_by_capacity(executor_caps)
     }
 
     pub async fn get_unprocessed_state_changes(&self) -> HashSet<StateChangeId> {
-        self.data.indexify_state.get_unprocessed_state_changes()
+        self.reverse_indexes().get_unprocessed_state_changes()
     }
 
     pub async fn get_content_namespace_table(
         &self,
     ) -> HashMap<NamespaceName, HashSet<indexify_internal_api::ContentMetadataId>> {
-        self.data.indexify_state.get_content_namespace_table()
+        self.reverse_indexes().get_content_namespace_table()
     }
 
     pub async fn get_extraction_policies_table(&self) -> HashMap<NamespaceName, HashSet<String>> {
-        self.data.indexify_state.get_extraction_policies_table()
+        self.reverse_indexes().get_extraction_policies_table()
     }
 
     pub async fn get_extractor_executors_table(
         &self,
     ) -> HashMap<ExtractorName, HashSet<ExecutorId>> {
-        self.data.indexify_state.get_extractor_executors_table()
+        self.reverse_indexes().get_extractor_executors_table()
     }
 
     pub async fn get_namespace_index_table(&self) -> HashMap<NamespaceName, HashSet<String>> {
-        self.data.indexify_state.get_namespace_index_table()
+        self.reverse_indexes().get_namespace_index_table()
     }
 
     pub async fn get_unfinished_tasks_by_extractor(
         &self,
     ) -> HashMap<ExtractorName, HashSet<TaskId>> {
-        self.data.indexify_state.get_unfinished_tasks_by_extractor()
+        self.reverse_indexes().get_unfinished_tasks_by_extractor()
     }
 
     pub async fn get_executor_running_task_count(&self) -> HashMap<ExecutorId, usize> {
-        self.data.indexify_state.get_executor_running_task_count()
+        self.reverse_indexes().get_executor_running_task_count()
+    }
+
+    /// Returns how long an executor has been without running tasks, if it
+    /// finished its last task
+    pub async fn idle_duration(&self, executor_id: &str, now_secs: u64) -> Option<u64> {
+        self.reverse_indexes().idle_duration(executor_id, now_secs)
+    }
+
+    pub async fn get_active_namespace_schema(
+        &self,
+        namespace: &str,
+    ) -> Result<Option<StructuredDataSchema>> {
+        self.data
+            .indexify_state
+            .get_active_namespace_schema(&self.db, namespace)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to get active schema of namespace {}: {}",
+                    namespace,
+                    e
+                )
+            })
     }
 
     pub async fn get_schemas_by_namespace(&self) -> HashMap<NamespaceName, HashSet<SchemaId>> {
-        self.data.indexify_state.get_schemas_by_namespace()
+        self.reverse_indexes().get_schemas_by_namespace()
+    }
+
+    pub fn emit_queue_depths(&self, sink: &impl QueueDepthSink) {
+        self.reverse_indexes().emit_queue_depths(sink)
+    }
+
+    pub fn write_backpressure(&self) -> BackpressureStatus {
+        self.data.indexify_state.write_backpressure(&self.db)
     }
 
     pub async fn are_content_tasks_completed(
         &self,
         content_id: &indexify_internal_api::ContentMetadataId,
     ) -> bool {
-        self.data
-            .indexify_state
+        self.reverse_indexes()
             .are_content_tasks_completed(content_id)
     }
 
@@ -484,8 +1174,7 @@ impl StateMachineStore {
         &self,
         content_id: &indexify_internal_api::ContentMetadataId,
     ) -> HashSet<indexify_internal_api::ContentMetadataId> {
-        self.data
-            .indexify_state
+        self.reverse_indexes()
             .content_children_table
             .get_children(content_id)
     }
@@ -581,18 +1270,22 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
                 let mut guard = self.data.last_applied_log_id.write().await;
                 *guard = Some(ent.log_id);
             }
-            let resp_value = None;
+            let mut resp_value = None;
             match ent.payload {
                 EntryPayload::Blank => {}
                 EntryPayload::Normal(req) => {
                     change_events.extend(req.new_state_changes.clone());
 
-                    if let Err(e) = self
+                    match self
                         .data
                         .indexify_state
-                        .apply_state_machine_updates(req.clone(), &self.db)
-                    {
-                        panic!("error applying state machine update: {}", e);
+                        .apply_state_machine_updates_with_state_changes_db(
+                            req.clone(),
+                            &self.db,
+                            &self.state_changes_db,
+                        ) {
+                        Ok(outcome) => resp_value = outcome,
+                        Err(e) => panic!("error applying state machine update: {}", e),
                     };
 
                     //  if the payload is a GC task, send it via channel
@@ -625,9 +1318,7 @@ impl RaftStateMachine<TypeConfig> for Arc<StateMachineStore> {
             replies.push(Response { value: resp_value });
         }
         for change_event in change_events {
-            if let Err(err) = self.data.state_change_tx.send(change_event) {
-                tracing::error!("error sending state change event: {}", err);
-            }
+            self.broadcast_state_change(change_event);
         }
         Ok(replies)
     }
@@ -908,16 +1599,64 @@ impl RaftLogStorage<TypeConfig> for LogStore {
     }
 }
 
-pub(crate) async fn new_storage<P: AsRef<Path>>(
-    db_path: P,
-    snapshot_path: P,
-) -> (LogStore, Arc<StateMachineStore>) {
-    let mut db_opts = Options::default();
-    db_opts.create_missing_column_families(true);
-    db_opts.create_if_missing(true);
+/// Options shared by all the column families. The write stall thresholds
+/// are left to the RocksDB defaults unless they are configured
+fn cf_options(config: &StateStoreConfig) -> Options {
+    let mut cf_opts = Options::default();
+    if let Some(trigger) = config.level0_slowdown_writes_trigger {
+        cf_opts.set_level_zero_slowdown_writes_trigger(trigger);
+    }
+    if let Some(trigger) = config.level0_stop_writes_trigger {
+        cf_opts.set_level_zero_stop_writes_trigger(trigger);
+    }
+    if let Some(limit) = config.soft_pending_compaction_bytes_limit {
+        cf_opts.set_soft_pending_compaction_bytes_limit(limit);
+    }
+    if let Some(limit) = config.hard_pending_compaction_bytes_limit {
+        cf_opts.set_hard_pending_compaction_bytes_limit(limit);
+    }
+    cf_opts
+}
+
+/// Options for the StateChanges column family. Processed state changes older
+/// than the retention are dropped when RocksDB compacts the column family
+fn state_changes_cf_options(config: &StateStoreConfig) -> Options {
+    let retention_secs = config.state_change_retention_secs;
+    let mut cf_opts = cf_options(config);
+    cf_opts.set_compaction_filter(
+        "processed_state_changes_filter",
+        move |_level: u32, _key: &[u8], value: &[u8]| {
+            let processed_at = JsonEncoder::decode::<StateChange>(value)
+                .ok()
+                .and_then(|state_change| state_change.processed_at);
+            match processed_at {
+                Some(processed_at)
+                    if processed_at.saturating_add(retention_secs) < timestamp_secs() =>
+                {
+                    CompactionDecision::Remove
+                }
+                _ => CompactionDecision::Keep,
+            }
+        },
+    );
+    cf_opts
+}
 
-    let store = ColumnFamilyDescriptor::new("store", Options::default());
-    let logs = ColumnFamilyDescriptor::new("logs", Options::default());
+/// Options for the ContentTable column family. A bloom filter is added when
+/// configured, so that lookups of missing content mostly skip reading blocks
+fn content_table_cf_options(config: &StateStoreConfig) -> Options {
+    let mut cf_opts = cf_options(config);
+    if let Some(bits_per_key) = config.content_bloom_filter_bits_per_key {
+        let mut block_opts = rocksdb::BlockBasedOptions::default();
+        block_opts.set_bloom_filter(bits_per_key, false);
+        cf_opts.set_block_based_table_factory(&block_opts);
+    }
+    cf_opts
+}
+
+pub(crate) fn column_families(config: &StateStoreConfig) -> Vec<ColumnFamilyDescriptor> {
+    let store = ColumnFamilyDescriptor::new("store", cf_options(config));
+    let logs = ColumnFamilyDescriptor::new("logs", cf_options(config));
 
     //  Create the column families for the state machine columns
     let sm_columns: Vec<String> = StateMachineColumns::iter()
@@ -925,22 +1664,332 @@ pub(crate) async fn new_storage<P: AsRef<Path>>(
         .collect();
     let sm_column_families: Vec<ColumnFamilyDescriptor> = sm_columns
         .iter()
-        .map(|name| ColumnFamilyDescriptor::new(name, Options::default()))
+        .map(|name| {
+            let cf_opts = if name == StateMachineColumns::StateChanges.as_ref() {
+                state_changes_cf_options(config)
+            } else if name == StateMachineColumns::ContentTable.as_ref() {
+                content_table_cf_options(config)
+            } else if name == StateMachineColumns::ExtractionPoliciesAppliedOnContent.as_ref() {
+                let mut cf_opts = cf_options(config);
+                cf_opts.set_merge_operator_associative(
+                    CONTENT_POLICY_MAPPING_MERGE_OPERATOR,
+                    merge_content_extraction_policy_mappings,
+                );
+                cf_opts
+            } else {
+                cf_options(config)
+            };
+            ColumnFamilyDescriptor::new(name, with_compaction_style(cf_opts, config, name))
+        })
         .collect();
     let mut all_column_families = vec![store, logs];
     all_column_families.extend(sm_column_families);
+    all_column_families
+}
 
-    let db: OptimisticTransactionDB =
-        OptimisticTransactionDB::open_cf_descriptors(&db_opts, db_path, all_column_families)
-            .unwrap();
+/// The column families which may be given a TTL. They are append mostly
+/// time series, so dropping their oldest files doesn't leave dangling
+/// references in the other column families. State changes which aren't
+/// processed within the TTL are dropped as well
+const TTL_COLUMN_FAMILIES: &[StateMachineColumns] = &[
+    StateMachineColumns::StateChanges,
+    StateMachineColumns::ArchivedTasks,
+];
+
+/// Returns the compaction style of the column family. Column families with a
+/// TTL use FIFO compaction
+fn compaction_style(config: &StateStoreConfig, name: &str) -> CompactionStyle {
+    if config.cf_ttl_secs.contains_key(name) {
+        return CompactionStyle::Fifo;
+    }
+    config
+        .compaction_styles
+        .get(name)
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Sets the compaction style configured for the column family. With FIFO
+/// compaction, files are only dropped once they are older than the TTL of the
+/// column family and never because of their size
+fn with_compaction_style(mut cf_opts: Options, config: &StateStoreConfig, name: &str) -> Options {
+    cf_opts.set_compaction_style(match compaction_style(config, name) {
+        CompactionStyle::Level => DBCompactionStyle::Level,
+        CompactionStyle::Universal => DBCompactionStyle::Universal,
+        CompactionStyle::Fifo => DBCompactionStyle::Fifo,
+    });
+    if let Some(ttl_secs) = config.cf_ttl_secs.get(name) {
+        let mut fifo_opts = rocksdb::FifoCompactOptions::default();
+        fifo_opts.set_max_table_files_size(u64::MAX);
+        cf_opts.set_fifo_compaction_options(&fifo_opts);
+        cf_opts.set_ttl(*ttl_secs);
+    }
+    cf_opts
+}
+
+/// Name of the file in the database directory recording the compaction styles
+/// the database was created with
+const COMPACTION_STYLES_MARKER: &str = "COMPACTION_STYLES";
+
+/// Checks that the configured compaction styles of the column families match
+/// the ones the database was created with, since the files written with one
+/// style can't always be compacted with another. Databases created before the
+/// styles were recorded use level compaction. It also rejects TTLs on column
+/// families which don't support them
+fn check_compaction_styles(
+    db_path: &Path,
+    config: &StateStoreConfig,
+    column_families: &[String],
+) -> Result<()> {
+    for name in config.compaction_styles.keys() {
+        if !StateMachineColumns::iter().any(|cf| cf.as_ref() == name) {
+            return Err(anyhow::anyhow!(
+                "unknown column family {} in compaction styles",
+                name
+            ));
+        }
+    }
+    if let Some((name, _)) = config
+        .compaction_styles
+        .iter()
+        .find(|(_, style)| **style == CompactionStyle::Fifo)
+    {
+        return Err(anyhow::anyhow!(
+            "column family {} can't be configured with fifo compaction, give it a ttl instead",
+            name
+        ));
+    }
+    for name in config.cf_ttl_secs.keys() {
+        if !TTL_COLUMN_FAMILIES.iter().any(|cf| cf.as_ref() == name) {
+            return Err(anyhow::anyhow!(
+                "column family {} doesn't support a ttl",
+                name
+            ));
+        }
+    }
+    let marker = db_path.join(COMPACTION_STYLES_MARKER);
+    let previous_styles: HashMap<String, CompactionStyle> = if marker.exists() {
+        serde_json::from_slice(&fs::read(&marker)?)?
+    } else if db_path.join("CURRENT").exists() {
+        HashMap::new()
+    } else {
+        return Ok(());
+    };
+    for name in column_families {
+        let previous_style = previous_styles.get(name).copied().unwrap_or_default();
+        let style = compaction_style(config, name);
+        if previous_style != style {
+            return Err(anyhow::anyhow!(
+                "the compaction style of column family {} can't be changed from {:?} to {:?} on an existing store",
+                name,
+                previous_style,
+                style
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Records the compaction styles the database was opened with
+fn write_compaction_styles(
+    db_path: &Path,
+    config: &StateStoreConfig,
+    column_families: &[String],
+) -> Result<()> {
+    let styles: HashMap<&String, CompactionStyle> = column_families
+        .iter()
+        .map(|name| (name, compaction_style(config, name)))
+        .collect();
+    fs::write(
+        db_path.join(COMPACTION_STYLES_MARKER),
+        serde_json::to_vec(&styles)?,
+    )?;
+    Ok(())
+}
+
+/// Name of the file in the database directory recording the WAL directory the
+/// database was last opened with
+const WAL_DIR_MARKER: &str = "WAL_DIR";
+
+/// Opens the database with the WAL directory from the config. If the database
+/// was last opened with a different WAL directory, it is first opened with
+/// the previous one and flushed so that no writes only present in the
+/// previous WAL are lost
+fn open_db(db_path: &Path, config: &StateStoreConfig) -> Result<OptimisticTransactionDB> {
+    let sm_columns: Vec<String> = StateMachineColumns::iter()
+        .map(|cf| cf.to_string())
+        .collect();
+    check_compaction_styles(db_path, config, &sm_columns)?;
+    let wal_dir = config
+        .wal_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| db_path.to_path_buf());
+    let wal_dir_marker = db_path.join(WAL_DIR_MARKER);
+    let previous_wal_dir = if wal_dir_marker.exists() {
+        Some(PathBuf::from(fs::read_to_string(&wal_dir_marker)?))
+    } else if db_path.join("CURRENT").exists() {
+        Some(db_path.to_path_buf())
+    } else {
+        None
+    };
+
+    let mut db_opts = Options::default();
+    db_opts.create_missing_column_families(true);
+    db_opts.create_if_missing(true);
+    db_opts.set_manual_wal_flush(config.async_commits);
+
+    if let Some(previous_wal_dir) = previous_wal_dir.filter(|dir| *dir != wal_dir) {
+        tracing::info!(
+            "moving the WAL of {} from {} to {}",
+            db_path.display(),
+            previous_wal_dir.display(),
+            wal_dir.display()
+        );
+        db_opts.set_wal_dir(&previous_wal_dir);
+        let db = OptimisticTransactionDB::open_cf_descriptors(
+            &db_opts,
+            db_path,
+            column_families(config),
+        )?;
+        let cf_names = ["store".to_string(), "logs".to_string()]
+            .into_iter()
+            .chain(StateMachineColumns::iter().map(|cf| cf.to_string()));
+        for cf_name in cf_names {
+            let cf_handle = db
+                .cf_handle(&cf_name)
+                .ok_or_else(|| anyhow::anyhow!("missing column family {}", cf_name))?;
+            db.flush_cf(cf_handle)?;
+        }
+    }
+
+    db_opts.set_wal_dir(&wal_dir);
+    let db =
+        OptimisticTransactionDB::open_cf_descriptors(&db_opts, db_path, column_families(config))?;
+    fs::write(&wal_dir_marker, wal_dir.to_string_lossy().as_bytes())?;
+    write_compaction_styles(db_path, config, &sm_columns)?;
+    Ok(db)
+}
+
+/// Opens the separate database for the state changes. It only has the
+/// column families of the state changes
+fn open_state_changes_db(
+    db_path: &Path,
+    config: &StateStoreConfig,
+) -> Result<OptimisticTransactionDB> {
+    let state_changes_columns = vec![
+        StateMachineColumns::StateChanges.to_string(),
+        StateMachineColumns::StateChangeSequence.to_string(),
+        StateMachineColumns::StoreMeta.to_string(),
+    ];
+    check_compaction_styles(db_path, config, &state_changes_columns)?;
+    let mut db_opts = Options::default();
+    db_opts.create_missing_column_families(true);
+    db_opts.create_if_missing(true);
+    db_opts.set_manual_wal_flush(config.async_commits);
+    let column_families = vec![
+        ColumnFamilyDescriptor::new(
+            StateMachineColumns::StateChanges.as_ref(),
+            with_compaction_style(
+                state_changes_cf_options(config),
+                config,
+                StateMachineColumns::StateChanges.as_ref(),
+            ),
+        ),
+        ColumnFamilyDescriptor::new(
+            StateMachineColumns::StateChangeSequence.as_ref(),
+            with_compaction_style(
+                cf_options(config),
+                config,
+                StateMachineColumns::StateChangeSequence.as_ref(),
+            ),
+        ),
+        ColumnFamilyDescriptor::new(
+            StateMachineColumns::StoreMeta.as_ref(),
+            with_compaction_style(
+                cf_options(config),
+                config,
+                StateMachineColumns::StoreMeta.as_ref(),
+            ),
+        ),
+    ];
+    let db = OptimisticTransactionDB::open_cf_descriptors(&db_opts, db_path, column_families)?;
+    write_compaction_styles(db_path, config, &state_changes_columns)?;
+    Ok(db)
+}
+
+/// Spawns a task flushing and syncing the WAL of the databases on every
+/// interval. It stops once the databases are dropped
+fn spawn_wal_flusher(dbs: Vec<Arc<OptimisticTransactionDB>>, interval: Duration) {
+    let dbs: Vec<_> = dbs.iter().map(Arc::downgrade).collect();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            for db in &dbs {
+                let db = match db.upgrade() {
+                    Some(db) => db,
+                    None => return,
+                };
+                if let Err(e) = db.flush_wal(true) {
+                    tracing::error!("failed to flush the WAL: {}", e);
+                }
+            }
+        }
+    });
+}
+
+pub(crate) async fn new_storage<P: AsRef<Path>>(
+    db_path: P,
+    snapshot_path: P,
+    config: &StateStoreConfig,
+) -> (LogStore, Arc<StateMachineStore>) {
+    let db = open_db(db_path.as_ref(), config).unwrap();
 
     let db = Arc::new(db);
 
+    migrations::run_migrations(&db, migrations::MIGRATIONS).unwrap();
+
     let log_store = LogStore { db: db.clone() };
 
     let snapshot_path = PathBuf::from(snapshot_path.as_ref());
 
-    let sm_store = StateMachineStore::new(db, snapshot_path).await.unwrap();
+    let state_changes_db = match &config.state_changes_path {
+        Some(state_changes_path) => {
+            Arc::new(open_state_changes_db(Path::new(state_changes_path), config).unwrap())
+        }
+        None => db.clone(),
+    };
+
+    if let Some(interval_ms) = config.wal_flush_interval_ms {
+        let mut dbs = vec![db.clone()];
+        if !Arc::ptr_eq(&db, &state_changes_db) {
+            dbs.push(state_changes_db.clone());
+        }
+        spawn_wal_flusher(dbs, Duration::from_millis(interval_ms));
+    }
+
+    let sm_store = StateMachineStore::new(
+        db,
+        state_changes_db,
+        snapshot_path,
+        config.state_change_broadcast_capacity,
+    )
+    .await
+    .unwrap();
+    if config.background_reverse_index_updates {
+        sm_store.data.indexify_state.start_reverse_index_worker();
+    }
+    if config.log_reverse_index_mutations {
+        sm_store
+            .data
+            .indexify_state
+            .enable_reverse_index_mutation_logging();
+    }
+    sm_store
+        .data
+        .indexify_state
+        .set_state_change_retention(config.state_change_retention_secs);
 
     (log_store, Arc::new(sm_store))
 }
@@ -949,17 +1998,27 @@ pub(crate) async fn new_storage<P: AsRef<Path>>(
 mod tests {
     use std::time::Duration;
 
+    use indexify_internal_api::{self as internal_api, StateChange};
     use openraft::{raft::InstallSnapshotRequest, testing::log_id, SnapshotMeta, Vote};
 
     use crate::{
+        server_config::StateStoreConfig,
         state::{
             self,
             store::{
-                serializer::{JsonEncode, JsonEncoder},
-                state_machine_objects::IndexifyStateSnapshot,
+                requests::{RequestPayload, StateMachineUpdateRequest},
+                serializer::{
+                    decode_compressed_state_change,
+                    encode_compressed_state_change,
+                    JsonEncode,
+                    JsonEncoder,
+                },
+                state_machine_objects::{IndexifyState, IndexifyStateSnapshot},
+                StateMachineColumns,
             },
         },
         test_utils::RaftTestCluster,
+        utils::timestamp_secs,
     };
 
     /// This is a dummy test which forces building a snapshot on the cluster by
@@ -993,4 +2052,354 @@ mod tests {
             .await?;
         Ok(())
     }
+
+    #[test]
+    fn test_state_changes_compaction_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db_opts = rocksdb::Options::default();
+        db_opts.create_missing_column_families(true);
+        db_opts.create_if_missing(true);
+        let db = rocksdb::OptimisticTransactionDB::open_cf_descriptors(
+            &db_opts,
+            dir.path(),
+            vec![rocksdb::ColumnFamilyDescriptor::new(
+                StateMachineColumns::StateChanges.as_ref(),
+                super::state_changes_cf_options(&StateStoreConfig {
+                    state_change_retention_secs: 60,
+                    ..Default::default()
+                }),
+            )],
+        )
+        .unwrap();
+        let cf = db
+            .cf_handle(StateMachineColumns::StateChanges.as_ref())
+            .unwrap();
+
+        let now = timestamp_secs();
+        let state_changes = [
+            ("old_processed", Some(now - 120)),
+            ("recent_processed", Some(now)),
+            ("unprocessed", None),
+        ];
+        for (id, processed_at) in state_changes {
+            let state_change = StateChange {
+                id: id.to_string(),
+                processed_at,
+                ..Default::default()
+            };
+            db.put_cf(cf, id, JsonEncoder::encode(&state_change).unwrap())
+                .unwrap();
+        }
+        db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+
+        assert!(db.get_cf(cf, "old_processed").unwrap().is_none());
+        assert!(db.get_cf(cf, "recent_processed").unwrap().is_some());
+        assert!(db.get_cf(cf, "unprocessed").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_compressed_state_change_round_trip() {
+        let state_change = StateChange {
+            id: "change_id".to_string(),
+            object_id: "content_id".to_string(),
+            change_type: internal_api::ChangeType::TaskCompleted {
+                content_id: internal_api::ContentMetadataId::new("content_id"),
+            },
+            created_at: 10,
+            processed_at: Some(20),
+            sequence: 7,
+        };
+        let compressed = encode_compressed_state_change(&state_change).unwrap();
+        let decoded = decode_compressed_state_change(&compressed).unwrap();
+        assert_eq!(
+            JsonEncoder::encode(&decoded).unwrap(),
+            JsonEncoder::encode(&state_change).unwrap()
+        );
+        assert!(decode_compressed_state_change(b"not compressed").is_err());
+    }
+
+    #[test]
+    fn test_open_db_with_wal_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("db");
+        let mut config = StateStoreConfig::default();
+        let read_value = |db: &rocksdb::OptimisticTransactionDB, key: &str| {
+            db.get_cf(
+                db.cf_handle(StateMachineColumns::Namespaces.as_ref())
+                    .unwrap(),
+                key,
+            )
+            .unwrap()
+        };
+        let write_value = |db: &rocksdb::OptimisticTransactionDB, key: &str| {
+            db.put_cf(
+                db.cf_handle(StateMachineColumns::Namespaces.as_ref())
+                    .unwrap(),
+                key,
+                "value",
+            )
+            .unwrap()
+        };
+
+        //  Writes in the WAL of the database directory survive moving the WAL
+        let db = super::open_db(&db_path, &config).unwrap();
+        write_value(&db, "before_move");
+        drop(db);
+
+        config.wal_dir = Some(dir.path().join("wal").to_string_lossy().to_string());
+        let db = super::open_db(&db_path, &config).unwrap();
+        assert!(read_value(&db, "before_move").is_some());
+        write_value(&db, "after_move");
+        drop(db);
+        assert!(dir.path().join("wal").read_dir().unwrap().next().is_some());
+
+        let db = super::open_db(&db_path, &config).unwrap();
+        assert!(read_value(&db, "before_move").is_some());
+        assert!(read_value(&db, "after_move").is_some());
+    }
+
+    #[test]
+    fn test_separate_state_changes_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StateStoreConfig::default();
+        let db = std::sync::Arc::new(super::open_db(&dir.path().join("db"), &config).unwrap());
+        let state_changes_db = std::sync::Arc::new(
+            super::open_state_changes_db(&dir.path().join("state_changes"), &config).unwrap(),
+        );
+        let state = IndexifyState::default();
+        let state_change = StateChange::new(
+            "namespace".into(),
+            internal_api::ChangeType::NewContent,
+            timestamp_secs(),
+        );
+        state
+            .apply_state_machine_updates_with_state_changes_db(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateNamespace {
+                        name: "namespace".into(),
+                        structured_data_schema: internal_api::StructuredDataSchema::new(
+                            "ingestion",
+                            "namespace",
+                        ),
+                    },
+                    new_state_changes: vec![state_change.clone()],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+                &state_changes_db,
+            )
+            .unwrap();
+
+        let stored: Option<StateChange> = state
+            .get_from_cf(
+                &state_changes_db,
+                StateMachineColumns::StateChanges,
+                &state_change.id,
+            )
+            .unwrap();
+        assert_eq!(
+            stored.map(|change| change.id),
+            Some(state_change.id.clone())
+        );
+        let stored: Option<StateChange> = state
+            .get_from_cf(&db, StateMachineColumns::StateChanges, &state_change.id)
+            .unwrap();
+        assert!(stored.is_none());
+
+        //  Everything else is written to the main database
+        let namespace: Option<String> = state
+            .get_from_cf(&db, StateMachineColumns::Namespaces, "namespace")
+            .unwrap();
+        assert_eq!(namespace, Some("namespace".to_string()));
+    }
+
+    #[test]
+    fn test_content_exists_with_bloom_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StateStoreConfig {
+            content_bloom_filter_bits_per_key: Some(10.0),
+            ..Default::default()
+        };
+        let db = std::sync::Arc::new(super::open_db(dir.path(), &config).unwrap());
+        let state = IndexifyState::default();
+        let content = internal_api::ContentMetadata {
+            id: internal_api::ContentMetadataId::new("present"),
+            ..Default::default()
+        };
+        state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![content.clone()],
+                        expected_version: None,
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+        let absent = internal_api::ContentMetadataId::new("absent");
+        assert!(state.content_exists(&db, &content.id).unwrap());
+        assert!(!state.content_exists(&db, &absent).unwrap());
+
+        //  The bloom filter is only consulted once the content is in an SST file
+        db.flush_cf(StateMachineColumns::ContentTable.cf(&db))
+            .unwrap();
+        assert!(state.content_exists(&db, &content.id).unwrap());
+        assert!(!state.content_exists(&db, &absent).unwrap());
+    }
+
+    #[test]
+    fn test_async_commits_survive_reopen_after_flush() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StateStoreConfig {
+            async_commits: true,
+            ..Default::default()
+        };
+        let namespace = "namespace".to_string();
+        {
+            let db = std::sync::Arc::new(super::open_db(dir.path(), &config).unwrap());
+            IndexifyState::default()
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::CreateNamespace {
+                            name: namespace.clone(),
+                            structured_data_schema: internal_api::StructuredDataSchema::new(
+                                "ingestion",
+                                &namespace,
+                            ),
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+            db.flush_wal(true).unwrap();
+        }
+
+        let db = super::open_db(dir.path(), &config).unwrap();
+        assert!(db
+            .get_cf(StateMachineColumns::Namespaces.cf(&db), &namespace)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_archived_tasks_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StateStoreConfig {
+            cf_ttl_secs: std::collections::HashMap::from([(
+                StateMachineColumns::ArchivedTasks.to_string(),
+                1,
+            )]),
+            ..Default::default()
+        };
+        let db = super::open_db(dir.path(), &config).unwrap();
+        let cf = StateMachineColumns::ArchivedTasks.cf(&db);
+        db.put_cf(cf, "old_task", b"old").unwrap();
+        db.flush_cf(cf).unwrap();
+        std::thread::sleep(Duration::from_secs(3));
+        db.put_cf(cf, "new_task", b"new").unwrap();
+        db.flush_cf(cf).unwrap();
+        db.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+
+        assert!(db.get_cf(cf, "old_task").unwrap().is_none());
+        assert!(db.get_cf(cf, "new_task").unwrap().is_some());
+        drop(db);
+
+        //  Column families which aren't time series can't be given a TTL
+        let err = super::open_db(
+            tempfile::tempdir().unwrap().path(),
+            &StateStoreConfig {
+                cf_ttl_secs: std::collections::HashMap::from([(
+                    StateMachineColumns::Tasks.to_string(),
+                    1,
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("column family Tasks doesn't support a ttl"));
+    }
+
+    #[test]
+    fn test_universal_compaction_on_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StateStoreConfig {
+            compaction_styles: std::collections::HashMap::from([(
+                StateMachineColumns::Tasks.to_string(),
+                crate::server_config::CompactionStyle::Universal,
+            )]),
+            ..Default::default()
+        };
+        let task = internal_api::Task {
+            id: "task".into(),
+            extractor: "extractor".into(),
+            ..Default::default()
+        };
+        {
+            let db = std::sync::Arc::new(super::open_db(dir.path(), &config).unwrap());
+            IndexifyState::default()
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::CreateTasks {
+                            tasks: vec![task.clone()],
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+            db.flush_cf(StateMachineColumns::Tasks.cf(&db)).unwrap();
+        }
+
+        //  Changing the compaction style of an existing store is rejected
+        let err = super::open_db(dir.path(), &StateStoreConfig::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("compaction style of column family Tasks"));
+
+        let db = std::sync::Arc::new(super::open_db(dir.path(), &config).unwrap());
+        let stored: internal_api::Task = IndexifyState::default()
+            .get_from_cf(&db, StateMachineColumns::Tasks, "task")
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.id, task.id);
+        assert_eq!(stored.extractor, task.extractor);
+    }
+
+    #[tokio::test]
+    async fn test_state_change_broadcast_drops_are_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = StateStoreConfig {
+            state_change_broadcast_capacity: 2,
+            ..Default::default()
+        };
+        let (_log_store, sm_store) =
+            super::new_storage(dir.path().join("db"), dir.path().join("snapshot"), &config).await;
+        let mut rx = sm_store.subscribe_to_compressed_state_changes();
+
+        //  The subscriber doesn't receive, so every state change sent once the
+        // channel is full evicts one it hasn't received
+        for i in 0..5 {
+            sm_store.broadcast_state_change(StateChange {
+                id: i.to_string(),
+                ..Default::default()
+            });
+        }
+        assert_eq!(sm_store.compressed_state_changes_dropped(), 3);
+        assert!(matches!(
+            rx.recv().await,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(3))
+        ));
+    }
 }
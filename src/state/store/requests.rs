@@ -2,9 +2,9 @@ use std::{collections::HashMap, time::SystemTime};
 
 use indexify_internal_api as internal_api;
 use internal_api::StateChange;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use super::{ExecutorId, TaskId};
+use super::{ExecutorId, ExtractionPolicyId, ExtractorName, NamespaceName, SchemaId, TaskId};
 use crate::state::NodeId;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -12,6 +12,9 @@ pub struct StateMachineUpdateRequest {
     pub payload: RequestPayload,
     pub new_state_changes: Vec<StateChange>,
     pub state_changes_processed: Vec<StateChangeProcessed>,
+    /// Id of the upstream request, recorded on the tracing span of the update
+    #[serde(default)]
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -20,7 +23,7 @@ pub struct StateChangeProcessed {
     pub processed_at: u64,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, strum::IntoStaticStr)]
 pub enum RequestPayload {
     //  NOTE: This isn't strictly a state machine update. It's used to change cluster membership.
     JoinCluster {
@@ -31,22 +34,63 @@ pub enum RequestPayload {
     RegisterExecutor {
         addr: String,
         executor_id: String,
-        extractor: internal_api::ExtractorDescription,
+        #[serde(alias = "extractor", deserialize_with = "deserialize_extractors")]
+        extractors: Vec<internal_api::ExtractorDescription>,
+        ts_secs: u64,
+        #[serde(default)]
+        labels: HashMap<String, String>,
+    },
+    RegisterExecutorAndClaim {
+        addr: String,
+        executor_id: String,
+        #[serde(alias = "extractor", deserialize_with = "deserialize_extractors")]
+        extractors: Vec<internal_api::ExtractorDescription>,
         ts_secs: u64,
+        max: usize,
+        #[serde(default)]
+        labels: HashMap<String, String>,
     },
     RemoveExecutor {
         executor_id: String,
     },
+    /// Removes the executors which haven't sent a heartbeat within the TTL
+    /// and puts the tasks assigned to them back into the unassigned tasks
+    ReapStaleExecutors {
+        now_secs: u64,
+        ttl_secs: u64,
+    },
     CreateNamespace {
         name: String,
         structured_data_schema: internal_api::StructuredDataSchema,
     },
+    /// Stops the tasks of a namespace from being assigned. The pause is kept
+    /// per namespace, so extractors shared with other namespaces keep
+    /// serving them
+    PauseNamespace {
+        namespace: String,
+    },
+    UnpauseNamespace {
+        namespace: String,
+    },
     CreateTasks {
         tasks: Vec<internal_api::Task>,
     },
     AssignTask {
         assignments: HashMap<TaskId, ExecutorId>,
     },
+    /// Assigns a task to an executor only if the task isn't assigned yet.
+    /// The assignment is dropped when the task is already assigned.
+    AssignTaskTo {
+        task_id: TaskId,
+        executor_id: ExecutorId,
+    },
+    /// Records the time at which an executor started a task it is assigned.
+    /// The start is dropped when the task isn't assigned to the executor.
+    MarkTaskStarted {
+        task_id: TaskId,
+        executor_id: ExecutorId,
+        ts_secs: u64,
+    },
     CreateOrAssignGarbageCollectionTask {
         gc_tasks: Vec<internal_api::GarbageCollectionTask>,
     },
@@ -56,6 +100,10 @@ pub enum RequestPayload {
     },
     CreateContent {
         content_metadata: Vec<internal_api::ContentMetadata>,
+        /// When set, the write is only applied if the latest stored version of
+        /// every content equals this version and each content carries the next
+        /// version. Conflicting writes are dropped.
+        expected_version: Option<u64>,
     },
     UpdateContent {
         content_metadata: Vec<internal_api::ContentMetadata>,
@@ -74,18 +122,261 @@ pub enum RequestPayload {
         namespace: String,
         id: String,
     },
+    CreateIndexWithSchema {
+        index: internal_api::Index,
+        namespace: String,
+        id: String,
+        schema: internal_api::StructuredDataSchema,
+    },
+    /// Creates all the indexes of a namespace in one transaction. Index ids
+    /// must be unique within the batch
+    CreateIndexes {
+        indexes: Vec<(String, internal_api::Index)>,
+        namespace: String,
+    },
+    PromoteIndexGeneration {
+        index_id: String,
+        generation: u64,
+    },
     UpdateTask {
         task: internal_api::Task,
         executor_id: Option<String>,
         content_metadata: Vec<internal_api::ContentMetadata>,
         update_time: SystemTime,
+        /// Set when the executor acknowledges a cancellation of the task
+        #[serde(default)]
+        cancelled: bool,
+        /// Reference to the output of the task in an external artifact store
+        artifact_ref: Option<String>,
+    },
+    /// Finishes a batch of tasks reported by one executor in one transaction,
+    /// along with the content each of them produced
+    FinishTasks {
+        executor_id: ExecutorId,
+        finished: Vec<(
+            internal_api::Task,
+            Option<Vec<internal_api::ContentMetadata>>,
+        )>,
+        update_time: SystemTime,
+    },
+    RequestTaskCancel {
+        task_id: TaskId,
+    },
+    DeleteTasks {
+        task_ids: Vec<TaskId>,
+    },
+    /// Moves the tasks which finished at least `older_than_secs` before
+    /// `now_secs` to the archived tasks
+    ArchiveFinishedTasks {
+        older_than_secs: u64,
+        now_secs: u64,
+    },
+    CancelContentTasks {
+        content_id: internal_api::ContentMetadataId,
+    },
+    /// Cancels the unfinished tasks created for an extraction policy
+    CancelPolicyTasks {
+        extraction_policy_id: String,
+    },
+    ReplaceSchema {
+        old_id: SchemaId,
+        new_schema: internal_api::StructuredDataSchema,
+        transform: SchemaTransform,
+    },
+    /// Writes a new schema and makes it the active schema of the namespace.
+    /// The schema previously active stays readable under its own id
+    SetNamespaceSchema {
+        namespace: String,
+        schema: internal_api::StructuredDataSchema,
+    },
+    RunSchemaBackfill {
+        schema_id: SchemaId,
+    },
+    /// Creates the tasks applying an extraction policy to the content of its
+    /// namespace which the policy wasn't applied to yet
+    BackfillPolicy {
+        namespace: NamespaceName,
+        policy_id: ExtractionPolicyId,
+        created_at: u64,
+    },
+    /// Marks an extraction policy applied on a content and creates the tasks
+    /// of the policies downstream of it for the content the policy produced
+    MarkPolicyAppliedAndSpawn {
+        content_id: ContentMetadataId,
+        policy_id: ExtractionPolicyId,
+        created_at: u64,
+    },
+    CancelOrphanedTasks,
+    /// Rewrites the task to executor entries which drifted from the executor
+    /// task assignments
+    RepairTaskExecutorIndex,
+    /// Re-adds unfinished tasks that are neither assigned to an executor nor
+    /// queued as unassigned to the unassigned tasks
+    ReconcileUnassignedTasks,
+    ReserveTaskIds {
+        reservation_id: String,
+        count: u64,
+    },
+    RecomputeExecutorLoad {
+        executor_id: ExecutorId,
+    },
+    /// Moves unassigned tasks from one extractor to another. The request is
+    /// rejected if any of the tasks is assigned or belongs to another
+    /// extractor
+    RetargetTasks {
+        from_extractor: ExtractorName,
+        to_extractor: ExtractorName,
+        task_ids: Vec<TaskId>,
+    },
+    /// Renames an extractor. Its description and its unfinished tasks are
+    /// moved to the new name. The request is rejected if any of the tasks is
+    /// assigned
+    UpdateExtractor {
+        from_extractor: ExtractorName,
+        to_extractor: ExtractorName,
+    },
+    /// Moves all the tasks assigned to an executor to another executor,
+    /// merging them with the tasks already assigned to it
+    SwapExecutorWorkloads {
+        from: ExecutorId,
+        to: ExecutorId,
+    },
+    /// Updates the last seen time of the executor, finishes the tasks it
+    /// completed since its last heartbeat and recomputes its running task
+    /// count
+    ExecutorHeartbeat {
+        executor_id: ExecutorId,
+        ts_secs: u64,
+        completed_task_updates: Vec<internal_api::Task>,
+        update_time: SystemTime,
+    },
+    /// Updates the last seen time of the executor and records whether its
+    /// previous last seen time was more than `ttl_secs` before `ts_secs`, see
+    /// HeartbeatOutcome
+    Heartbeat {
+        executor_id: ExecutorId,
+        ts_secs: u64,
+        ttl_secs: u64,
     },
     MarkStateChangesProcessed {
         state_changes: Vec<StateChangeProcessed>,
     },
 }
 
+impl RequestPayload {
+    /// Name of the payload variant, used to name the tracing span of an update
+    pub fn kind(&self) -> &'static str {
+        self.into()
+    }
+}
+
+/// Transformation applied to the labels of the existing content of a schema
+/// when the schema is replaced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum SchemaTransform {
+    Identity,
+    RenameColumn { from: String, to: String },
+    DropColumn { name: String },
+}
+
+impl SchemaTransform {
+    pub fn apply(&self, labels: &mut HashMap<String, String>) {
+        match self {
+            SchemaTransform::Identity => {}
+            SchemaTransform::RenameColumn { from, to } => {
+                if let Some(value) = labels.remove(from) {
+                    labels.insert(to.clone(), value);
+                }
+            }
+            SchemaTransform::DropColumn { name } => {
+                labels.remove(name);
+            }
+        }
+    }
+}
+
+/// Deserializes the extractors of a registering executor, accepting the
+/// single extractor of requests written before an executor could register
+/// several of them
+fn deserialize_extractors<'de, D>(
+    deserializer: D,
+) -> Result<Vec<internal_api::ExtractorDescription>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Extractors {
+        Many(Vec<internal_api::ExtractorDescription>),
+        One(internal_api::ExtractorDescription),
+    }
+
+    Ok(match Extractors::deserialize(deserializer)? {
+        Extractors::Many(extractors) => extractors,
+        Extractors::One(extractor) => vec![extractor],
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StateMachineUpdateResponse {
     pub handled_by: NodeId,
+    /// JSON encoded outcome of the request, for requests the state machine
+    /// decides on, such as a compare and swap
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl StateMachineUpdateResponse {
+    /// Decodes the outcome the state machine returned for the request
+    pub fn outcome<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        let value = self
+            .value
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("state machine returned no outcome"))?;
+        serde_json::from_str(value)
+            .map_err(|e| anyhow::anyhow!("invalid state machine outcome: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_executor_with_single_extractor() {
+        let extractor = internal_api::ExtractorDescription {
+            name: "extractor".into(),
+            ..Default::default()
+        };
+        let request = serde_json::json!({
+            "RegisterExecutor": {
+                "addr": "addr",
+                "executor_id": "executor",
+                "extractor": extractor,
+                "ts_secs": 0,
+            }
+        });
+        match serde_json::from_value(request).unwrap() {
+            RequestPayload::RegisterExecutor { extractors, .. } => {
+                assert_eq!(extractors, vec![extractor.clone()]);
+            }
+            payload => panic!("unexpected payload {:?}", payload),
+        }
+
+        let request = serde_json::json!({
+            "RegisterExecutorAndClaim": {
+                "addr": "addr",
+                "executor_id": "executor",
+                "extractors": [extractor],
+                "ts_secs": 0,
+                "max": 1,
+            }
+        });
+        match serde_json::from_value(request).unwrap() {
+            RequestPayload::RegisterExecutorAndClaim { extractors, .. } => {
+                assert_eq!(extractors, vec![extractor]);
+            }
+            payload => panic!("unexpected payload {:?}", payload),
+        }
+    }
 }
@@ -1,3 +1,8 @@
+use std::io::{Read, Write};
+
+use anyhow::anyhow;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use indexify_internal_api::StateChange;
 use serde::de::DeserializeOwned;
 
 use super::StateMachineError;
@@ -18,3 +23,25 @@ impl JsonEncode for JsonEncoder {
         serde_json::from_slice(bytes).map_err(StateMachineError::SerializationError)
     }
 }
+
+/// Encodes a state change in the compact form broadcast to remote
+/// subscribers, its JSON compressed with zlib
+pub fn encode_compressed_state_change(change: &StateChange) -> Result<Vec<u8>, StateMachineError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&JsonEncoder::encode(change)?)
+        .map_err(|e| anyhow!("unable to compress state change {}: {}", change.id, e))?;
+    let compressed = encoder
+        .finish()
+        .map_err(|e| anyhow!("unable to compress state change {}: {}", change.id, e))?;
+    Ok(compressed)
+}
+
+/// Decodes a state change received in the compressed broadcast form
+pub fn decode_compressed_state_change(bytes: &[u8]) -> Result<StateChange, StateMachineError> {
+    let mut decompressed = Vec::new();
+    ZlibDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| anyhow!("unable to decompress state change: {}", e))?;
+    JsonEncoder::decode(&decompressed)
+}
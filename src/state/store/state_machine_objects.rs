@@ -1,8 +1,12 @@
 use core::fmt;
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    sync::{Arc, RwLock},
-    time::SystemTime,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        RwLock,
+    },
+    time::{Duration, SystemTime},
 };
 
 use anyhow::{anyhow, Result};
@@ -11,11 +15,14 @@ use internal_api::{ContentMetadataId, ExtractorDescription, StateChange, TaskOut
 use itertools::Itertools;
 use rocksdb::OptimisticTransactionDB;
 use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use strum::IntoEnumIterator;
 use tracing::{error, warn};
 
 use super::{
-    requests::{RequestPayload, StateChangeProcessed, StateMachineUpdateRequest},
+    requests::{RequestPayload, SchemaTransform, StateChangeProcessed, StateMachineUpdateRequest},
     serializer::JsonEncode,
+    ContentId,
     ExecutorId,
     ExtractionPolicyId,
     ExtractorName,
@@ -27,7 +34,10 @@ use super::{
     StateMachineError,
     TaskId,
 };
-use crate::state::NodeId;
+use crate::{
+    coordinator_filters::{matches_mime_type, validate_selector},
+    state::NodeId,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
 pub struct UnassignedTasks {
@@ -79,15 +89,21 @@ impl UnprocessedStateChanges {
         guard.insert(state_change_id);
     }
 
-    pub fn remove(&self, state_change_id: &StateChangeId) {
+    /// Removes the state change, returning whether it was unprocessed
+    pub fn remove(&self, state_change_id: &StateChangeId) -> bool {
         let mut guard = self.unprocessed_state_changes.write().unwrap();
-        guard.remove(state_change_id);
+        guard.remove(state_change_id)
     }
 
     pub fn inner(&self) -> HashSet<StateChangeId> {
         let guard = self.unprocessed_state_changes.read().unwrap();
         guard.clone()
     }
+
+    pub fn count(&self) -> usize {
+        let guard = self.unprocessed_state_changes.read().unwrap();
+        guard.len()
+    }
 }
 
 impl From<HashSet<StateChangeId>> for UnprocessedStateChanges {
@@ -277,6 +293,38 @@ impl UnfinishedTasksByExtractor {
         let guard = self.unfinished_tasks_by_extractor.read().unwrap();
         guard.values().map(|v| v.len()).sum()
     }
+
+    /// Moves tasks from one extractor to another under a single lock. The
+    /// extractor is removed once it's left without tasks
+    pub fn move_tasks(&self, from: &ExtractorName, to: &ExtractorName, task_ids: &[TaskId]) {
+        if task_ids.is_empty() || from == to {
+            return;
+        }
+        let mut guard = self.unfinished_tasks_by_extractor.write().unwrap();
+        if let Some(from_tasks) = guard.get_mut(from) {
+            for task_id in task_ids {
+                from_tasks.remove(task_id);
+            }
+            if from_tasks.is_empty() {
+                guard.remove(from);
+            }
+        }
+        guard
+            .entry(to.clone())
+            .or_default()
+            .extend(task_ids.iter().cloned());
+    }
+
+    /// Moves all the tasks of an extractor to another one under a single lock
+    pub fn rekey(&self, old: &ExtractorName, new: &ExtractorName) {
+        if old == new {
+            return;
+        }
+        let mut guard = self.unfinished_tasks_by_extractor.write().unwrap();
+        if let Some(tasks) = guard.remove(old) {
+            guard.entry(new.clone()).or_default().extend(tasks);
+        }
+    }
 }
 
 impl From<HashMap<ExtractorName, HashSet<TaskId>>> for UnfinishedTasksByExtractor {
@@ -356,6 +404,92 @@ impl From<HashMap<ExecutorId, usize>> for ExecutorRunningTaskCount {
     }
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+pub struct ExecutorIdleSince {
+    executor_idle_since: Arc<RwLock<HashMap<ExecutorId, u64>>>,
+}
+
+impl ExecutorIdleSince {
+    pub fn get(&self, executor_id: &str) -> Option<u64> {
+        let guard = self.executor_idle_since.read().unwrap();
+        guard.get(executor_id).copied()
+    }
+
+    pub fn insert(&self, executor_id: &ExecutorId, idle_since: u64) {
+        let mut guard = self.executor_idle_since.write().unwrap();
+        guard.insert(executor_id.clone(), idle_since);
+    }
+
+    pub fn remove(&self, executor_id: &ExecutorId) {
+        let mut guard = self.executor_idle_since.write().unwrap();
+        guard.remove(executor_id);
+    }
+
+    pub fn inner(&self) -> HashMap<ExecutorId, u64> {
+        let guard = self.executor_idle_since.read().unwrap();
+        guard.clone()
+    }
+}
+
+impl From<HashMap<ExecutorId, u64>> for ExecutorIdleSince {
+    fn from(executor_idle_since: HashMap<ExecutorId, u64>) -> Self {
+        let executor_idle_since = Arc::new(RwLock::new(executor_idle_since));
+        Self {
+            executor_idle_since,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+pub struct ExecutorWeightedLoad {
+    executor_weighted_load: Arc<RwLock<HashMap<ExecutorId, u64>>>,
+}
+
+impl ExecutorWeightedLoad {
+    pub fn get(&self, executor_id: &str) -> Option<u64> {
+        let guard = self.executor_weighted_load.read().unwrap();
+        guard.get(executor_id).copied()
+    }
+
+    pub fn insert(&self, executor_id: &ExecutorId, load: u64) {
+        let mut guard = self.executor_weighted_load.write().unwrap();
+        guard.insert(executor_id.clone(), load);
+    }
+
+    pub fn remove(&self, executor_id: &ExecutorId) {
+        let mut guard = self.executor_weighted_load.write().unwrap();
+        guard.remove(executor_id);
+    }
+
+    pub fn inner(&self) -> HashMap<ExecutorId, u64> {
+        let guard = self.executor_weighted_load.read().unwrap();
+        guard.clone()
+    }
+
+    pub fn add(&self, executor_id: &ExecutorId, weight: u64) {
+        let mut guard = self.executor_weighted_load.write().unwrap();
+        *guard.entry(executor_id.clone()).or_insert(0) += weight;
+    }
+
+    pub fn subtract(&self, executor_id: &ExecutorId, weight: u64) {
+        let mut guard = self.executor_weighted_load.write().unwrap();
+        let load = guard.entry(executor_id.clone()).or_insert(0);
+        if *load < weight {
+            warn!("Tried to decrement weighted load below 0. This is a bug because the state machine shouldn't allow it.");
+        }
+        *load = load.saturating_sub(weight);
+    }
+}
+
+impl From<HashMap<ExecutorId, u64>> for ExecutorWeightedLoad {
+    fn from(executor_weighted_load: HashMap<ExecutorId, u64>) -> Self {
+        let executor_weighted_load = Arc::new(RwLock::new(executor_weighted_load));
+        Self {
+            executor_weighted_load,
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
 pub struct SchemasByNamespace {
     schemas_by_namespace: Arc<RwLock<HashMap<NamespaceName, HashSet<SchemaId>>>>,
@@ -454,6 +588,56 @@ impl From<HashMap<ContentMetadataId, HashSet<ContentMetadataId>>> for ContentChi
     }
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+pub struct ContentChunksTable {
+    content_chunks_table: Arc<RwLock<HashMap<ContentMetadataId, BTreeMap<u32, ContentMetadataId>>>>,
+}
+
+impl ContentChunksTable {
+    pub fn insert(
+        &self,
+        source_id: &ContentMetadataId,
+        chunk_index: u32,
+        chunk_id: &ContentMetadataId,
+    ) {
+        let mut guard = self.content_chunks_table.write().unwrap();
+        guard
+            .entry(source_id.clone())
+            .or_default()
+            .insert(chunk_index, chunk_id.clone());
+    }
+
+    pub fn remove_all(&self, source_id: &ContentMetadataId) {
+        let mut guard = self.content_chunks_table.write().unwrap();
+        guard.remove(source_id);
+    }
+
+    /// Returns the chunks of the source content ordered by chunk index
+    pub fn get_chunks(&self, source_id: &ContentMetadataId) -> Vec<ContentMetadataId> {
+        let guard = self.content_chunks_table.read().unwrap();
+        guard
+            .get(source_id)
+            .map(|chunks| chunks.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn inner(&self) -> HashMap<ContentMetadataId, BTreeMap<u32, ContentMetadataId>> {
+        let guard = self.content_chunks_table.read().unwrap();
+        guard.clone()
+    }
+}
+
+impl From<HashMap<ContentMetadataId, BTreeMap<u32, ContentMetadataId>>> for ContentChunksTable {
+    fn from(
+        content_chunks_table: HashMap<ContentMetadataId, BTreeMap<u32, ContentMetadataId>>,
+    ) -> Self {
+        let content_chunks_table = Arc::new(RwLock::new(content_chunks_table));
+        Self {
+            content_chunks_table,
+        }
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
 pub struct PendingTasksForContent {
     pending_tasks_for_content:
@@ -500,6 +684,24 @@ impl PendingTasksForContent {
         guard.get(content_id).is_none()
     }
 
+    pub fn get(&self, content_id: &ContentMetadataId) -> HashSet<TaskId> {
+        let guard = self.pending_tasks_for_content.read().unwrap();
+        guard
+            .get(content_id)
+            .map(|policies_map| policies_map.values().flatten().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get_for_policy(&self, extraction_policy_id: &str) -> HashSet<TaskId> {
+        let guard = self.pending_tasks_for_content.read().unwrap();
+        guard
+            .values()
+            .filter_map(|policies_map| policies_map.get(extraction_policy_id))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
     pub fn inner(
         &self,
     ) -> HashMap<ContentMetadataId, HashMap<ExtractionPolicyId, HashSet<TaskId>>> {
@@ -524,6 +726,57 @@ impl From<HashMap<ContentMetadataId, HashMap<ExtractionPolicyId, HashSet<TaskId>
     }
 }
 
+/// Key of a generation of an index in the IndexTable. The first generation is
+/// stored under the index id so indexes created before generations existed
+/// keep resolving
+fn index_generation_key(index_id: &str, generation: u64) -> String {
+    if generation == 0 {
+        return index_id.to_string();
+    }
+    format!("{}::g{}", index_id, generation)
+}
+
+/// Entry of the StateChangeSequence column family. The processed time of the
+/// state change is copied into it, so that the entries past the retention can
+/// be pruned without reading the state changes
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct StateChangeSequenceEntry {
+    pub state_change_id: StateChangeId,
+    #[serde(default)]
+    pub processed_at: Option<u64>,
+}
+
+/// Returns the last sequence number of a column family keyed by big endian
+/// sequence numbers, such as StateChangeSequence, or 0 if it's empty
+fn last_sequence(
+    db: &Arc<OptimisticTransactionDB>,
+    txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+    column: StateMachineColumns,
+) -> Result<u64, StateMachineError> {
+    let mut iter = txn.iterator_cf(column.cf(db), rocksdb::IteratorMode::End);
+    match iter.next() {
+        Some(item) => {
+            let (key, _) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            decode_numeric_key(&key)
+        }
+        None => Ok(0),
+    }
+}
+
+/// Encodes a numeric id used as a key, such as a sequence number or a node
+/// id. The fixed width big endian encoding makes the lexicographic order of
+/// the keys match the numeric order of the ids
+pub fn numeric_key(id: u64) -> [u8; 8] {
+    id.to_be_bytes()
+}
+
+pub fn decode_numeric_key(key: &[u8]) -> Result<u64, StateMachineError> {
+    let bytes: [u8; 8] = key
+        .try_into()
+        .map_err(|_| StateMachineError::DatabaseError(format!("Invalid numeric key {:?}", key)))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
 pub struct Metrics {
     /// Number of tasks total
@@ -543,6 +796,10 @@ pub struct Metrics {
 
     /// Total number of bytes in extracted contents
     pub content_extracted_bytes: u64,
+
+    /// Number of state changes processed
+    #[serde(default)]
+    pub state_changes_processed: u64,
 }
 
 impl Metrics {
@@ -555,6 +812,381 @@ impl Metrics {
     }
 }
 
+/// Whether RocksDB is holding back writes because compaction can't keep up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackpressureStatus {
+    Normal,
+    /// Writes are being slowed down to the given rate in bytes per second
+    Delayed {
+        delayed_write_rate: u64,
+    },
+    Stopped,
+}
+
+/// The key of a content in the ContentByModifiedTime column family. The
+/// modified time is zero padded so the keys of a namespace sort by time
+pub fn content_by_modified_time_key(content: &internal_api::ContentMetadata) -> String {
+    format!(
+        "{}::{:020}::{}::v{}",
+        content.namespace, content.last_modified_at, content.id.id, content.id.version
+    )
+}
+
+/// The key of a tagged content in the ContentByTag column family
+pub fn content_by_tag_key(namespace: &str, tag: &str, content_id: &ContentMetadataId) -> String {
+    format!(
+        "{}::{}::{}::v{}",
+        namespace, tag, content_id.id, content_id.version
+    )
+}
+
+/// How the tags given to `list_content_by_tags` are matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMatch {
+    /// Content carrying every one of the tags
+    All,
+    /// Content carrying at least one of the tags
+    Any,
+}
+
+/// The first 8 bytes of a digest as a little endian integer
+fn digest_to_u64(digest: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Commits the state changes written to a separate database, once the rest
+/// of the update is committed
+fn commit_state_changes(
+    state_changes_txn: Option<rocksdb::Transaction<OptimisticTransactionDB>>,
+) -> Result<(), StateMachineError> {
+    if let Some(state_changes_txn) = state_changes_txn {
+        state_changes_txn
+            .commit()
+            .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Reverse index updates computed along with the forward index writes of a
+/// request. They're applied once the writes are committed, so that a failed
+/// commit leaves the reverse indexes untouched
+#[derive(Default)]
+struct PostCommitUpdates<'a> {
+    updates: Vec<Box<dyn FnOnce(&IndexifyState) + 'a>>,
+}
+
+impl<'a> PostCommitUpdates<'a> {
+    fn push(&mut self, update: impl FnOnce(&IndexifyState) + 'a) {
+        self.updates.push(Box::new(update));
+    }
+
+    fn apply(self, state: &IndexifyState) {
+        for update in self.updates {
+            update(state);
+        }
+    }
+}
+
+/// Encodes the outcome of a request returned to the writer in the raft
+/// response
+fn encode_outcome<T: serde::Serialize>(outcome: &T) -> Result<Option<String>, StateMachineError> {
+    Ok(Some(serde_json::to_string(outcome)?))
+}
+
+/// The key of a task in the FinishedTasksByTime column family. The finish
+/// time is zero padded so the keys sort by time
+fn finished_task_key(finished_at: u64, task_id: &str) -> String {
+    format!("{:020}::{}", finished_at, task_id)
+}
+
+/// The ids of the schemas an extraction policy reads and writes. The input
+/// schema is the one of the policy's content source and the output schema the
+/// one named after the policy
+fn policy_schema_ids(policy: &internal_api::ExtractionPolicy) -> [SchemaId; 2] {
+    [
+        internal_api::StructuredDataSchema::schema_id(&policy.namespace, &policy.content_source),
+        internal_api::StructuredDataSchema::schema_id(&policy.namespace, &policy.name),
+    ]
+}
+
+/// The key in StoreMeta of the next task id that can be reserved
+const NEXT_RESERVED_TASK_ID_KEY: &str = "next_reserved_task_id";
+
+/// The key in StoreMeta of the last sequence number given to a state change.
+/// Pruning can remove every entry of StateChangeSequence, so the sequence
+/// can't be derived from the surviving keys
+const LAST_STATE_CHANGE_SEQUENCE_KEY: &str = "last_state_change_sequence";
+
+/// The key in StoreMeta of the first sequence number still in
+/// StateChangeSequence. Everything before it has been pruned
+const FIRST_RETAINED_STATE_CHANGE_SEQUENCE_KEY: &str = "first_retained_state_change_sequence";
+
+/// Number of content read at a time when backfilling an extraction policy
+const BACKFILL_CHUNK_SIZE: usize = 1000;
+
+/// A contiguous block of task ids reserved by a client. Reserved ids are never
+/// handed out again, so they can be referenced before the tasks are created
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TaskIdReservation {
+    pub start: u64,
+    pub count: u64,
+}
+
+impl TaskIdReservation {
+    pub fn task_ids(&self) -> Vec<TaskId> {
+        (self.start..self.start + self.count)
+            .map(|id| format!("reserved_{}", id))
+            .collect()
+    }
+}
+
+/// Read options for large sequential scans of a column family
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Bytes read ahead of the iterator. RocksDB's default is used when unset
+    pub readahead_size: Option<usize>,
+    /// Iterates in total key order, ignoring any prefix extractor of the
+    /// column family
+    pub total_order_seek: bool,
+}
+
+impl ScanOptions {
+    fn read_options(&self) -> rocksdb::ReadOptions {
+        let mut read_opts = rocksdb::ReadOptions::default();
+        if let Some(readahead_size) = self.readahead_size {
+            read_opts.set_readahead_size(readahead_size);
+        }
+        read_opts.set_total_order_seek(self.total_order_seek);
+        read_opts
+    }
+}
+
+/// The rows of a column family scan along with the keys whose values could
+/// not be decoded and were skipped
+#[derive(Debug, Clone)]
+pub struct ScanResult<V> {
+    pub rows: Vec<(String, V)>,
+    pub corrupt_keys: Vec<String>,
+}
+
+/// The rows of a column family captured at a point in time
+#[derive(Debug, Clone, Default)]
+pub struct CfSnapshot {
+    rows: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+/// The keys of a column family that changed since a snapshot was captured.
+/// The keys are sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfDiff {
+    pub added: Vec<Vec<u8>>,
+    pub removed: Vec<Vec<u8>>,
+    pub modified: Vec<Vec<u8>>,
+}
+
+/// A snapshot of the work tracked by the state machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthSummary {
+    pub unprocessed_state_changes: usize,
+    pub unassigned_tasks: usize,
+    pub executors: usize,
+    pub running_tasks: usize,
+    /// The unfinished tasks split between paused and active extractors. Only
+    /// set when the summary is requested with the paused extractors
+    pub unfinished_tasks: Option<UnfinishedTaskCounts>,
+}
+
+/// The scheduler's view of an extractor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractorStatus {
+    pub extractor: ExtractorName,
+    /// The executors running the extractor and their running task count,
+    /// sorted by executor id
+    pub executor_loads: Vec<(ExecutorId, usize)>,
+    pub unassigned_tasks: usize,
+    pub unfinished_tasks: usize,
+    pub paused: bool,
+}
+
+/// Unfinished task counts split between the extractors which are paused and
+/// the ones which are not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UnfinishedTaskCounts {
+    pub active: usize,
+    pub paused: usize,
+}
+
+/// The changes needed to go from the current task assignments to a desired
+/// assignment plan
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssignmentDelta {
+    /// Unassigned tasks to assign to an executor
+    pub assigns: HashMap<TaskId, ExecutorId>,
+    /// Assigned tasks to move from their current executor to another one
+    pub reassigns: HashMap<TaskId, (ExecutorId, ExecutorId)>,
+    /// Assigned tasks which are not part of the plan, with their executor
+    pub unassigns: HashMap<TaskId, ExecutorId>,
+}
+
+/// The extraction policies applied on a content and the time each of them
+/// completed
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ContentExtractionPolicyMapping {
+    pub extraction_policy_names: HashSet<String>,
+    pub time_of_policy_completion: HashMap<String, u64>,
+}
+
+impl ContentExtractionPolicyMapping {
+    /// Merges another mapping into this one. The names are unioned and the
+    /// latest completion time of a policy is kept
+    fn merge(&mut self, other: ContentExtractionPolicyMapping) {
+        self.extraction_policy_names
+            .extend(other.extraction_policy_names);
+        for (policy_name, completion_time) in other.time_of_policy_completion {
+            let time = self
+                .time_of_policy_completion
+                .entry(policy_name)
+                .or_default();
+            *time = (*time).max(completion_time);
+        }
+    }
+}
+
+/// Name of the merge operator of the ExtractionPoliciesAppliedOnContent
+/// column family
+pub const CONTENT_POLICY_MAPPING_MERGE_OPERATOR: &str = "content_extraction_policy_mapping";
+
+/// Merge operator of the ExtractionPoliciesAppliedOnContent column family.
+/// Each operand is a ContentExtractionPolicyMapping merged into the stored one
+pub fn merge_content_extraction_policy_mappings(
+    key: &[u8],
+    existing_value: Option<&[u8]>,
+    operands: &rocksdb::MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut mapping = ContentExtractionPolicyMapping::default();
+    for value in existing_value.into_iter().chain(operands) {
+        match JsonEncoder::decode::<ContentExtractionPolicyMapping>(value) {
+            Ok(operand) => mapping.merge(operand),
+            Err(e) => {
+                error!(
+                    "unable to decode content policy mapping of {}: {}",
+                    String::from_utf8_lossy(key),
+                    e
+                );
+                return None;
+            }
+        }
+    }
+    JsonEncoder::encode(&mapping).ok()
+}
+
+/// The outcome of the latest heartbeat of an executor. An executor is expired
+/// when its previous heartbeat is older than the TTL given with the heartbeat
+/// or it was removed, in which case it has to be admitted again from scratch
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HeartbeatOutcome {
+    pub ts_secs: u64,
+    pub previous_last_seen: Option<u64>,
+    pub was_expired: bool,
+}
+
+/// The summary of a finished task kept once the task is archived
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchivedTask {
+    pub id: TaskId,
+    pub extractor: ExtractorName,
+    pub extraction_policy_id: String,
+    pub namespace: NamespaceName,
+    pub content_id: ContentMetadataId,
+    pub outcome: TaskOutcome,
+    pub finished_at: u64,
+}
+
+/// A task along with the executor it is assigned to and the content it
+/// processes
+#[derive(Debug, Clone)]
+pub struct TaskDetail {
+    pub task: internal_api::Task,
+    pub executor: Option<internal_api::ExecutorMetadata>,
+    pub content: Option<internal_api::ContentMetadata>,
+}
+
+/// The fields of a content needed to list it. It's decoded straight from the
+/// stored ContentMetadata, skipping over the labels and the other fields
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct ContentSummary {
+    pub id: ContentMetadataId,
+    pub name: String,
+    pub size_bytes: u64,
+    #[serde(default)]
+    tombstoned: bool,
+}
+
+/// A sink for the per extractor queue depths, used to export them to an
+/// external time series store
+pub trait QueueDepthSink {
+    /// Records the number of unfinished tasks for an extractor and how many of
+    /// them are still waiting to be assigned to an executor
+    fn record_queue_depth(&self, extractor: &str, unfinished_tasks: usize, unassigned_tasks: usize);
+}
+
+/// A store of the outputs of tasks which are too large to be kept in the
+/// state machine. Only the references to the artifacts are stored in the
+/// state machine, the store resolves them to their contents
+pub trait ArtifactStore {
+    /// Fetches the contents of the artifact with the given reference
+    fn fetch(&self, artifact_ref: &str) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Reverse index updates waiting to be applied by the background worker. The
+/// updates are applied in the order they were queued
+#[derive(Debug, Default)]
+struct ReverseIndexQueue {
+    sender: std::sync::Mutex<Option<std::sync::mpsc::Sender<StateMachineUpdateRequest>>>,
+    /// Number of queued updates that have not been applied yet
+    pending: Arc<(std::sync::Mutex<usize>, std::sync::Condvar)>,
+}
+
+impl ReverseIndexQueue {
+    /// Queues the request for the background worker. The request is handed
+    /// back when there is no worker to apply it
+    fn push(&self, request: StateMachineUpdateRequest) -> Option<StateMachineUpdateRequest> {
+        let sender = self.sender.lock().unwrap();
+        let sender = match sender.as_ref() {
+            Some(sender) => sender,
+            None => return Some(request),
+        };
+        *self.pending.0.lock().unwrap() += 1;
+        match sender.send(request) {
+            Ok(()) => None,
+            Err(e) => {
+                error!("reverse index worker stopped, applying updates inline");
+                self.complete();
+                Some(e.0)
+            }
+        }
+    }
+
+    fn complete(&self) {
+        let (pending, drained) = &*self.pending;
+        let mut pending = pending.lock().unwrap();
+        *pending -= 1;
+        if *pending == 0 {
+            drained.notify_all();
+        }
+    }
+
+    fn wait_until_drained(&self) {
+        let (pending, drained) = &*self.pending;
+        let mut pending = pending.lock().unwrap();
+        while *pending > 0 {
+            pending = drained.wait(pending).unwrap();
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize, Default)]
 pub struct IndexifyState {
     // Reverse Indexes
@@ -585,24 +1217,49 @@ pub struct IndexifyState {
     /// Executor id -> number of tasks running on executor
     pub executor_running_task_count: ExecutorRunningTaskCount,
 
+    /// Summed cost of the tasks currently running on each executor
+    /// Executor id -> weighted load
+    pub executor_weighted_load: ExecutorWeightedLoad,
+
+    /// Time at which each executor finished its last running task, cleared
+    /// once a task is assigned to it again
+    /// Executor id -> seconds since the epoch
+    pub executor_idle_since: ExecutorIdleSince,
+
     /// Namespace -> Schemas
     pub schemas_by_namespace: SchemasByNamespace,
 
     /// Parent content id -> children content id's
     pub content_children_table: ContentChildrenTable,
 
+    /// Source content id -> chunk index -> chunk content id
+    pub content_chunks_table: ContentChunksTable,
+
     /// content id -> Map<ExtractionPolicyId, HashSet<TaskId>>
     pub pending_tasks_for_content: PendingTasksForContent,
 
     /// Metrics
     pub metrics: std::sync::Mutex<Metrics>,
+
+    /// Reverse index updates queued for the background worker, if started
+    #[serde(skip)]
+    reverse_index_queue: ReverseIndexQueue,
+
+    /// Whether the reverse index mutations of every request are logged
+    #[serde(skip)]
+    log_reverse_index_mutations: AtomicBool,
+
+    /// Seconds a processed state change stays in StateChangeSequence, or None
+    /// to never prune it
+    #[serde(skip)]
+    state_change_retention_secs: std::sync::Mutex<Option<u64>>,
 }
 
 impl fmt::Display for IndexifyState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "IndexifyState {{ unassigned_tasks: {:?}, unprocessed_state_changes: {:?}, content_namespace_table: {:?}, extraction_policies_table: {:?}, extractor_executors_table: {:?}, namespace_index_table: {:?}, unfinished_tasks_by_extractor: {:?}, executor_running_task_count: {:?}, schemas_by_namespace: {:?} }}, content_children_table: {:?}",
+            "IndexifyState {{ unassigned_tasks: {:?}, unprocessed_state_changes: {:?}, content_namespace_table: {:?}, extraction_policies_table: {:?}, extractor_executors_table: {:?}, namespace_index_table: {:?}, unfinished_tasks_by_extractor: {:?}, executor_running_task_count: {:?}, executor_weighted_load: {:?}, executor_idle_since: {:?}, schemas_by_namespace: {:?}, content_children_table: {:?}, content_chunks_table: {:?} }}",
             self.unassigned_tasks,
             self.unprocessed_state_changes,
             self.content_namespace_table,
@@ -611,28 +1268,93 @@ impl fmt::Display for IndexifyState {
             self.namespace_index_table,
             self.unfinished_tasks_by_extractor,
             self.executor_running_task_count,
+            self.executor_weighted_load,
+            self.executor_idle_since,
             self.schemas_by_namespace,
             self.content_children_table,
+            self.content_chunks_table,
         )
     }
 }
 
 impl IndexifyState {
+    /// Reads a u64 stored in StoreMeta
+    fn get_store_meta_u64(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        key: &str,
+    ) -> Result<Option<u64>, StateMachineError> {
+        txn.get_cf(StateMachineColumns::StoreMeta.cf(db), key)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|value| JsonEncoder::decode::<u64>(&value))
+            .transpose()
+    }
+
+    /// The last sequence number given to a state change. Stores written
+    /// before the high-water mark existed never pruned StateChangeSequence,
+    /// so their last key is the last sequence number
+    fn last_state_change_sequence(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+    ) -> Result<u64, StateMachineError> {
+        match self.get_store_meta_u64(db, txn, LAST_STATE_CHANGE_SEQUENCE_KEY)? {
+            Some(sequence) => Ok(sequence),
+            None => last_sequence(db, txn, StateMachineColumns::StateChangeSequence),
+        }
+    }
+
+    fn first_retained_state_change_sequence(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+    ) -> Result<u64, StateMachineError> {
+        Ok(self
+            .get_store_meta_u64(db, txn, FIRST_RETAINED_STATE_CHANGE_SEQUENCE_KEY)?
+            .unwrap_or(1))
+    }
+
     fn set_new_state_changes(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
         state_changes: &Vec<StateChange>,
     ) -> Result<(), StateMachineError> {
+        if state_changes.is_empty() {
+            return Ok(());
+        }
+        let mut sequence = self.last_state_change_sequence(db, txn)?;
         for change in state_changes {
-            let serialized_change = JsonEncoder::encode(change)?;
+            sequence += 1;
+            let change = StateChange {
+                sequence,
+                ..change.clone()
+            };
+            let serialized_change = JsonEncoder::encode(&change)?;
             txn.put_cf(
                 StateMachineColumns::StateChanges.cf(db),
                 &change.id,
                 &serialized_change,
             )
             .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let entry = StateChangeSequenceEntry {
+                state_change_id: change.id.clone(),
+                processed_at: change.processed_at,
+            };
+            txn.put_cf(
+                StateMachineColumns::StateChangeSequence.cf(db),
+                numeric_key(sequence),
+                JsonEncoder::encode(&entry)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
         }
+        txn.put_cf(
+            StateMachineColumns::StoreMeta.cf(db),
+            LAST_STATE_CHANGE_SEQUENCE_KEY,
+            JsonEncoder::encode(&sequence)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
         Ok(())
     }
 
@@ -642,7 +1364,11 @@ impl IndexifyState {
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
         state_changes: &Vec<StateChangeProcessed>,
     ) -> Result<(), StateMachineError> {
+        if state_changes.is_empty() {
+            return Ok(());
+        }
         let state_changes_cf = StateMachineColumns::StateChanges.cf(db);
+        let first_retained = self.first_retained_state_change_sequence(db, txn)?;
 
         for change in state_changes {
             let result = txn
@@ -660,20 +1386,118 @@ impl IndexifyState {
                 &serialized_change,
             )
             .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            //  Don't bring back the entry of a state change that was already pruned
+            if state_change.sequence < first_retained {
+                continue;
+            }
+            let entry = StateChangeSequenceEntry {
+                state_change_id: state_change.id.clone(),
+                processed_at: state_change.processed_at,
+            };
+            txn.put_cf(
+                StateMachineColumns::StateChangeSequence.cf(db),
+                numeric_key(state_change.sequence),
+                JsonEncoder::encode(&entry)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        }
+
+        //  The processed times come from the request, so every replica prunes the
+        //  same entries
+        let retention_secs = *self.state_change_retention_secs.lock().unwrap();
+        if let Some(retention_secs) = retention_secs {
+            let latest = state_changes
+                .iter()
+                .map(|change| change.processed_at)
+                .max()
+                .unwrap_or_default();
+            self.prune_state_change_sequence(
+                db,
+                txn,
+                first_retained,
+                latest.saturating_sub(retention_secs),
+            )?;
         }
         Ok(())
     }
 
-    fn set_index(
+    /// Deletes the entries at the start of StateChangeSequence processed
+    /// before `processed_before`. Pruning stops at the first entry that is
+    /// unprocessed or recent, so the retained entries stay contiguous
+    fn prune_state_change_sequence(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
-        index: &internal_api::Index,
-        id: &String,
+        first_retained: u64,
+        processed_before: u64,
+    ) -> Result<(), StateMachineError> {
+        let sequence_cf = StateMachineColumns::StateChangeSequence.cf(db);
+        let start = numeric_key(first_retained);
+        let iter = txn.iterator_cf(
+            sequence_cf,
+            rocksdb::IteratorMode::From(&start, rocksdb::Direction::Forward),
+        );
+        let mut next_retained = first_retained;
+        for item in iter {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let entry = JsonEncoder::decode::<StateChangeSequenceEntry>(&value)?;
+            match entry.processed_at {
+                Some(processed_at) if processed_at < processed_before => {}
+                _ => break,
+            }
+            txn.delete_cf(sequence_cf, &key)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            next_retained = decode_numeric_key(&key)? + 1;
+        }
+        if next_retained != first_retained {
+            txn.put_cf(
+                StateMachineColumns::StoreMeta.cf(db),
+                FIRST_RETAINED_STATE_CHANGE_SEQUENCE_KEY,
+                JsonEncoder::encode(&next_retained)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn set_index(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        index: &internal_api::Index,
+        id: &String,
     ) -> Result<(), StateMachineError> {
         let serialized_index = JsonEncoder::encode(index)?;
-        txn.put_cf(StateMachineColumns::IndexTable.cf(db), id, serialized_index)
+        txn.put_cf(
+            StateMachineColumns::IndexTable.cf(db),
+            index_generation_key(id, index.generation),
+            serialized_index,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+
+        //  The first generation of an index serves reads until another one is promoted
+        let active_generation = txn
+            .get_cf(StateMachineColumns::ActiveIndexGenerations.cf(db), id)
             .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        if active_generation.is_none() {
+            self.set_active_index_generation(db, txn, id, index.generation)?;
+        }
+        Ok(())
+    }
+
+    fn set_active_index_generation(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        index_id: &str,
+        generation: u64,
+    ) -> Result<(), StateMachineError> {
+        txn.put_cf(
+            StateMachineColumns::ActiveIndexGenerations.cf(db),
+            index_id,
+            JsonEncoder::encode(&generation)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
         Ok(())
     }
 
@@ -693,14 +1517,68 @@ impl IndexifyState {
         Ok(task)
     }
 
+    /// Returns the task if it exists, for updates which are dropped when the
+    /// task is unknown
+    fn _find_task(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task_id: &TaskId,
+    ) -> Result<Option<internal_api::Task>, StateMachineError> {
+        txn.get_cf(StateMachineColumns::Tasks.cf(db), task_id)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|serialized_task| JsonEncoder::decode(&serialized_task))
+            .transpose()
+    }
+
+    /// Sums the costs of the given tasks. Tasks which aren't found don't add to
+    /// the cost
+    fn tasks_weight<'a>(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task_ids: impl IntoIterator<Item = &'a TaskId>,
+    ) -> Result<u64, StateMachineError> {
+        let mut weight = 0;
+        for task in txn.multi_get_cf(
+            task_ids
+                .into_iter()
+                .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+        ) {
+            let task = task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(task) = task {
+                weight += JsonEncoder::decode::<internal_api::Task>(&task)?.weight();
+            }
+        }
+        Ok(weight)
+    }
+
     fn set_tasks(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
         tasks: &Vec<internal_api::Task>,
     ) -> Result<(), StateMachineError> {
-        // content_id -> Set(Extraction Policy Ids)
-        for task in tasks {
+        //  Only tasks which don't exist yet get a creation sequence number
+        let existing_tasks = txn.multi_get_cf(
+            tasks
+                .iter()
+                .map(|task| (StateMachineColumns::Tasks.cf(db), &task.id)),
+        );
+        let mut sequence = last_sequence(db, txn, StateMachineColumns::TaskCreationSequence)?;
+        let mut sequenced_task_ids = HashSet::new();
+        for (task, existing_task) in tasks.iter().zip(existing_tasks) {
+            let existing_task =
+                existing_task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if existing_task.is_none() && sequenced_task_ids.insert(&task.id) {
+                sequence += 1;
+                txn.put_cf(
+                    StateMachineColumns::TaskCreationSequence.cf(db),
+                    numeric_key(sequence),
+                    &task.id,
+                )
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            }
             let serialized_task = JsonEncoder::encode(task)?;
             txn.put_cf(
                 StateMachineColumns::Tasks.cf(db),
@@ -708,17 +1586,28 @@ impl IndexifyState {
                 &serialized_task,
             )
             .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
-            self.update_content_extraction_policy_state(
-                db,
-                txn,
-                &task.content_metadata.id,
-                &task.extraction_policy_id,
-                SystemTime::UNIX_EPOCH,
-            )?;
         }
-        Ok(())
+        let applied_policies: Vec<_> = tasks
+            .iter()
+            .map(|task| {
+                (
+                    task.content_metadata.id.clone(),
+                    task.extraction_policy_id.clone(),
+                )
+            })
+            .collect();
+        self.set_content_policies_applied_on_content(
+            db,
+            txn,
+            &applied_policies,
+            SystemTime::UNIX_EPOCH,
+        )
     }
 
+    /// Writes the updated tasks. The extraction policy of every finished task
+    /// is marked applied on its content in the same transaction, with the
+    /// update time as the time of completion, so a task is never finished
+    /// without its content being marked processed
     fn update_tasks(
         &self,
         db: &Arc<OptimisticTransactionDB>,
@@ -726,6 +1615,7 @@ impl IndexifyState {
         tasks: Vec<&internal_api::Task>,
         update_time: SystemTime,
     ) -> Result<(), StateMachineError> {
+        let mut applied_policies = Vec::new();
         for task in tasks {
             let serialized_task = JsonEncoder::encode(task)?;
             txn.put_cf(
@@ -735,18 +1625,357 @@ impl IndexifyState {
             )
             .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
             if task.terminal_state() {
-                self.update_content_extraction_policy_state(
-                    db,
-                    txn,
-                    &task.content_metadata.id,
-                    &task.extraction_policy_id,
-                    update_time,
-                )?;
+                applied_policies.push((
+                    task.content_metadata.id.clone(),
+                    task.extraction_policy_id.clone(),
+                ));
+                let finished_at = update_time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map_err(|e| {
+                        StateMachineError::DatabaseError(format!(
+                            "Error converting task finish time to u64: {}",
+                            e
+                        ))
+                    })?
+                    .as_secs();
+                txn.put_cf(
+                    StateMachineColumns::FinishedTasksByTime.cf(db),
+                    finished_task_key(finished_at, &task.id),
+                    JsonEncoder::encode(&task.id)?,
+                )
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            }
+        }
+        self.set_content_policies_applied_on_content(db, txn, &applied_policies, update_time)
+    }
+
+    /// Marks a task as cancelled and detaches it from the executor it was
+    /// assigned to. The reverse indexes are updated once the writes are
+    /// committed
+    fn finalize_task_cancellation(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        mut task: internal_api::Task,
+        executor_id: Option<&ExecutorId>,
+        update_time: SystemTime,
+        post_commit: &mut PostCommitUpdates,
+    ) -> Result<(), StateMachineError> {
+        task.outcome = TaskOutcome::Cancelled;
+        self.update_tasks(db, txn, vec![&task], update_time)?;
+        self.detach_cancelled_task(db, txn, task, executor_id.cloned(), post_commit)
+    }
+
+    /// Removes a cancelled task from the assignments of its executor, and from
+    /// the reverse indexes of pending work once the writes are committed
+    fn detach_cancelled_task(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task: internal_api::Task,
+        executor_id: Option<ExecutorId>,
+        post_commit: &mut PostCommitUpdates,
+    ) -> Result<(), StateMachineError> {
+        if let Some(executor_id) = &executor_id {
+            let mut existing_tasks =
+                self.get_task_assignments_for_executor(db, txn, executor_id)?;
+            existing_tasks.remove(&task.id);
+            let new_task_assignment = HashMap::from([(executor_id.to_string(), existing_tasks)]);
+            self.set_task_assignments(db, txn, &new_task_assignment)?;
+            self.delete_task_executors(db, txn, &[task.id.clone()])?;
+        }
+        post_commit.push(move |state| state.remove_deleted_tasks(vec![(task, executor_id)]));
+        Ok(())
+    }
+
+    /// Reserves the next `count` task ids under the reservation id. The next
+    /// reservable id is persisted along with the reservation, so ids are not
+    /// reused across restarts
+    fn reserve_task_ids(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        reservation_id: &str,
+        count: u64,
+    ) -> Result<TaskIdReservation, StateMachineError> {
+        let start = txn
+            .get_cf(
+                StateMachineColumns::StoreMeta.cf(db),
+                NEXT_RESERVED_TASK_ID_KEY,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|value| JsonEncoder::decode::<u64>(&value))
+            .transpose()?
+            .unwrap_or_default();
+        let reservation = TaskIdReservation { start, count };
+        txn.put_cf(
+            StateMachineColumns::StoreMeta.cf(db),
+            NEXT_RESERVED_TASK_ID_KEY,
+            JsonEncoder::encode(&(start + count))?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        txn.put_cf(
+            StateMachineColumns::TaskIdReservations.cf(db),
+            reservation_id,
+            JsonEncoder::encode(&reservation)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        Ok(reservation)
+    }
+
+    /// Cancels the unfinished tasks whose content is no longer in the
+    /// ContentTable. Returns the ids of the cancelled tasks
+    fn cancel_orphaned(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        post_commit: &mut PostCommitUpdates,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        let unfinished_task_ids = self
+            .unfinished_tasks_by_extractor
+            .inner()
+            .into_values()
+            .flatten()
+            .sorted()
+            .collect_vec();
+        let mut cancelled_task_ids = Vec::new();
+        for task_id in unfinished_task_ids {
+            let task = self._get_task(db, txn, &task_id)?;
+            let content = txn
+                .get_cf(
+                    StateMachineColumns::ContentTable.cf(db),
+                    task.content_metadata.id.to_string(),
+                )
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if content.is_some() {
+                continue;
+            }
+            //  The content is gone, so there are no applied extraction policies to update
+            let task = internal_api::Task {
+                outcome: TaskOutcome::Cancelled,
+                ..task
+            };
+            txn.put_cf(
+                StateMachineColumns::Tasks.cf(db),
+                &task.id,
+                JsonEncoder::encode(&task)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let executor_id = self.task_executor(db, &task_id)?;
+            self.detach_cancelled_task(db, txn, task, executor_id, post_commit)?;
+            cancelled_task_ids.push(task_id);
+        }
+        Ok(cancelled_task_ids)
+    }
+
+    /// Moves unassigned tasks from an extractor to another one. The request is
+    /// rejected before anything is written if any of the tasks is assigned to
+    /// an executor, has finished or doesn't belong to `from_extractor`
+    fn retarget_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        from_extractor: &ExtractorName,
+        to_extractor: &ExtractorName,
+        task_ids: &[TaskId],
+        post_commit: &mut PostCommitUpdates,
+    ) -> Result<(), StateMachineError> {
+        self.set_tasks_extractor(db, txn, from_extractor, to_extractor, task_ids)?;
+        let retargeted_task_ids = task_ids.to_vec();
+        let (from_extractor, to_extractor) = (from_extractor.clone(), to_extractor.clone());
+        post_commit.push(move |state| {
+            //  Retargeting every unfinished task of the extractor renames it
+            let renamed = state
+                .unfinished_tasks_by_extractor
+                .inner()
+                .get(&from_extractor)
+                .map_or(false, |task_ids| {
+                    task_ids
+                        .iter()
+                        .all(|task_id| retargeted_task_ids.contains(task_id))
+                });
+            if renamed {
+                state.rekey_unfinished_tasks(&from_extractor, &to_extractor);
+            } else {
+                state.unfinished_tasks_by_extractor.move_tasks(
+                    &from_extractor,
+                    &to_extractor,
+                    &retargeted_task_ids,
+                );
+            }
+        });
+        Ok(())
+    }
+
+    /// Renames an extractor, moving its stored description and its unfinished
+    /// tasks to the new name. The tasks are checked and written as by
+    /// `retarget_tasks`
+    fn rename_extractor(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        from_extractor: &ExtractorName,
+        to_extractor: &ExtractorName,
+        post_commit: &mut PostCommitUpdates,
+    ) -> Result<(), StateMachineError> {
+        let task_ids = self
+            .unfinished_tasks_by_extractor
+            .inner()
+            .remove(from_extractor)
+            .unwrap_or_default()
+            .into_iter()
+            .sorted()
+            .collect_vec();
+        self.set_tasks_extractor(db, txn, from_extractor, to_extractor, &task_ids)?;
+
+        let extractors_cf = StateMachineColumns::Extractors.cf(db);
+        let stored_extractor = txn
+            .get_cf(extractors_cf, from_extractor)
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error reading extractor: {}", e))
+            })?
+            .map(|value| JsonEncoder::decode::<ExtractorDescription>(&value))
+            .transpose()?;
+        if let Some(extractor) = stored_extractor {
+            let extractor = ExtractorDescription {
+                name: to_extractor.clone(),
+                ..extractor
+            };
+            txn.delete_cf(extractors_cf, from_extractor).map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error deleting extractor: {}", e))
+            })?;
+            txn.put_cf(
+                extractors_cf,
+                to_extractor,
+                JsonEncoder::encode(&extractor)?,
+            )
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error writing extractor: {}", e))
+            })?;
+        }
+
+        let (from_extractor, to_extractor) = (from_extractor.clone(), to_extractor.clone());
+        post_commit.push(move |state| state.rekey_unfinished_tasks(&from_extractor, &to_extractor));
+        Ok(())
+    }
+
+    /// Sets the extractor of unassigned tasks of `from_extractor` to
+    /// `to_extractor`. Fails before anything is written if any of the tasks is
+    /// assigned to an executor, has finished or belongs to another extractor
+    fn set_tasks_extractor(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        from_extractor: &ExtractorName,
+        to_extractor: &ExtractorName,
+        task_ids: &[TaskId],
+    ) -> Result<(), StateMachineError> {
+        let mut tasks = Vec::new();
+        for task_id in task_ids {
+            if let Some(executor_id) = self.task_executor(db, task_id)? {
+                return Err(StateMachineError::ExternalError(anyhow!(
+                    "can't retarget task {} which is assigned to executor {}",
+                    task_id,
+                    executor_id
+                )));
+            }
+            let task = self._get_task(db, txn, task_id)?;
+            if task.terminal_state() {
+                return Err(StateMachineError::ExternalError(anyhow!(
+                    "can't retarget task {} which already finished",
+                    task_id
+                )));
             }
+            if task.extractor != *from_extractor {
+                return Err(StateMachineError::ExternalError(anyhow!(
+                    "can't retarget task {} of extractor {} from extractor {}",
+                    task_id,
+                    task.extractor,
+                    from_extractor
+                )));
+            }
+            tasks.push(task);
+        }
+        for mut task in tasks {
+            task.extractor = to_extractor.clone();
+            txn.put_cf(
+                StateMachineColumns::Tasks.cf(db),
+                &task.id,
+                JsonEncoder::encode(&task)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(format!("Error writing task: {}", e)))?;
         }
         Ok(())
     }
 
+    fn set_task_artifact_ref(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task_id: &TaskId,
+        artifact_ref: &str,
+    ) -> Result<(), StateMachineError> {
+        txn.put_cf(
+            StateMachineColumns::TaskArtifacts.cf(db),
+            task_id,
+            JsonEncoder::encode(&artifact_ref)?,
+        )
+        .map_err(|e| {
+            StateMachineError::DatabaseError(format!(
+                "Error writing task artifact reference: {}",
+                e
+            ))
+        })
+    }
+
+    /// Deletes tasks along with their executor assignments. Task ids that
+    /// don't exist are skipped. Returns the deleted tasks along with the
+    /// executor each of them was assigned to
+    fn delete_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task_ids: &Vec<TaskId>,
+    ) -> Result<Vec<(internal_api::Task, Option<ExecutorId>)>, StateMachineError> {
+        let mut deleted_tasks = Vec::new();
+        for task_id in task_ids {
+            let task = match txn
+                .get_cf(StateMachineColumns::Tasks.cf(db), task_id)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            {
+                Some(task) => JsonEncoder::decode::<internal_api::Task>(&task)?,
+                None => continue,
+            };
+            txn.delete_cf(StateMachineColumns::Tasks.cf(db), task_id)
+                .map_err(|e| {
+                    StateMachineError::DatabaseError(format!("Error deleting task: {}", e))
+                })?;
+            txn.delete_cf(StateMachineColumns::TaskArtifacts.cf(db), task_id)
+                .map_err(|e| {
+                    StateMachineError::DatabaseError(format!(
+                        "Error deleting task artifact reference: {}",
+                        e
+                    ))
+                })?;
+
+            let executor_id = txn
+                .get_cf(StateMachineColumns::TaskToExecutor.cf(db), task_id)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+                .map(|executor_id| JsonEncoder::decode::<ExecutorId>(&executor_id))
+                .transpose()?;
+            if let Some(executor_id) = &executor_id {
+                let mut existing_tasks =
+                    self.get_task_assignments_for_executor(db, txn, executor_id)?;
+                existing_tasks.remove(task_id);
+                let new_task_assignment = HashMap::from([(executor_id.clone(), existing_tasks)]);
+                self.set_task_assignments(db, txn, &new_task_assignment)?;
+                self.delete_task_executors(db, txn, &[task_id.clone()])?;
+            }
+            deleted_tasks.push((task, executor_id));
+        }
+        Ok(deleted_tasks)
+    }
+
     fn set_garbage_collection_tasks(
         &self,
         db: &Arc<OptimisticTransactionDB>,
@@ -830,6 +2059,108 @@ impl IndexifyState {
         Ok(())
     }
 
+    /// Adds tasks to the assignments of their executors. The current
+    /// assignments of all the affected executors are read in a single
+    /// multi-get, merged in memory and written back in one pass
+    fn add_task_assignments(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        assignments: &HashMap<TaskId, ExecutorId>,
+    ) -> Result<(), StateMachineError> {
+        let new_assignments: HashMap<&ExecutorId, HashSet<TaskId>> =
+            assignments
+                .iter()
+                .fold(HashMap::new(), |mut acc, (task_id, executor_id)| {
+                    acc.entry(executor_id).or_default().insert(task_id.clone());
+                    acc
+                });
+        let executor_ids = new_assignments.keys().collect_vec();
+        let existing_assignments = txn.multi_get_cf(
+            executor_ids
+                .iter()
+                .map(|executor_id| (StateMachineColumns::TaskAssignments.cf(db), executor_id)),
+        );
+
+        let mut task_assignments = HashMap::new();
+        for (executor_id, existing_tasks) in executor_ids.into_iter().zip(existing_assignments) {
+            let existing_tasks = existing_tasks.map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error reading task assignments: {}", e))
+            })?;
+            let mut tasks: HashSet<TaskId> = match existing_tasks {
+                Some(existing_tasks) => JsonEncoder::decode(&existing_tasks)?,
+                None => HashSet::new(),
+            };
+            tasks.extend(new_assignments[executor_id].iter().cloned());
+            task_assignments.insert(executor_id.clone(), tasks);
+        }
+        self.set_task_assignments(db, txn, &task_assignments)
+    }
+
+    /// Set the executor that each task has been assigned to
+    fn set_task_executors(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        assignments: &HashMap<TaskId, ExecutorId>,
+    ) -> Result<(), StateMachineError> {
+        let task_to_executor_cf = StateMachineColumns::TaskToExecutor.cf(db);
+        for (task_id, executor_id) in assignments {
+            txn.put_cf(
+                task_to_executor_cf,
+                task_id,
+                JsonEncoder::encode(executor_id)?,
+            )
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error writing task executor: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    fn delete_task_executors(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task_ids: &[TaskId],
+    ) -> Result<(), StateMachineError> {
+        let task_to_executor_cf = StateMachineColumns::TaskToExecutor.cf(db);
+        for task_id in task_ids {
+            txn.delete_cf(task_to_executor_cf, task_id).map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error deleting task executor: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Moves the task assignments of an executor to another executor and
+    /// returns the tasks assigned to the other executor afterwards
+    fn swap_executor_workloads(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        from: &ExecutorId,
+        to: &ExecutorId,
+    ) -> Result<HashSet<TaskId>, StateMachineError> {
+        let moved_tasks = self.get_task_assignments_for_executor(db, txn, from)?;
+        let mut assigned_tasks = self.get_task_assignments_for_executor(db, txn, to)?;
+        assigned_tasks.extend(moved_tasks.iter().cloned());
+        self.set_task_assignments(
+            db,
+            txn,
+            &HashMap::from([
+                (from.clone(), HashSet::new()),
+                (to.clone(), assigned_tasks.clone()),
+            ]),
+        )?;
+        let task_executors: HashMap<TaskId, ExecutorId> = moved_tasks
+            .into_iter()
+            .map(|task_id| (task_id, to.clone()))
+            .collect();
+        self.set_task_executors(db, txn, &task_executors)?;
+        Ok(assigned_tasks)
+    }
+
     // FIXME USE MULTI-GET HERE
     fn delete_task_assignments_for_executor(
         &self,
@@ -867,34 +2198,278 @@ impl IndexifyState {
         Ok(task_ids)
     }
 
-    fn set_content(
+    /// Marks all the pending tasks of a piece of content as cancelled and
+    /// detaches them from the executors they were assigned to. Returns the
+    /// cancelled tasks along with the executor each of them was assigned to
+    fn cancel_content_tasks(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
-        contents_vec: &Vec<internal_api::ContentMetadata>,
-    ) -> Result<(), StateMachineError> {
-        for content in contents_vec {
-            let content_key = format!("{}::v{}", content.id.id, content.id.version);
-            let serialized_content = JsonEncoder::encode(content)?;
-            txn.put_cf(
-                StateMachineColumns::ContentTable.cf(db),
-                content_key,
-                &serialized_content,
-            )
-            .map_err(|e| {
-                StateMachineError::DatabaseError(format!("error writing content: {}", e))
-            })?;
-        }
-        Ok(())
+        content_id: &ContentMetadataId,
+    ) -> Result<Vec<(internal_api::Task, Option<ExecutorId>)>, StateMachineError> {
+        let task_ids = self.pending_tasks_for_content.get(content_id);
+        self.cancel_tasks(db, txn, task_ids)
     }
 
-    fn tombstone_content_tree(
+    /// Marks all the pending tasks of an extraction policy as cancelled and
+    /// detaches them from the executors they were assigned to
+    fn cancel_policy_tasks(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
-        content_metadata: &Vec<indexify_internal_api::ContentMetadata>,
-    ) -> Result<(), StateMachineError> {
-        for content in content_metadata {
+        extraction_policy_id: &str,
+    ) -> Result<Vec<(internal_api::Task, Option<ExecutorId>)>, StateMachineError> {
+        let task_ids = self
+            .pending_tasks_for_content
+            .get_for_policy(extraction_policy_id);
+        self.cancel_tasks(db, txn, task_ids)
+    }
+
+    fn cancel_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        task_ids: HashSet<TaskId>,
+    ) -> Result<Vec<(internal_api::Task, Option<ExecutorId>)>, StateMachineError> {
+        if task_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        //  Remove the tasks from the executors they were assigned to
+        let executor_ids = txn.multi_get_cf(
+            task_ids
+                .iter()
+                .map(|task_id| (StateMachineColumns::TaskToExecutor.cf(db), task_id)),
+        );
+        let mut task_executors: HashMap<TaskId, ExecutorId> = HashMap::new();
+        for (task_id, executor_id) in task_ids.iter().zip(executor_ids) {
+            let executor_id = executor_id.map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error reading task executor: {}", e))
+            })?;
+            if let Some(executor_id) = executor_id {
+                task_executors.insert(task_id.clone(), JsonEncoder::decode(&executor_id)?);
+            }
+        }
+        let mut updated_task_assignments: HashMap<ExecutorId, HashSet<TaskId>> = HashMap::new();
+        for (task_id, executor_id) in &task_executors {
+            if !updated_task_assignments.contains_key(executor_id) {
+                let assigned_tasks =
+                    self.get_task_assignments_for_executor(db, txn, executor_id)?;
+                updated_task_assignments.insert(executor_id.clone(), assigned_tasks);
+            }
+            if let Some(assigned_tasks) = updated_task_assignments.get_mut(executor_id) {
+                assigned_tasks.remove(task_id);
+            }
+        }
+        self.set_task_assignments(db, txn, &updated_task_assignments)?;
+        self.delete_task_executors(db, txn, &task_executors.keys().cloned().collect::<Vec<_>>())?;
+
+        //  Mark the tasks as cancelled
+        let mut cancelled_tasks = Vec::new();
+        for task_id in task_ids {
+            let mut task = self._get_task(db, txn, &task_id)?;
+            task.outcome = TaskOutcome::Cancelled;
+            let serialized_task = JsonEncoder::encode(&task)?;
+            txn.put_cf(
+                StateMachineColumns::Tasks.cf(db),
+                task.id.clone(),
+                &serialized_task,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let executor_id = task_executors.remove(&task_id);
+            cancelled_tasks.push((task, executor_id));
+        }
+        Ok(cancelled_tasks)
+    }
+
+    fn set_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        contents_vec: &Vec<internal_api::ContentMetadata>,
+    ) -> Result<(), StateMachineError> {
+        let content_keys: Vec<String> = contents_vec
+            .iter()
+            .map(|content| format!("{}::v{}", content.id.id, content.id.version))
+            .collect();
+        let existing_contents = txn.multi_get_cf(
+            content_keys
+                .iter()
+                .map(|key| (StateMachineColumns::ContentTable.cf(db), key)),
+        );
+        //  Content written earlier in the batch isn't seen by the multi get
+        let mut written_contents: HashMap<String, &internal_api::ContentMetadata> = HashMap::new();
+        for ((content, content_key), existing_content) in
+            contents_vec.iter().zip(content_keys).zip(existing_contents)
+        {
+            let existing_content = existing_content
+                .map_err(|e| {
+                    StateMachineError::DatabaseError(format!("error reading content: {}", e))
+                })?
+                .map(|value| JsonEncoder::decode::<internal_api::ContentMetadata>(&value))
+                .transpose()?;
+            match written_contents.get(&content_key) {
+                Some(written_content) => {
+                    self.delete_content_modified_time(db, txn, written_content)?;
+                    self.delete_content_tags(db, txn, written_content)?;
+                }
+                None => {
+                    if let Some(existing_content) = existing_content {
+                        self.delete_content_modified_time(db, txn, &existing_content)?;
+                        self.delete_content_tags(db, txn, &existing_content)?;
+                    }
+                }
+            }
+            let serialized_content = JsonEncoder::encode(content)?;
+            txn.put_cf(
+                StateMachineColumns::ContentTable.cf(db),
+                content_key,
+                &serialized_content,
+            )
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("error writing content: {}", e))
+            })?;
+            txn.put_cf(
+                StateMachineColumns::ContentByModifiedTime.cf(db),
+                content_by_modified_time_key(content),
+                JsonEncoder::encode(&content.id)?,
+            )
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!(
+                    "error writing content modified time: {}",
+                    e
+                ))
+            })?;
+            for tag in &content.tags {
+                txn.put_cf(
+                    StateMachineColumns::ContentByTag.cf(db),
+                    content_by_tag_key(&content.namespace, tag, &content.id),
+                    JsonEncoder::encode(&content.id)?,
+                )
+                .map_err(|e| {
+                    StateMachineError::DatabaseError(format!("error writing content tag: {}", e))
+                })?;
+            }
+            written_contents.insert(content_key, content);
+        }
+        Ok(())
+    }
+
+    /// Replaces a `CreateContent` request whose expected version doesn't match
+    /// the stored content with a no-op. The state changes the request created
+    /// are dropped along with the content, since they refer to content which
+    /// is never written
+    fn drop_conflicting_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        request: StateMachineUpdateRequest,
+    ) -> Result<StateMachineUpdateRequest, StateMachineError> {
+        let (content_metadata, expected_version) = match &request.payload {
+            RequestPayload::CreateContent {
+                content_metadata,
+                expected_version: Some(expected_version),
+            } => (content_metadata, *expected_version),
+            _ => return Ok(request),
+        };
+        for content in content_metadata {
+            let stored_version = self
+                .get_latest_version_of_content(&content.id.id, db, txn)?
+                .unwrap_or(0);
+            if stored_version != expected_version || content.id.version != expected_version + 1 {
+                tracing::warn!(
+                    "dropping write of content {}: expected version {}, stored version {}",
+                    content.id,
+                    expected_version,
+                    stored_version
+                );
+                return Ok(StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![],
+                        expected_version: None,
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: request.state_changes_processed,
+                    correlation_id: request.correlation_id,
+                });
+            }
+        }
+        Ok(request)
+    }
+
+    /// Fails when any of the content would overwrite existing immutable
+    /// content. Tombstoned content can be overwritten
+    fn check_immutable_content_overwrite(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        contents_vec: &[internal_api::ContentMetadata],
+    ) -> Result<(), StateMachineError> {
+        let existing_contents = txn.multi_get_cf(contents_vec.iter().map(|content| {
+            (
+                StateMachineColumns::ContentTable.cf(db),
+                format!("{}::v{}", content.id.id, content.id.version),
+            )
+        }));
+        for existing_content in existing_contents {
+            let existing_content = existing_content
+                .map_err(|e| {
+                    StateMachineError::DatabaseError(format!("error reading content: {}", e))
+                })?
+                .map(|value| JsonEncoder::decode::<internal_api::ContentMetadata>(&value))
+                .transpose()?;
+            if let Some(existing_content) = existing_content {
+                if existing_content.immutable && !existing_content.tombstoned {
+                    return Err(StateMachineError::ExternalError(anyhow!(
+                        "content {} is immutable and can't be overwritten",
+                        existing_content.id
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn delete_content_modified_time(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        content: &internal_api::ContentMetadata,
+    ) -> Result<(), StateMachineError> {
+        txn.delete_cf(
+            StateMachineColumns::ContentByModifiedTime.cf(db),
+            content_by_modified_time_key(content),
+        )
+        .map_err(|e| {
+            StateMachineError::DatabaseError(format!("error deleting content modified time: {}", e))
+        })
+    }
+
+    fn delete_content_tags(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        content: &internal_api::ContentMetadata,
+    ) -> Result<(), StateMachineError> {
+        for tag in &content.tags {
+            txn.delete_cf(
+                StateMachineColumns::ContentByTag.cf(db),
+                content_by_tag_key(&content.namespace, tag, &content.id),
+            )
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("error deleting content tag: {}", e))
+            })?;
+        }
+        Ok(())
+    }
+
+    fn tombstone_content_tree(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        content_metadata: &Vec<indexify_internal_api::ContentMetadata>,
+    ) -> Result<(), StateMachineError> {
+        for content in content_metadata {
             let content_key = format!("{}::v{}", content.id.id, content.id.version);
             let serialized_content = JsonEncoder::encode(content)?;
             txn.put_cf(
@@ -918,13 +2493,35 @@ impl IndexifyState {
         content_ids: Vec<ContentMetadataId>,
     ) -> Result<(), StateMachineError> {
         for content_id in content_ids {
+            let content_key = format!("{}::v{}", content_id.id, content_id.version);
+            let content = txn
+                .get_cf(StateMachineColumns::ContentTable.cf(db), &content_key)
+                .map_err(|e| {
+                    StateMachineError::TransactionError(format!(
+                        "error in txn while trying to read content: {}",
+                        e
+                    ))
+                })?
+                .map(|value| JsonEncoder::decode::<internal_api::ContentMetadata>(&value))
+                .transpose()?;
+            if let Some(content) = content {
+                self.delete_content_modified_time(db, txn, &content)?;
+                self.delete_content_tags(db, txn, &content)?;
+            }
+            txn.delete_cf(StateMachineColumns::ContentTable.cf(db), &content_key)
+                .map_err(|e| {
+                    StateMachineError::TransactionError(format!(
+                        "error in txn while trying to delete content: {}",
+                        e
+                    ))
+                })?;
             txn.delete_cf(
-                StateMachineColumns::ContentTable.cf(db),
-                &format!("{}::v{}", content_id.id, content_id.version),
+                StateMachineColumns::ExtractionPoliciesAppliedOnContent.cf(db),
+                &content_key,
             )
             .map_err(|e| {
                 StateMachineError::TransactionError(format!(
-                    "error in txn while trying to delete content: {}",
+                    "error in txn while trying to delete content policy mapping: {}",
                     e
                 ))
             })?;
@@ -936,26 +2533,76 @@ impl IndexifyState {
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
-        addr: String,
-        executor_id: &str,
-        extractor: &ExtractorDescription,
-        ts_secs: &u64,
+        executor: &internal_api::ExecutorMetadata,
     ) -> Result<(), StateMachineError> {
-        let serialized_executor = JsonEncoder::encode(&internal_api::ExecutorMetadata {
-            id: executor_id.into(),
-            last_seen: *ts_secs,
-            addr: addr.clone(),
-            extractor: extractor.clone(),
-        })?;
+        let serialized_executor = JsonEncoder::encode(executor)?;
         txn.put_cf(
             StateMachineColumns::Executors.cf(db),
-            executor_id,
+            &executor.id,
             serialized_executor,
         )
         .map_err(|e| StateMachineError::DatabaseError(format!("Error writing executor: {}", e)))?;
         Ok(())
     }
 
+    /// Updates the last seen time of an executor and returns the previous one.
+    /// Heartbeats of executors which have since been removed are ignored
+    fn set_executor_last_seen(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        executor_id: &str,
+        ts_secs: u64,
+    ) -> Result<Option<u64>, StateMachineError> {
+        let executors_cf = StateMachineColumns::Executors.cf(db);
+        let serialized_executor = txn.get_cf(executors_cf, executor_id).map_err(|e| {
+            StateMachineError::DatabaseError(format!("Error reading executor: {}", e))
+        })?;
+        let mut executor = match serialized_executor {
+            Some(executor) => JsonEncoder::decode::<internal_api::ExecutorMetadata>(&executor)?,
+            None => {
+                tracing::warn!("ignoring heartbeat of unknown executor {}", executor_id);
+                return Ok(None);
+            }
+        };
+        let previous_last_seen = executor.last_seen;
+        executor.last_seen = ts_secs;
+        txn.put_cf(executors_cf, executor_id, JsonEncoder::encode(&executor)?)
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error writing executor: {}", e))
+            })?;
+        Ok(Some(previous_last_seen))
+    }
+
+    /// Updates the last seen time of an executor and records whether it had
+    /// expired before this heartbeat
+    fn heartbeat(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        executor_id: &str,
+        ts_secs: u64,
+        ttl_secs: u64,
+    ) -> Result<(), StateMachineError> {
+        let previous_last_seen = self.set_executor_last_seen(db, txn, executor_id, ts_secs)?;
+        let was_expired = match previous_last_seen {
+            Some(last_seen) => last_seen.saturating_add(ttl_secs) < ts_secs,
+            None => true,
+        };
+        let outcome = HeartbeatOutcome {
+            ts_secs,
+            previous_last_seen,
+            was_expired,
+        };
+        txn.put_cf(
+            StateMachineColumns::HeartbeatOutcomes.cf(db),
+            executor_id,
+            JsonEncoder::encode(&outcome)?,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
     fn delete_executor(
         &self,
         db: &Arc<OptimisticTransactionDB>,
@@ -980,12 +2627,75 @@ impl IndexifyState {
         Ok(executor_meta)
     }
 
+    /// Deletes the executors which haven't sent a heartbeat within `ttl_secs`
+    /// along with their task assignments. Returns the deleted executors, in
+    /// id order, with the ids of the tasks which were assigned to them
+    fn reap_stale(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        now_secs: u64,
+        ttl_secs: u64,
+    ) -> Result<Vec<(internal_api::ExecutorMetadata, Vec<TaskId>)>, StateMachineError> {
+        let mut stale_executor_ids = Vec::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::Executors.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (_, value) = item.map_err(|e| {
+                StateMachineError::DatabaseError(format!("unable to read executors: {}", e))
+            })?;
+            let executor: internal_api::ExecutorMetadata = JsonEncoder::decode(&value)?;
+            if executor.last_seen.saturating_add(ttl_secs) < now_secs {
+                stale_executor_ids.push(executor.id);
+            }
+        }
+        let mut reaped = Vec::new();
+        for executor_id in stale_executor_ids {
+            let executor_meta = self.delete_executor(db, txn, &executor_id)?;
+            let mut task_ids = self.delete_task_assignments_for_executor(db, txn, &executor_id)?;
+            self.delete_task_executors(db, txn, &task_ids)?;
+            task_ids.sort();
+            reaped.push((executor_meta, task_ids));
+        }
+        Ok(reaped)
+    }
+
+    /// Stores the description of an extractor registered by an executor. A
+    /// description differing from the stored one is ignored while other
+    /// executors still serve the extractor, since they would run a different
+    /// extractor under the same name
     fn set_extractor(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        executor_id: &ExecutorId,
         extractor: &ExtractorDescription,
     ) -> Result<(), StateMachineError> {
+        let stored_extractor = txn
+            .get_cf(StateMachineColumns::Extractors.cf(db), &extractor.name)
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error reading extractor: {}", e))
+            })?
+            .map(|value| JsonEncoder::decode::<ExtractorDescription>(&value))
+            .transpose()?;
+        if let Some(stored_extractor) = stored_extractor {
+            let served_by_others = self
+                .extractor_executors_table
+                .inner()
+                .get(&extractor.name)
+                .map_or(false, |executor_ids| {
+                    executor_ids.iter().any(|id| id != executor_id)
+                });
+            if stored_extractor != *extractor && served_by_others {
+                tracing::warn!(
+                    "ignoring redefinition of extractor {} by executor {}: already registered with a different description",
+                    extractor.name,
+                    executor_id
+                );
+                return Ok(());
+            }
+        }
         let serialized_extractor = JsonEncoder::encode(extractor)?;
         txn.put_cf(
             StateMachineColumns::Extractors.cf(db),
@@ -1004,6 +2714,14 @@ impl IndexifyState {
         updated_structured_data_schema: &Option<internal_api::StructuredDataSchema>,
         new_structured_data_schema: &internal_api::StructuredDataSchema,
     ) -> Result<(), StateMachineError> {
+        //  A policy with malformed filters never matches any content
+        validate_selector(&extraction_policy.filters).map_err(|e| {
+            StateMachineError::ExternalError(anyhow!(
+                "invalid extraction policy {}: {}",
+                extraction_policy.name,
+                e
+            ))
+        })?;
         let serialized_extraction_policy = JsonEncoder::encode(extraction_policy)?;
         txn.put_cf(
             &StateMachineColumns::ExtractionPolicies.cf(db),
@@ -1054,114 +2772,650 @@ impl IndexifyState {
         Ok(())
     }
 
-    pub fn update_content_extraction_policy_state(
+    /// Replaces a schema and records the transform to backfill its existing
+    /// content with. The new schema serves reads as soon as the transaction
+    /// commits
+    fn replace_schema(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
-        content_id: &ContentMetadataId,
-        extraction_policy_id: &str,
-        policy_completion_time: SystemTime,
+        old_id: &SchemaId,
+        new_schema: &internal_api::StructuredDataSchema,
+        transform: &SchemaTransform,
     ) -> Result<(), StateMachineError> {
-        let value = txn
-            .get_cf(
-                StateMachineColumns::ContentTable.cf(db),
-                format!("{}::v{}", content_id.id, content_id.version),
-            )
-            .map_err(|e| {
-                StateMachineError::DatabaseError(format!(
-                    "Error getting the content policies applied on content id {}: {}",
-                    content_id, e
-                ))
-            })?
-            .ok_or_else(|| {
-                StateMachineError::DatabaseError(format!(
-                    "Content not found while updating applied extraction policies {}",
-                    content_id
-                ))
-            })?;
-        let mut content_meta = JsonEncoder::decode::<internal_api::ContentMetadata>(&value)?;
-        let epoch_time = policy_completion_time
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map_err(|e| {
-                StateMachineError::DatabaseError(format!(
-                    "Error converting policy completion time to u64: {}",
-                    e
-                ))
-            })?
-            .as_secs();
-        content_meta
-            .extraction_policy_ids
-            .insert(extraction_policy_id.to_string(), epoch_time);
-        let data = JsonEncoder::encode(&content_meta)?;
+        if *old_id != new_schema.id {
+            txn.delete_cf(StateMachineColumns::StructuredDataSchemas.cf(db), old_id)
+                .map_err(|e| {
+                    StateMachineError::DatabaseError(format!("Error deleting schema: {}", e))
+                })?;
+        }
+        self.set_schema(db, txn, new_schema)?;
         txn.put_cf(
-            StateMachineColumns::ContentTable.cf(db),
-            format!("{}::v{}", content_id.id, content_id.version),
-            data,
+            StateMachineColumns::PendingSchemaBackfills.cf(db),
+            &new_schema.id,
+            JsonEncoder::encode(transform)?,
         )
         .map_err(|e| {
-            StateMachineError::DatabaseError(format!(
-                "Error writing content policies applied on content for id {}: {}",
-                content_id, e
-            ))
+            StateMachineError::DatabaseError(format!("Error writing schema backfill: {}", e))
         })?;
-
         Ok(())
     }
 
-    pub fn set_coordinator_addr(
+    /// Writes the schema and points the namespace's active schema to it
+    fn set_namespace_schema(
         &self,
         db: &Arc<OptimisticTransactionDB>,
         txn: &rocksdb::Transaction<OptimisticTransactionDB>,
-        node_id: NodeId,
-        coordinator_addr: &str,
+        namespace: &str,
+        schema: &internal_api::StructuredDataSchema,
     ) -> Result<(), StateMachineError> {
-        let serialized_coordinator_addr = JsonEncoder::encode(&coordinator_addr)?;
+        self.set_schema(db, txn, schema)?;
         txn.put_cf(
-            StateMachineColumns::CoordinatorAddress.cf(db),
-            node_id.to_string(),
-            serialized_coordinator_addr,
+            StateMachineColumns::ActiveNamespaceSchemas.cf(db),
+            namespace,
+            JsonEncoder::encode(&schema.id)?,
         )
         .map_err(|e| {
-            StateMachineError::DatabaseError(format!(
-                "Error writing coordinator address for node {}: {}",
-                node_id, e
-            ))
+            StateMachineError::DatabaseError(format!("Error writing active schema: {}", e))
         })?;
         Ok(())
     }
 
-    pub fn mark_state_changes_processed(
+    /// Applies the pending transform of a schema to the labels of the content
+    /// in its namespace and content source. Returns the number of content
+    /// updated
+    fn backfill_schema(
         &self,
-        state_change: &StateChangeProcessed,
-        _processed_at: u64,
-    ) {
-        self.unprocessed_state_changes
-            .remove(&state_change.state_change_id);
-    }
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        schema_id: &str,
+    ) -> Result<usize, StateMachineError> {
+        let backfills_cf = StateMachineColumns::PendingSchemaBackfills.cf(db);
+        let transform = match txn.get_cf(backfills_cf, schema_id).map_err(|e| {
+            StateMachineError::DatabaseError(format!("Error reading schema backfill: {}", e))
+        })? {
+            Some(transform) => JsonEncoder::decode::<SchemaTransform>(&transform)?,
+            None => return Ok(0),
+        };
+        let schema = txn
+            .get_cf(StateMachineColumns::StructuredDataSchemas.cf(db), schema_id)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!("Schema {} not found", schema_id))
+            })?;
+        let schema = JsonEncoder::decode::<internal_api::StructuredDataSchema>(&schema)?;
 
-    fn update_schema_reverse_idx(&self, schema: internal_api::StructuredDataSchema) {
-        self.schemas_by_namespace
-            .insert(&schema.namespace, &schema.id);
+        let content_ids = self
+            .content_namespace_table
+            .inner()
+            .get(&schema.namespace)
+            .cloned()
+            .unwrap_or_default();
+        let content: Vec<internal_api::ContentMetadata> = self
+            .get_content_from_ids_with_version(content_ids, db)?
+            .into_iter()
+            .filter(|content| content.source == schema.content_source)
+            .map(|mut content| {
+                transform.apply(&mut content.labels);
+                content
+            })
+            .collect();
+        self.set_content(db, txn, &content)?;
+
+        txn.delete_cf(backfills_cf, schema_id).map_err(|e| {
+            StateMachineError::DatabaseError(format!("Error deleting schema backfill: {}", e))
+        })?;
+        Ok(content.len())
     }
 
-    /// This method will make all state machine forward index writes to RocksDB
-    pub fn apply_state_machine_updates(
+    /// Builds the tasks applying an extraction policy to the content of a
+    /// namespace. Content the policy was already applied to or which already
+    /// has a task of the policy is skipped. The content is read in chunks and
+    /// the tasks are returned ordered by content id, so they're sequenced the
+    /// same way on every node
+    fn backfill_policy_tasks(
         &self,
-        request: StateMachineUpdateRequest,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<(), StateMachineError> {
-        let txn = db.transaction();
-
-        self.set_new_state_changes(db, &txn, &request.new_state_changes)?;
-        self.set_processed_state_changes(db, &txn, &request.state_changes_processed)?;
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        namespace: &str,
+        policy_id: &str,
+        created_at: u64,
+    ) -> Result<Vec<internal_api::Task>, StateMachineError> {
+        let extraction_policy: internal_api::ExtractionPolicy = self
+            .get_from_cf(db, StateMachineColumns::ExtractionPolicies, policy_id)
+            .map_err(StateMachineError::ExternalError)?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!(
+                    "Extraction policy {} not found",
+                    policy_id
+                ))
+            })?;
+        let extractor: ExtractorDescription = self
+            .get_from_cf(
+                db,
+                StateMachineColumns::Extractors,
+                &extraction_policy.extractor,
+            )
+            .map_err(StateMachineError::ExternalError)?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!(
+                    "Extractor {} not found",
+                    extraction_policy.extractor
+                ))
+            })?;
 
-        match &request.payload {
-            RequestPayload::CreateIndex {
-                index,
-                namespace: _,
+        let mut content_ids: Vec<ContentMetadataId> = self
+            .content_namespace_table
+            .inner()
+            .remove(namespace)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        content_ids.sort_by_key(|content_id| content_id.to_string());
+        let mut tasks = Vec::new();
+        for chunk in content_ids.chunks(BACKFILL_CHUNK_SIZE) {
+            let contents = txn.multi_get_cf(chunk.iter().map(|content_id| {
+                (
+                    StateMachineColumns::ContentTable.cf(db),
+                    content_id.to_string(),
+                )
+            }));
+            let mut chunk_tasks = Vec::new();
+            for content in contents {
+                let content =
+                    content.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+                let content = match content {
+                    Some(content) => self.with_applied_policies(
+                        db,
+                        txn,
+                        JsonEncoder::decode::<internal_api::ContentMetadata>(&content)?,
+                    )?,
+                    None => continue,
+                };
+                if content.tombstoned ||
+                    content
+                        .extraction_policy_ids
+                        .contains_key(&extraction_policy.id) ||
+                    !self.policy_matches_content(db, &extraction_policy, &content)?
+                {
+                    continue;
+                }
+                chunk_tasks.push(internal_api::Task::new(
+                    &extraction_policy,
+                    &extractor,
+                    &content,
+                    created_at,
+                ));
+            }
+            let existing_tasks = txn.multi_get_cf(
+                chunk_tasks
+                    .iter()
+                    .map(|task| (StateMachineColumns::Tasks.cf(db), &task.id)),
+            );
+            for (task, existing_task) in chunk_tasks.into_iter().zip(existing_tasks) {
+                let existing_task =
+                    existing_task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+                if existing_task.is_none() {
+                    tasks.push(task);
+                }
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Creates the tasks of the policies which take as input the content a
+    /// policy produced from a content. The produced content is ready once the
+    /// policy is applied on the content it was produced from. Content already
+    /// processed by a downstream policy and existing tasks are skipped
+    fn downstream_policy_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        content_id: &ContentMetadataId,
+        policy_id: &str,
+        created_at: u64,
+    ) -> Result<Vec<internal_api::Task>, StateMachineError> {
+        let applied_policy: internal_api::ExtractionPolicy = self
+            .get_from_cf(db, StateMachineColumns::ExtractionPolicies, policy_id)
+            .map_err(StateMachineError::ExternalError)?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!(
+                    "Extraction policy {} not found",
+                    policy_id
+                ))
+            })?;
+        let mut downstream_policy_ids: Vec<String> = self
+            .extraction_policies_table
+            .get(&applied_policy.namespace)
+            .into_iter()
+            .collect();
+        downstream_policy_ids.sort();
+        let mut downstream_policies = Vec::new();
+        for downstream_policy_id in downstream_policy_ids {
+            let policy: Option<internal_api::ExtractionPolicy> = self
+                .get_from_cf(
+                    db,
+                    StateMachineColumns::ExtractionPolicies,
+                    &downstream_policy_id,
+                )
+                .map_err(StateMachineError::ExternalError)?;
+            let policy = match policy {
+                Some(policy) if policy.content_source == applied_policy.name => policy,
+                _ => continue,
+            };
+            let extractor: Option<ExtractorDescription> = self
+                .get_from_cf(db, StateMachineColumns::Extractors, &policy.extractor)
+                .map_err(StateMachineError::ExternalError)?;
+            if let Some(extractor) = extractor {
+                downstream_policies.push((policy, extractor));
+            }
+        }
+        if downstream_policies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut child_ids: Vec<ContentMetadataId> = self
+            .content_children_table
+            .get_children(content_id)
+            .into_iter()
+            .collect();
+        child_ids.sort_by_key(|child_id| child_id.to_string());
+        let children = txn.multi_get_cf(child_ids.iter().map(|child_id| {
+            (
+                StateMachineColumns::ContentTable.cf(db),
+                child_id.to_string(),
+            )
+        }));
+        let mut tasks = Vec::new();
+        for child in children {
+            let child = child.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let child = match child {
+                Some(child) => self.with_applied_policies(
+                    db,
+                    txn,
+                    JsonEncoder::decode::<internal_api::ContentMetadata>(&child)?,
+                )?,
+                None => continue,
+            };
+            if child.tombstoned {
+                continue;
+            }
+            for (policy, extractor) in &downstream_policies {
+                if child.extraction_policy_ids.contains_key(&policy.id) ||
+                    !self.policy_matches_content(db, policy, &child)?
+                {
+                    continue;
+                }
+                let task = internal_api::Task::new(policy, extractor, &child, created_at);
+                if txn
+                    .get_cf(StateMachineColumns::Tasks.cf(db), &task.id)
+                    .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+                    .is_none()
+                {
+                    tasks.push(task);
+                }
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Cancels the tasks referencing content which no longer exists in its own
+    /// transaction. Returns the ids of the cancelled tasks
+    pub fn cancel_orphaned_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        let txn = db.transaction();
+        let mut post_commit = PostCommitUpdates::default();
+        let cancelled_task_ids = self.cancel_orphaned(db, &txn, &mut post_commit)?;
+        txn.commit()
+            .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+        post_commit.apply(self);
+        Ok(cancelled_task_ids)
+    }
+
+    /// Returns the outcome of the latest heartbeat of an executor
+    pub fn get_heartbeat_outcome(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        executor_id: &str,
+    ) -> Result<Option<HeartbeatOutcome>, StateMachineError> {
+        self.get_from_cf(db, StateMachineColumns::HeartbeatOutcomes, executor_id)
+            .map_err(StateMachineError::ExternalError)
+    }
+
+    /// Returns the task ids reserved under the reservation id
+    pub fn get_task_id_reservation(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        reservation_id: &str,
+    ) -> Result<Option<TaskIdReservation>, StateMachineError> {
+        db.get_cf(
+            StateMachineColumns::TaskIdReservations.cf(db),
+            reservation_id,
+        )
+        .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+        .map(|value| JsonEncoder::decode(&value))
+        .transpose()
+    }
+
+    pub fn update_content_extraction_policy_state(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        content_id: &ContentMetadataId,
+        extraction_policy_id: &str,
+        policy_completion_time: SystemTime,
+    ) -> Result<(), StateMachineError> {
+        self.set_content_policies_applied_on_content(
+            db,
+            txn,
+            &[(content_id.clone(), extraction_policy_id.to_string())],
+            policy_completion_time,
+        )
+    }
+
+    /// Records the extraction policies applied on a batch of content. Each
+    /// policy is merged into the content's ContentExtractionPolicyMapping, so
+    /// neither the content nor the mapping is read
+    fn set_content_policies_applied_on_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        applied_policies: &[(ContentMetadataId, String)],
+        policy_completion_time: SystemTime,
+    ) -> Result<(), StateMachineError> {
+        if applied_policies.is_empty() {
+            return Ok(());
+        }
+        let epoch_time = policy_completion_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_err(|e| {
+                StateMachineError::DatabaseError(format!(
+                    "Error converting policy completion time to u64: {}",
+                    e
+                ))
+            })?
+            .as_secs();
+        for (content_id, extraction_policy_id) in applied_policies {
+            self.mark_extraction_policy_applied_on_content(
+                db,
+                txn,
+                &format!("{}::v{}", content_id.id, content_id.version),
+                extraction_policy_id,
+                epoch_time,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Records the policy as applied on the content in the
+    /// ExtractionPoliciesAppliedOnContent column family. The record is merged
+    /// into the stored mapping by the column family's merge operator, so the
+    /// mapping isn't read
+    fn mark_extraction_policy_applied_on_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        content_key: &str,
+        extraction_policy_name: &str,
+        completion_time_secs: u64,
+    ) -> Result<(), StateMachineError> {
+        let mapping = ContentExtractionPolicyMapping {
+            extraction_policy_names: HashSet::from([extraction_policy_name.to_string()]),
+            time_of_policy_completion: HashMap::from([(
+                extraction_policy_name.to_string(),
+                completion_time_secs,
+            )]),
+        };
+        txn.merge_cf(
+            StateMachineColumns::ExtractionPoliciesAppliedOnContent.cf(db),
+            content_key,
+            JsonEncoder::encode(&mapping)?,
+        )
+        .map_err(|e| {
+            StateMachineError::DatabaseError(format!(
+                "Error merging policies applied on content for id {}: {}",
+                content_key, e
+            ))
+        })
+    }
+
+    pub fn set_coordinator_addr(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        node_id: NodeId,
+        coordinator_addr: &str,
+    ) -> Result<(), StateMachineError> {
+        let serialized_coordinator_addr = JsonEncoder::encode(&coordinator_addr)?;
+        txn.put_cf(
+            StateMachineColumns::CoordinatorAddress.cf(db),
+            numeric_key(node_id),
+            serialized_coordinator_addr,
+        )
+        .map_err(|e| {
+            StateMachineError::DatabaseError(format!(
+                "Error writing coordinator address for node {}: {}",
+                node_id, e
+            ))
+        })?;
+        Ok(())
+    }
+
+    pub fn mark_state_changes_processed(
+        &self,
+        state_change: &StateChangeProcessed,
+        _processed_at: u64,
+    ) {
+        if self
+            .unprocessed_state_changes
+            .remove(&state_change.state_change_id)
+        {
+            self.metrics.lock().unwrap().state_changes_processed += 1;
+        }
+    }
+
+    /// Returns the number of unprocessed and processed state changes without
+    /// scanning. The processed count is kept in the metrics, so it's restored
+    /// along with them from snapshots
+    pub fn state_change_counts(&self) -> (usize, usize) {
+        let processed = self.metrics.lock().unwrap().state_changes_processed;
+        (self.unprocessed_state_changes.count(), processed as usize)
+    }
+
+    fn update_schema_reverse_idx(&self, schema: internal_api::StructuredDataSchema) {
+        self.schemas_by_namespace
+            .insert(&schema.namespace, &schema.id);
+    }
+
+    /// Returns up to `max` unassigned tasks of an extractor. The tasks are
+    /// picked in order of their ids so every replica claims the same ones
+    fn claimable_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        extractors: &[ExtractorDescription],
+        max: usize,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        self.reverse_index_barrier();
+        let assignable_tasks = self.assignable_tasks(db)?;
+        let unfinished_tasks_by_extractor = self.unfinished_tasks_by_extractor.inner();
+        Ok(extractors
+            .iter()
+            .filter_map(|extractor| unfinished_tasks_by_extractor.get(&extractor.name))
+            .flatten()
+            .filter(|task_id| assignable_tasks.contains(*task_id))
+            .cloned()
+            .sorted()
+            .dedup()
+            .take(max)
+            .collect())
+    }
+
+    fn set_namespace_paused(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        namespace: &str,
+        paused: bool,
+    ) -> Result<(), StateMachineError> {
+        let cf = StateMachineColumns::PausedNamespaces.cf(db);
+        let result = if paused {
+            txn.put_cf(cf, namespace, JsonEncoder::encode(&namespace)?)
+        } else {
+            txn.delete_cf(cf, namespace)
+        };
+        result.map_err(|e| {
+            StateMachineError::DatabaseError(format!(
+                "Error updating pause of namespace {}: {}",
+                namespace, e
+            ))
+        })
+    }
+
+    /// This method will make all state machine forward index writes to RocksDB.
+    /// Returns the JSON encoded outcome of the request, if it has one
+    pub fn apply_state_machine_updates(
+        &self,
+        request: StateMachineUpdateRequest,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Option<String>, StateMachineError> {
+        self.apply_state_machine_updates_with_state_changes_db(request, db, db)
+    }
+
+    /// Same as apply_state_machine_updates, returning the ids of the new state
+    /// changes persisted by the request, in the order of the request
+    pub fn apply_state_machine_updates_returning_state_changes(
+        &self,
+        request: StateMachineUpdateRequest,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<StateChangeId>, StateMachineError> {
+        let state_change_ids = request
+            .new_state_changes
+            .iter()
+            .map(|state_change| state_change.id.clone())
+            .collect();
+        self.apply_state_machine_updates(request, db)?;
+        Ok(state_change_ids)
+    }
+
+    /// Same as apply_state_machine_updates, with the state changes written to
+    /// `state_changes_db`. When it's a separate database, the state changes
+    /// are committed after the rest of the update, so that no state change
+    /// refers to an update which failed to commit
+    pub fn apply_state_machine_updates_with_state_changes_db(
+        &self,
+        request: StateMachineUpdateRequest,
+        db: &Arc<OptimisticTransactionDB>,
+        state_changes_db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Option<String>, StateMachineError> {
+        //  The span is exited on every return path, including errors
+        let span = tracing::info_span!(
+            "apply_state_machine_updates",
+            otel.name = request.payload.kind(),
+            correlation_id = request.correlation_id.as_deref(),
+        );
+        let before = self
+            .log_reverse_index_mutations
+            .load(Ordering::Relaxed)
+            .then(|| self.reverse_index_snapshot());
+        let result = span.in_scope(|| self.apply_request(request, db, state_changes_db));
+        if let Some(before) = before {
+            log_reverse_index_mutations(&before, &self.reverse_index_snapshot());
+        }
+        result
+    }
+
+    fn apply_request(
+        &self,
+        request: StateMachineUpdateRequest,
+        db: &Arc<OptimisticTransactionDB>,
+        state_changes_db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Option<String>, StateMachineError> {
+        //  The forward index writes below may read and update the reverse indexes,
+        // so the updates of the previous requests are applied first
+        self.reverse_index_barrier();
+        let txn = db.transaction();
+        let mut outcome = None;
+        let mut post_commit = PostCommitUpdates::default();
+        let compare_and_swap = matches!(
+            &request.payload,
+            RequestPayload::CreateContent {
+                expected_version: Some(_),
+                ..
+            }
+        );
+        let request = self.drop_conflicting_content(db, &txn, request)?;
+        if compare_and_swap {
+            //  A dropped write is replaced with a no-op without an expected version
+            let swapped = matches!(
+                &request.payload,
+                RequestPayload::CreateContent {
+                    expected_version: Some(_),
+                    ..
+                }
+            );
+            outcome = encode_outcome(&swapped)?;
+        }
+
+        let mut state_changes_processed = request.state_changes_processed.clone();
+        if let RequestPayload::MarkStateChangesProcessed { state_changes } = &request.payload {
+            state_changes_processed.extend(state_changes.iter().cloned());
+        }
+        let state_changes_txn = if Arc::ptr_eq(db, state_changes_db) {
+            self.set_new_state_changes(db, &txn, &request.new_state_changes)?;
+            self.set_processed_state_changes(db, &txn, &state_changes_processed)?;
+            None
+        } else {
+            let state_changes_txn = state_changes_db.transaction();
+            self.set_new_state_changes(
+                state_changes_db,
+                &state_changes_txn,
+                &request.new_state_changes,
+            )?;
+            self.set_processed_state_changes(
+                state_changes_db,
+                &state_changes_txn,
+                &state_changes_processed,
+            )?;
+            Some(state_changes_txn)
+        };
+
+        match &request.payload {
+            RequestPayload::CreateIndex {
+                index,
+                namespace: _,
+                id,
+            } => {
+                self.set_index(db, &txn, index, id)?;
+            }
+            RequestPayload::CreateIndexWithSchema {
+                index,
+                namespace: _,
                 id,
+                schema,
             } => {
                 self.set_index(db, &txn, index, id)?;
+                self.set_schema(db, &txn, schema)?;
+            }
+            RequestPayload::CreateIndexes {
+                indexes,
+                namespace: _,
+            } => {
+                let mut ids = HashSet::new();
+                for (id, index) in indexes {
+                    if !ids.insert(id) {
+                        warn!("skipping duplicate index id {} in batch", id);
+                        continue;
+                    }
+                    self.set_index(db, &txn, index, id)?;
+                }
+            }
+            RequestPayload::PromoteIndexGeneration {
+                index_id,
+                generation,
+            } => {
+                self.set_active_index_generation(db, &txn, index_id, *generation)?;
             }
             RequestPayload::CreateTasks { tasks } => {
                 self.set_tasks(db, &txn, tasks)?;
@@ -1180,24 +3434,132 @@ impl IndexifyState {
                 }
             }
             RequestPayload::AssignTask { assignments } => {
-                let assignments: HashMap<&String, HashSet<TaskId>> =
-                    assignments
-                        .iter()
-                        .fold(HashMap::new(), |mut acc, (task_id, executor_id)| {
-                            acc.entry(executor_id).or_default().insert(task_id.clone());
-                            acc
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the costs of the tasks are only known from the forward index
+                self.set_task_executors(db, &txn, assignments)?;
+                self.add_task_assignments(db, &txn, assignments)?;
+                let mut weights = Vec::new();
+                for (task_id, executor_id) in assignments {
+                    weights.push((executor_id, self.tasks_weight(db, &txn, [task_id])?));
+                }
+                post_commit.push(move |state| {
+                    for (executor_id, weight) in weights {
+                        state.executor_weighted_load.add(executor_id, weight);
+                    }
+                });
+            }
+            RequestPayload::AssignTaskTo {
+                task_id,
+                executor_id,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since only a task which is still unassigned is assigned
+                match self.task_executor(db, task_id)? {
+                    Some(assigned_to) => {
+                        tracing::warn!(
+                            "dropping assignment of task {} to executor {}: assigned to executor {}",
+                            task_id,
+                            executor_id,
+                            assigned_to
+                        );
+                    }
+                    None => {
+                        let assignments = HashMap::from([(task_id.clone(), executor_id.clone())]);
+                        self.set_task_executors(db, &txn, &assignments)?;
+                        self.add_task_assignments(db, &txn, &assignments)?;
+                        let weight = self.tasks_weight(db, &txn, [task_id])?;
+                        post_commit.push(move |state| {
+                            state.unassigned_tasks.remove(task_id);
+                            state
+                                .executor_running_task_count
+                                .increment_running_task_count(executor_id);
+                            state.executor_weighted_load.add(executor_id, weight);
+                            state.executor_idle_since.remove(executor_id);
                         });
-
-                // FIXME - Write a test which assigns tasks mutliple times to the same executor
-                // and make sure it's additive.
-
-                for (executor_id, tasks) in assignments.iter() {
-                    let mut existing_tasks =
-                        self.get_task_assignments_for_executor(db, &txn, executor_id)?;
-                    existing_tasks.extend(tasks.clone());
-                    let task_assignment =
-                        HashMap::from([(executor_id.to_string(), existing_tasks)]);
-                    self.set_task_assignments(db, &txn, &task_assignment)?;
+                    }
+                }
+            }
+            RequestPayload::MarkTaskStarted {
+                task_id,
+                executor_id,
+                ts_secs,
+            } => match self.task_executor(db, task_id)? {
+                Some(assigned_to) if assigned_to == *executor_id => {
+                    let mut task = self._get_task(db, &txn, task_id)?;
+                    if task.terminal_state() {
+                        tracing::info!("ignoring start of task {} which already finished", task_id);
+                    } else {
+                        task.started_at = Some(*ts_secs);
+                        self.update_tasks(db, &txn, vec![&task], SystemTime::UNIX_EPOCH)?;
+                    }
+                }
+                assigned_to => {
+                    tracing::warn!(
+                        "dropping start of task {} by executor {}: assigned to executor {:?}",
+                        task_id,
+                        executor_id,
+                        assigned_to
+                    );
+                }
+            },
+            RequestPayload::RequestTaskCancel { task_id } => {
+                match self._find_task(db, &txn, task_id)? {
+                    None => {
+                        tracing::warn!("dropping cancellation of unknown task {}", task_id);
+                    }
+                    Some(task) if task.terminal_state() => {}
+                    Some(mut task) => match self.task_executor(db, task_id)? {
+                        //  The executor finalizes the cancellation once it has stopped the task
+                        Some(_) => {
+                            task.cancel_requested = true;
+                            self.update_tasks(db, &txn, vec![&task], SystemTime::UNIX_EPOCH)?;
+                        }
+                        //  NOTE: Special case where forward and reverse indexes are updated
+                        // together since no executor will acknowledge the cancellation of an
+                        // unassigned task
+                        None => {
+                            self.finalize_task_cancellation(
+                                db,
+                                &txn,
+                                task,
+                                None,
+                                SystemTime::UNIX_EPOCH,
+                                &mut post_commit,
+                            )?;
+                        }
+                    },
+                }
+            }
+            RequestPayload::UpdateTask {
+                task,
+                executor_id,
+                content_metadata: _,
+                update_time,
+                cancelled: true,
+                artifact_ref: _,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the cancellation is ignored if the task has already finished
+                match self._find_task(db, &txn, &task.id)? {
+                    None => {
+                        tracing::warn!("dropping cancellation of unknown task {}", task.id);
+                    }
+                    Some(stored_task) if stored_task.terminal_state() => {
+                        tracing::info!(
+                            "ignoring cancellation of task {} which already finished",
+                            task.id
+                        );
+                    }
+                    Some(stored_task) => {
+                        self.finalize_task_cancellation(
+                            db,
+                            &txn,
+                            stored_task,
+                            executor_id.as_ref(),
+                            *update_time,
+                            &mut post_commit,
+                        )?;
+                    }
                 }
             }
             RequestPayload::UpdateTask {
@@ -1205,9 +3567,14 @@ impl IndexifyState {
                 executor_id,
                 content_metadata,
                 update_time,
+                cancelled: _,
+                artifact_ref,
             } => {
                 self.update_tasks(db, &txn, vec![task], *update_time)?;
                 self.set_content(db, &txn, content_metadata)?;
+                if let Some(artifact_ref) = artifact_ref {
+                    self.set_task_artifact_ref(db, &txn, &task.id, artifact_ref)?;
+                }
 
                 if task.terminal_state() {
                     self.metrics
@@ -1224,20 +3591,130 @@ impl IndexifyState {
                         let new_task_assignment =
                             HashMap::from([(executor_id.to_string(), existing_tasks)]);
                         self.set_task_assignments(db, &txn, &new_task_assignment)?;
+                        self.delete_task_executors(db, &txn, &[task.id.clone()])?;
                     }
                 }
             }
-            RequestPayload::RegisterExecutor {
+            RequestPayload::FinishTasks {
+                executor_id,
+                finished,
+                update_time,
+            } => {
+                self.update_tasks(
+                    db,
+                    &txn,
+                    finished.iter().map(|(task, _)| task).collect(),
+                    *update_time,
+                )?;
+                for (_, content_metadata) in finished {
+                    if let Some(content_metadata) = content_metadata {
+                        self.set_content(db, &txn, content_metadata)?;
+                    }
+                }
+
+                let finished_task_ids: Vec<TaskId> = finished
+                    .iter()
+                    .filter(|(task, _)| task.terminal_state())
+                    .map(|(task, _)| task.id.clone())
+                    .collect();
+                let mut existing_tasks =
+                    self.get_task_assignments_for_executor(db, &txn, executor_id)?;
+                for task_id in &finished_task_ids {
+                    existing_tasks.remove(task_id);
+                }
+                self.set_task_assignments(
+                    db,
+                    &txn,
+                    &HashMap::from([(executor_id.to_string(), existing_tasks)]),
+                )?;
+                self.delete_task_executors(db, &txn, &finished_task_ids)?;
+
+                let mut metrics = self.metrics.lock().unwrap();
+                for (task, _) in finished.iter().filter(|(task, _)| task.terminal_state()) {
+                    metrics.update_task_completion(task.outcome);
+                }
+            }
+            RequestPayload::RegisterExecutor {
                 addr,
                 executor_id,
-                extractor,
+                extractors,
                 ts_secs,
+                labels,
             } => {
                 //  Insert the executor
-                self.set_executor(db, &txn, addr.into(), executor_id, extractor, ts_secs)?;
+                self.set_executor(
+                    db,
+                    &txn,
+                    &internal_api::ExecutorMetadata {
+                        id: executor_id.clone(),
+                        last_seen: *ts_secs,
+                        addr: addr.clone(),
+                        extractors: extractors.clone(),
+                        labels: labels.clone(),
+                    },
+                )?;
+
+                //  Insert the associated extractors
+                for extractor in extractors {
+                    self.set_extractor(db, &txn, executor_id, extractor)?;
+                }
+            }
+            RequestPayload::RegisterExecutorAndClaim {
+                addr,
+                executor_id,
+                extractors,
+                ts_secs,
+                max,
+                labels,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the claimed tasks are picked from the reverse indexes
+                self.set_executor(
+                    db,
+                    &txn,
+                    &internal_api::ExecutorMetadata {
+                        id: executor_id.clone(),
+                        last_seen: *ts_secs,
+                        addr: addr.clone(),
+                        extractors: extractors.clone(),
+                        labels: labels.clone(),
+                    },
+                )?;
+                for extractor in extractors {
+                    self.set_extractor(db, &txn, executor_id, extractor)?;
+                }
 
-                //  Insert the associated extractor
-                self.set_extractor(db, &txn, extractor)?;
+                let claimed_task_ids = self.claimable_tasks(db, extractors, *max)?;
+                let task_executors: HashMap<TaskId, ExecutorId> = claimed_task_ids
+                    .iter()
+                    .map(|task_id| (task_id.clone(), executor_id.clone()))
+                    .collect();
+                self.set_task_executors(db, &txn, &task_executors)?;
+                let mut assigned_tasks =
+                    self.get_task_assignments_for_executor(db, &txn, executor_id)?;
+                assigned_tasks.extend(claimed_task_ids.iter().cloned());
+                let running_task_count = assigned_tasks.len();
+                let weighted_load = self.tasks_weight(db, &txn, &assigned_tasks)?;
+                let task_assignment = HashMap::from([(executor_id.clone(), assigned_tasks)]);
+                self.set_task_assignments(db, &txn, &task_assignment)?;
+
+                outcome = encode_outcome(&claimed_task_ids)?;
+                post_commit.push(move |state| {
+                    for extractor in extractors {
+                        state
+                            .extractor_executors_table
+                            .insert(&extractor.name, executor_id);
+                    }
+                    state
+                        .executor_running_task_count
+                        .insert(executor_id, running_task_count);
+                    state
+                        .executor_weighted_load
+                        .insert(executor_id, weighted_load);
+                    for task_id in &claimed_task_ids {
+                        state.unassigned_tasks.remove(task_id);
+                    }
+                });
             }
             RequestPayload::RemoveExecutor { executor_id } => {
                 //  NOTE: Special case where forward and reverse indexes are updated together
@@ -1247,25 +3724,90 @@ impl IndexifyState {
 
                 // Remove all tasks assigned to this executor and get a handle on the task ids
                 let task_ids = self.delete_task_assignments_for_executor(db, &txn, executor_id)?;
+                self.delete_task_executors(db, &txn, &task_ids)?;
+
+                post_commit.push(move |state| {
+                    //  Remove the the extractors from the executor -> extractor mapping table
+                    for extractor in &executor_meta.extractors {
+                        state
+                            .extractor_executors_table
+                            .remove(&extractor.name, &executor_meta.id);
+                    }
 
-                txn.commit()
-                    .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
-
-                //  Remove the the extractor from the executor -> extractor mapping table
-                self.extractor_executors_table
-                    .remove(&executor_meta.extractor.name, &executor_meta.id);
-
-                //  Put the tasks of the deleted executor into the unassigned tasks list
-                for task_id in task_ids {
-                    self.unassigned_tasks.insert(&task_id);
-                }
-
-                // Remove from the executor load table
-                self.executor_running_task_count.remove(executor_id);
+                    //  Put the tasks of the deleted executor into the unassigned tasks list
+                    for task_id in task_ids {
+                        state.unassigned_tasks.insert(&task_id);
+                    }
 
-                return Ok(());
+                    // Remove from the executor load table
+                    state.executor_running_task_count.remove(executor_id);
+                    state.executor_weighted_load.remove(executor_id);
+                    state.executor_idle_since.remove(executor_id);
+                });
+            }
+            RequestPayload::ReapStaleExecutors { now_secs, ttl_secs } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the stale executors are only known from the forward index
+                let reaped = self.reap_stale(db, &txn, *now_secs, *ttl_secs)?;
+                post_commit.push(move |state| {
+                    for (executor_meta, task_ids) in reaped {
+                        for extractor in &executor_meta.extractors {
+                            state
+                                .extractor_executors_table
+                                .remove(&extractor.name, &executor_meta.id);
+                        }
+                        for task_id in &task_ids {
+                            state.unassigned_tasks.insert(task_id);
+                        }
+                        state.executor_running_task_count.remove(&executor_meta.id);
+                        state.executor_weighted_load.remove(&executor_meta.id);
+                        state.executor_idle_since.remove(&executor_meta.id);
+                        tracing::info!(
+                            "reaped stale executor {}, requeued tasks: {:?}",
+                            executor_meta.id,
+                            task_ids
+                        );
+                    }
+                });
+            }
+            RequestPayload::CancelContentTasks { content_id } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the executors of the cancelled tasks are only known from the forward
+                // index
+                let cancelled_tasks = self.cancel_content_tasks(db, &txn, content_id)?;
+                post_commit.push(move |state| state.remove_deleted_tasks(cancelled_tasks));
             }
-            RequestPayload::CreateContent { content_metadata } => {
+            RequestPayload::CancelPolicyTasks {
+                extraction_policy_id,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the executors of the cancelled tasks are only known from the forward
+                // index
+                let cancelled_tasks = self.cancel_policy_tasks(db, &txn, extraction_policy_id)?;
+                post_commit.push(move |state| state.remove_deleted_tasks(cancelled_tasks));
+            }
+            RequestPayload::DeleteTasks { task_ids } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the reverse index entries are only known from the stored tasks
+                let deleted_tasks = self.delete_tasks(db, &txn, task_ids)?;
+                post_commit.push(move |state| state.remove_deleted_tasks(deleted_tasks));
+            }
+            RequestPayload::ArchiveFinishedTasks {
+                older_than_secs,
+                now_secs,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the archived tasks are only known from the forward index
+                let archived_tasks =
+                    self.archive_finished_tasks(db, &txn, *older_than_secs, *now_secs)?;
+                outcome = encode_outcome(&archived_tasks.len())?;
+                post_commit.push(move |state| state.remove_deleted_tasks(archived_tasks));
+            }
+            RequestPayload::CreateContent {
+                content_metadata,
+                expected_version: _,
+            } => {
+                self.check_immutable_content_overwrite(db, &txn, content_metadata)?;
                 self.set_content(db, &txn, content_metadata)?;
             }
             RequestPayload::UpdateContent { content_metadata } => {
@@ -1297,9 +3839,203 @@ impl IndexifyState {
             } => {
                 self.set_namespace(db, &txn, name, structured_data_schema)?;
             }
-            RequestPayload::MarkStateChangesProcessed { state_changes } => {
-                self.set_processed_state_changes(db, &txn, state_changes)?;
+            RequestPayload::PauseNamespace { namespace } => {
+                self.set_namespace_paused(db, &txn, namespace, true)?;
+            }
+            RequestPayload::UnpauseNamespace { namespace } => {
+                self.set_namespace_paused(db, &txn, namespace, false)?;
+            }
+            RequestPayload::ReplaceSchema {
+                old_id,
+                new_schema,
+                transform,
+            } => {
+                self.replace_schema(db, &txn, old_id, new_schema, transform)?;
+            }
+            RequestPayload::SetNamespaceSchema { namespace, schema } => {
+                self.set_namespace_schema(db, &txn, namespace, schema)?;
+            }
+            RequestPayload::RunSchemaBackfill { schema_id } => {
+                let updated = self.backfill_schema(db, &txn, schema_id)?;
+                outcome = encode_outcome(&updated)?;
             }
+            RequestPayload::BackfillPolicy {
+                namespace,
+                policy_id,
+                created_at,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the tasks are created by the state machine
+                let tasks =
+                    self.backfill_policy_tasks(db, &txn, namespace, policy_id, *created_at)?;
+                self.set_tasks(db, &txn, &tasks)?;
+                post_commit.push(move |state| state.add_unfinished_tasks(&tasks));
+            }
+            RequestPayload::MarkPolicyAppliedAndSpawn {
+                content_id,
+                policy_id,
+                created_at,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the downstream tasks are created by the state machine
+                let completion_time = SystemTime::UNIX_EPOCH + Duration::from_secs(*created_at);
+                self.set_content_policies_applied_on_content(
+                    db,
+                    &txn,
+                    &[(content_id.clone(), policy_id.clone())],
+                    completion_time,
+                )?;
+                let tasks =
+                    self.downstream_policy_tasks(db, &txn, content_id, policy_id, *created_at)?;
+                self.set_tasks(db, &txn, &tasks)?;
+                post_commit.push(move |state| state.add_unfinished_tasks(&tasks));
+            }
+            RequestPayload::CancelOrphanedTasks => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the orphaned tasks are found from the reverse indexes
+                self.cancel_orphaned(db, &txn, &mut post_commit)?;
+            }
+            RequestPayload::ReconcileUnassignedTasks => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the orphaned tasks are found from the reverse indexes
+                let reconciled_task_ids = self.reconcile_unassigned(db, &txn, &mut post_commit)?;
+                outcome = encode_outcome(&reconciled_task_ids)?;
+            }
+            RequestPayload::RepairTaskExecutorIndex => {
+                let repaired_task_ids = self.repair_task_executor_index(db, &txn)?;
+                outcome = encode_outcome(&repaired_task_ids)?;
+            }
+            RequestPayload::ReserveTaskIds {
+                reservation_id,
+                count,
+            } => {
+                self.reserve_task_ids(db, &txn, reservation_id, *count)?;
+            }
+            RequestPayload::RecomputeExecutorLoad { executor_id } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the running count is recomputed from the task assignments
+                let assigned_tasks =
+                    self.get_task_assignments_for_executor(db, &txn, executor_id)?;
+                let weighted_load = self.tasks_weight(db, &txn, &assigned_tasks)?;
+                post_commit.push(move |state| {
+                    state
+                        .executor_running_task_count
+                        .insert(executor_id, assigned_tasks.len());
+                    state
+                        .executor_weighted_load
+                        .insert(executor_id, weighted_load);
+                });
+            }
+            RequestPayload::RetargetTasks {
+                from_extractor,
+                to_extractor,
+                task_ids,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since only the tasks which are still unassigned are retargeted
+                self.retarget_tasks(
+                    db,
+                    &txn,
+                    from_extractor,
+                    to_extractor,
+                    task_ids,
+                    &mut post_commit,
+                )?;
+            }
+            RequestPayload::UpdateExtractor {
+                from_extractor,
+                to_extractor,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the renamed tasks are only known from the reverse indexes
+                self.rename_extractor(db, &txn, from_extractor, to_extractor, &mut post_commit)?;
+            }
+            RequestPayload::SwapExecutorWorkloads { from, to } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since the running counts are recomputed from the task assignments
+                let assigned_tasks = self.swap_executor_workloads(db, &txn, from, to)?;
+                let task_count = assigned_tasks.len();
+                let weighted_load = self.tasks_weight(db, &txn, &assigned_tasks)?;
+                post_commit.push(move |state| {
+                    state.executor_running_task_count.insert(from, 0);
+                    state.executor_running_task_count.insert(to, task_count);
+                    state.executor_weighted_load.insert(from, 0);
+                    state.executor_weighted_load.insert(to, weighted_load);
+                    if task_count > 0 {
+                        state.executor_idle_since.remove(to);
+                    }
+                });
+            }
+            RequestPayload::Heartbeat {
+                executor_id,
+                ts_secs,
+                ttl_secs,
+            } => {
+                self.heartbeat(db, &txn, executor_id, *ts_secs, *ttl_secs)?;
+            }
+            RequestPayload::ExecutorHeartbeat {
+                executor_id,
+                ts_secs,
+                completed_task_updates,
+                update_time,
+            } => {
+                //  NOTE: Special case where forward and reverse indexes are updated together
+                // since only the finished tasks assigned to the executor are completed
+                self.set_executor_last_seen(db, &txn, executor_id, *ts_secs)?;
+                let mut assigned_tasks =
+                    self.get_task_assignments_for_executor(db, &txn, executor_id)?;
+                let completed_tasks: Vec<&internal_api::Task> = completed_task_updates
+                    .iter()
+                    .filter(|task| {
+                        if !task.terminal_state() {
+                            warn!(
+                                "ignoring heartbeat update of task {} which hasn't finished",
+                                task.id
+                            );
+                            return false;
+                        }
+                        if !assigned_tasks.contains(&task.id) {
+                            warn!(
+                                "ignoring heartbeat update of task {} which isn't assigned to executor {}",
+                                task.id, executor_id
+                            );
+                            return false;
+                        }
+                        true
+                    })
+                    .collect();
+                self.update_tasks(db, &txn, completed_tasks.clone(), *update_time)?;
+                let completed_task_ids: Vec<TaskId> =
+                    completed_tasks.iter().map(|task| task.id.clone()).collect();
+                for task_id in &completed_task_ids {
+                    assigned_tasks.remove(task_id);
+                }
+                self.set_task_assignments(
+                    db,
+                    &txn,
+                    &HashMap::from([(executor_id.clone(), assigned_tasks)]),
+                )?;
+                self.delete_task_executors(db, &txn, &completed_task_ids)?;
+
+                post_commit.push(move |state| {
+                    let mut metrics = state.metrics.lock().unwrap();
+                    for task in completed_tasks {
+                        metrics.update_task_completion(task.outcome);
+                        state.unassigned_tasks.remove(&task.id);
+                        state
+                            .unfinished_tasks_by_extractor
+                            .remove(&task.extractor, &task.id);
+                        state.pending_tasks_for_content.remove(
+                            &task.content_metadata.id,
+                            &task.extraction_policy_id,
+                            &task.id,
+                        );
+                        state.finish_running_task(executor_id, task, *update_time);
+                    }
+                });
+            }
+            //  Written along with the state changes processed by the request
+            RequestPayload::MarkStateChangesProcessed { state_changes: _ } => {}
             RequestPayload::JoinCluster {
                 node_id,
                 address: _,
@@ -1309,17 +4045,132 @@ impl IndexifyState {
             }
         };
 
-        self.apply(request).map_err(|e| {
-            StateMachineError::ExternalError(anyhow!(
-                "Error while applying reverse index updates: {}",
-                e
-            ))
-        })?;
-
         txn.commit()
             .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+        commit_state_changes(state_changes_txn)?;
+        post_commit.apply(self);
+
+        //  The reverse index updates are only queued once the forward index writes
+        // are committed
+        if let Some(request) = self.reverse_index_queue.push(request) {
+            self.apply(request).map_err(|e| {
+                StateMachineError::ExternalError(anyhow!(
+                    "Error while applying reverse index updates: {}",
+                    e
+                ))
+            })?;
+        }
 
-        Ok(())
+        Ok(outcome)
+    }
+
+    /// Starts a background thread applying the reverse index updates of the
+    /// writes, so that apply_state_machine_updates returns without waiting on
+    /// them. Readers call reverse_index_barrier to observe the updates of the
+    /// writes that have returned. The thread stops when the state is dropped
+    pub fn start_reverse_index_worker(self: &Arc<Self>) {
+        let (sender, receiver) = std::sync::mpsc::channel::<StateMachineUpdateRequest>();
+        let state = Arc::downgrade(self);
+        std::thread::spawn(move || {
+            for request in receiver {
+                let state = match state.upgrade() {
+                    Some(state) => state,
+                    None => break,
+                };
+                if let Err(e) = state.apply(request) {
+                    error!("error while applying reverse index updates: {}", e);
+                }
+                state.reverse_index_queue.complete();
+            }
+        });
+        *self.reverse_index_queue.sender.lock().unwrap() = Some(sender);
+    }
+
+    /// Logs at debug level every reverse index mutation of the requests
+    /// applied from now on. The reverse indexes are snapshotted around each
+    /// request, so it's only meant for debugging drift
+    pub fn enable_reverse_index_mutation_logging(&self) {
+        self.log_reverse_index_mutations
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// Prunes the StateChangeSequence entries of the state changes processed
+    /// more than `retention_secs` before the latest processed one
+    pub fn set_state_change_retention(&self, retention_secs: u64) {
+        *self.state_change_retention_secs.lock().unwrap() = Some(retention_secs);
+    }
+
+    /// Waits until the background worker has applied every queued reverse
+    /// index update. Returns immediately when no worker is started
+    pub fn reverse_index_barrier(&self) {
+        self.reverse_index_queue.wait_until_drained();
+    }
+
+    /// Moves the unfinished tasks of a renamed extractor from its old name to
+    /// its new name in the reverse index, once RetargetTasks or
+    /// UpdateExtractor has updated the stored tasks
+    pub fn rekey_unfinished_tasks(&self, old: &ExtractorName, new: &ExtractorName) {
+        self.unfinished_tasks_by_extractor.rekey(old, new);
+    }
+
+    /// Decrements the load of an executor which finished a task, recording the
+    /// time it became idle at when it was its last running task
+    fn finish_running_task(
+        &self,
+        executor_id: &ExecutorId,
+        task: &internal_api::Task,
+        finished_at: SystemTime,
+    ) {
+        let was_running = self
+            .executor_running_task_count
+            .get(executor_id)
+            .map_or(false, |count| count > 0);
+        self.executor_running_task_count
+            .decrement_running_task_count(executor_id);
+        self.executor_weighted_load
+            .subtract(executor_id, task.weight());
+        if was_running && self.executor_running_task_count.get(executor_id) == Some(0) {
+            let idle_since = finished_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            self.executor_idle_since.insert(executor_id, idle_since);
+        }
+    }
+
+    /// Removes deleted tasks from the reverse indexes, along with their load on
+    /// the executors they were assigned to
+    fn remove_deleted_tasks(&self, deleted_tasks: Vec<(internal_api::Task, Option<ExecutorId>)>) {
+        for (task, executor_id) in deleted_tasks {
+            self.unassigned_tasks.remove(&task.id);
+            self.unfinished_tasks_by_extractor
+                .remove(&task.extractor, &task.id);
+            self.pending_tasks_for_content.remove(
+                &task.content_metadata.id,
+                &task.extraction_policy_id,
+                &task.id,
+            );
+            if let Some(executor_id) = executor_id {
+                self.executor_running_task_count
+                    .decrement_running_task_count(&executor_id);
+                self.executor_weighted_load
+                    .subtract(&executor_id, task.weight());
+            }
+        }
+    }
+
+    /// Adds newly created tasks to the reverse indexes of unfinished tasks
+    fn add_unfinished_tasks(&self, tasks: &[internal_api::Task]) {
+        for task in tasks {
+            self.unassigned_tasks.insert(&task.id);
+            self.unfinished_tasks_by_extractor
+                .insert(&task.extractor, &task.id);
+            self.pending_tasks_for_content.insert(
+                &task.content_metadata.id,
+                &task.extraction_policy_id,
+                &task.id,
+            );
+        }
     }
 
     /// This method handles all reverse index writes. All reverse indexes are
@@ -1333,34 +4184,23 @@ impl IndexifyState {
         }
         match request.payload {
             RequestPayload::RegisterExecutor {
-                addr,
+                addr: _,
                 executor_id,
-                extractor,
-                ts_secs,
+                extractors,
+                ts_secs: _,
+                labels: _,
             } => {
-                self.extractor_executors_table
-                    .insert(&extractor.name, &executor_id);
-                let _executor_info = internal_api::ExecutorMetadata {
-                    id: executor_id.clone(),
-                    last_seen: ts_secs,
-                    addr: addr.clone(),
-                    extractor: extractor.clone(),
-                };
+                for extractor in extractors {
+                    self.extractor_executors_table
+                        .insert(&extractor.name, &executor_id);
+                }
                 // initialize executor load at 0
                 self.executor_running_task_count.insert(&executor_id, 0);
+                self.executor_weighted_load.insert(&executor_id, 0);
                 Ok(())
             }
             RequestPayload::CreateTasks { tasks } => {
-                for task in tasks {
-                    self.unassigned_tasks.insert(&task.id);
-                    self.unfinished_tasks_by_extractor
-                        .insert(&task.extractor, &task.id);
-                    self.pending_tasks_for_content.insert(
-                        &task.content_metadata.id,
-                        &task.extraction_policy_id,
-                        &task.id,
-                    );
-                }
+                self.add_unfinished_tasks(&tasks);
                 Ok(())
             }
             RequestPayload::AssignTask { assignments } => {
@@ -1369,6 +4209,7 @@ impl IndexifyState {
 
                     self.executor_running_task_count
                         .increment_running_task_count(&executor_id);
+                    self.executor_idle_since.remove(&executor_id);
                 }
                 Ok(())
             }
@@ -1379,10 +4220,14 @@ impl IndexifyState {
             } => {
                 if mark_finished {
                     self.content_children_table.remove_all(&gc_task.content_id);
+                    self.content_chunks_table.remove_all(&gc_task.content_id);
                 }
                 Ok(())
             }
-            RequestPayload::CreateContent { content_metadata } => {
+            RequestPayload::CreateContent {
+                content_metadata,
+                expected_version: _,
+            } => {
                 for content in content_metadata {
                     self.content_namespace_table
                         .insert(&content.namespace, &content.id);
@@ -1390,6 +4235,15 @@ impl IndexifyState {
                         self.content_children_table
                             .insert(&content.parent_id, &content.id);
                     }
+                    if let (Some(source_content_id), Some(chunk_index)) =
+                        (&content.source_content_id, content.chunk_index)
+                    {
+                        self.content_chunks_table.insert(
+                            source_content_id,
+                            chunk_index,
+                            &content.id,
+                        );
+                    }
                     let mut guard = self.metrics.lock().unwrap();
                     if content.parent_id.id.is_empty() {
                         guard.content_uploads += 1;
@@ -1437,6 +4291,23 @@ impl IndexifyState {
                 self.update_schema_reverse_idx(structured_data_schema);
                 Ok(())
             }
+            RequestPayload::ReplaceSchema {
+                old_id,
+                new_schema,
+                transform: _,
+            } => {
+                self.schemas_by_namespace
+                    .remove(&new_schema.namespace, &old_id);
+                self.update_schema_reverse_idx(new_schema);
+                Ok(())
+            }
+            RequestPayload::SetNamespaceSchema {
+                namespace: _,
+                schema,
+            } => {
+                self.update_schema_reverse_idx(schema);
+                Ok(())
+            }
             RequestPayload::CreateIndex {
                 index: _,
                 namespace,
@@ -1445,19 +4316,37 @@ impl IndexifyState {
                 self.namespace_index_table.insert(&namespace, &id);
                 Ok(())
             }
+            RequestPayload::CreateIndexWithSchema {
+                index: _,
+                namespace,
+                id,
+                schema,
+            } => {
+                self.namespace_index_table.insert(&namespace, &id);
+                self.update_schema_reverse_idx(schema);
+                Ok(())
+            }
+            RequestPayload::CreateIndexes { indexes, namespace } => {
+                for (id, _) in indexes {
+                    self.namespace_index_table.insert(&namespace, &id);
+                }
+                Ok(())
+            }
             RequestPayload::UpdateTask {
                 task,
                 executor_id,
                 content_metadata,
-                update_time: _,
+                update_time,
+                cancelled,
+                artifact_ref: _,
             } => {
-                if task.terminal_state() {
+                //  Cancellations update the reverse indexes along with the forward indexes
+                if task.terminal_state() && !cancelled {
                     self.unassigned_tasks.remove(&task.id);
                     self.unfinished_tasks_by_extractor
                         .remove(&task.extractor, &task.id);
                     if let Some(executor_id) = executor_id {
-                        self.executor_running_task_count
-                            .decrement_running_task_count(&executor_id);
+                        self.finish_running_task(&executor_id, &task, update_time);
                     }
                     let content_id = task.content_metadata.id;
                     self.pending_tasks_for_content.remove(
@@ -1472,6 +4361,30 @@ impl IndexifyState {
                 }
                 Ok(())
             }
+            RequestPayload::FinishTasks {
+                executor_id,
+                finished,
+                update_time,
+            } => {
+                for (task, content_metadata) in finished {
+                    if task.terminal_state() {
+                        self.unassigned_tasks.remove(&task.id);
+                        self.unfinished_tasks_by_extractor
+                            .remove(&task.extractor, &task.id);
+                        self.finish_running_task(&executor_id, &task, update_time);
+                        self.pending_tasks_for_content.remove(
+                            &task.content_metadata.id,
+                            &task.extraction_policy_id,
+                            &task.id,
+                        );
+                    }
+                    for content in content_metadata.unwrap_or_default() {
+                        self.content_namespace_table
+                            .insert(&content.namespace, &content.id);
+                    }
+                }
+                Ok(())
+            }
             RequestPayload::MarkStateChangesProcessed { state_changes } => {
                 for state_change in state_changes {
                     self.mark_state_changes_processed(&state_change, state_change.processed_at);
@@ -1551,617 +4464,7582 @@ impl IndexifyState {
         Ok(Some(result))
     }
 
-    /// This method is used to get the tasks assigned to an executor
-    /// It does this by looking up the TaskAssignments CF to get the task id's
-    /// and then using those id's to look up tasks via Tasks CF
-    pub fn get_tasks_for_executor(
+    /// Checks that the entities referenced by the unprocessed state changes
+    /// exist and returns the dangling references, sorted by state change id,
+    /// with the reason they are dangling. Executor changes aren't checked since
+    /// executors are routinely removed before their changes are processed.
+    /// `state_changes_db` is the database holding the state changes, which
+    /// may be separate from the one holding the entities
+    pub fn validate_state_change_references(
         &self,
-        executor_id: &str,
-        limit: Option<u64>,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::Task>, StateMachineError> {
-        //  NOTE: Don't do deserialization within the transaction
-        let txn = db.transaction();
-        let task_ids_bytes = txn
-            .get_cf(StateMachineColumns::TaskAssignments.cf(db), executor_id)
-            .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
-
-        let task_ids: Vec<String> = task_ids_bytes
-            .map(|task_id_bytes| {
-                JsonEncoder::decode(&task_id_bytes)
-                    .map_err(StateMachineError::from)
-                    .unwrap_or_else(|e| {
-                        error!("Failed to deserialize task id: {}", e);
-                        Vec::new()
-                    })
-            })
-            .unwrap_or_else(Vec::new);
-
-        // FIXME Use MULTIGET
-        let limit = limit.unwrap_or(task_ids.len() as u64) as usize;
+        state_changes_db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<(StateChangeId, String)>, StateMachineError> {
+        let content_missing = |content_id: &str| -> Result<Option<String>, StateMachineError> {
+            let content_id = ContentMetadataId::try_from(content_id.to_string())
+                .map_err(StateMachineError::ExternalError)?;
+            if self.content_exists(db, &content_id)? {
+                Ok(None)
+            } else {
+                Ok(Some(format!("content {} not found", content_id)))
+            }
+        };
+        let key_missing = |column: StateMachineColumns, key: &str| {
+            db.get_pinned_cf(column.cf(db), key)
+                .map(|value| value.is_none())
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))
+        };
 
-        let tasks: Result<Vec<indexify_internal_api::Task>, StateMachineError> = task_ids
-            .into_iter()
-            .take(limit)
-            .map(|task_id| {
-                let task_bytes = txn
-                    .get_cf(StateMachineColumns::Tasks.cf(db), task_id.as_bytes())
-                    .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
-                    .ok_or_else(|| {
-                        StateMachineError::DatabaseError(format!("Task {} not found", task_id))
-                    })?;
-                JsonEncoder::decode(&task_bytes).map_err(StateMachineError::from)
-            })
-            .collect();
-        tasks
+        let mut state_change_ids: Vec<StateChangeId> =
+            self.unprocessed_state_changes.inner().into_iter().collect();
+        state_change_ids.sort();
+        let mut dangling = Vec::new();
+        for state_change_id in state_change_ids {
+            let state_change: Option<StateChange> = self
+                .get_from_cf(
+                    state_changes_db,
+                    StateMachineColumns::StateChanges,
+                    &state_change_id,
+                )
+                .map_err(StateMachineError::ExternalError)?;
+            let state_change = match state_change {
+                Some(state_change) => state_change,
+                None => {
+                    dangling.push((state_change_id, "state change not found".to_string()));
+                    continue;
+                }
+            };
+            let reason = match &state_change.change_type {
+                internal_api::ChangeType::NewContent |
+                internal_api::ChangeType::UpdateContent |
+                internal_api::ChangeType::TombstoneContentTree => {
+                    content_missing(&state_change.object_id)?
+                }
+                internal_api::ChangeType::NewExtractionPolicy => {
+                    if key_missing(
+                        StateMachineColumns::ExtractionPolicies,
+                        &state_change.object_id,
+                    )? {
+                        Some(format!(
+                            "extraction policy {} not found",
+                            state_change.object_id
+                        ))
+                    } else {
+                        None
+                    }
+                }
+                internal_api::ChangeType::TaskCompleted { content_id } => {
+                    //  Finished tasks may have been archived since
+                    if key_missing(StateMachineColumns::Tasks, &state_change.object_id)? &&
+                        key_missing(StateMachineColumns::ArchivedTasks, &state_change.object_id)?
+                    {
+                        Some(format!("task {} not found", state_change.object_id))
+                    } else {
+                        content_missing(&content_id.to_string())?
+                    }
+                }
+                internal_api::ChangeType::NewGargabeCollectionTask => {
+                    if key_missing(
+                        StateMachineColumns::GarbageCollectionTasks,
+                        &state_change.object_id,
+                    )? {
+                        Some(format!(
+                            "garbage collection task {} not found",
+                            state_change.object_id
+                        ))
+                    } else {
+                        None
+                    }
+                }
+                internal_api::ChangeType::ExecutorAdded |
+                internal_api::ChangeType::ExecutorRemoved => None,
+            };
+            if let Some(reason) = reason {
+                dangling.push((state_change_id, reason));
+            }
+        }
+        Ok(dangling)
     }
 
-    /// This method will fetch indexes based on the id's of the indexes provided
-    pub fn get_indexes_from_ids(
+    /// Returns up to `limit` state changes in the order they were applied,
+    /// starting after the given state change. State changes removed once
+    /// processed are skipped. Fails with `CursorExpired` when the state
+    /// changes following the cursor have been pruned
+    pub fn iter_state_changes(
         &self,
-        task_ids: HashSet<TaskId>,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::Index>, StateMachineError> {
-        let txn = db.transaction();
-        let indexes: Result<Vec<indexify_internal_api::Index>, StateMachineError> = task_ids
-            .into_iter()
-            .map(|task_id| {
-                let index_bytes = txn
-                    .get_cf(StateMachineColumns::IndexTable.cf(db), task_id.as_bytes())
-                    .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
-                    .ok_or_else(|| {
-                        StateMachineError::DatabaseError(format!("Index {} not found", task_id))
-                    })?;
-                JsonEncoder::decode(&index_bytes).map_err(StateMachineError::from)
-            })
-            .collect();
-        indexes
+        start_after: Option<StateChangeId>,
+        limit: usize,
+    ) -> Result<Vec<StateChange>, StateMachineError> {
+        let first_retained = db
+            .get_cf(
+                StateMachineColumns::StoreMeta.cf(db),
+                FIRST_RETAINED_STATE_CHANGE_SEQUENCE_KEY,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|value| JsonEncoder::decode::<u64>(&value))
+            .transpose()?
+            .unwrap_or(1);
+        let start = match start_after {
+            Some(state_change_id) => {
+                let sequence = self
+                    .get_from_cf::<StateChange, _>(
+                        db,
+                        StateMachineColumns::StateChanges,
+                        &state_change_id,
+                    )
+                    .map_err(StateMachineError::ExternalError)?
+                    .map(|state_change| state_change.sequence + 1);
+                match sequence {
+                    Some(sequence) if sequence >= first_retained => sequence,
+                    _ => return Err(StateMachineError::CursorExpired(state_change_id)),
+                }
+            }
+            None => first_retained,
+        };
+        let iter = db.iterator_cf(
+            StateMachineColumns::StateChangeSequence.cf(db),
+            rocksdb::IteratorMode::From(&numeric_key(start), rocksdb::Direction::Forward),
+        );
+        let mut state_changes = Vec::new();
+        for item in iter {
+            if state_changes.len() >= limit {
+                break;
+            }
+            let (_, entry) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let entry: StateChangeSequenceEntry = JsonEncoder::decode(&entry)?;
+            let state_change: Option<StateChange> = self
+                .get_from_cf(
+                    db,
+                    StateMachineColumns::StateChanges,
+                    &entry.state_change_id,
+                )
+                .map_err(StateMachineError::ExternalError)?;
+            state_changes.extend(state_change);
+        }
+        Ok(state_changes)
     }
 
-    /// This method will fetch the executors from RocksDB CF based on the
-    /// executor id's provided
-    pub fn get_executors_from_ids(
+    /// Returns the state changes referencing any version of a content, in the
+    /// order they were applied. The state changes are scanned, so a namespace
+    /// hint skips the scan for content which isn't indexed in that namespace,
+    /// including content which has since been deleted
+    pub fn content_timeline(
         &self,
-        executor_ids: HashSet<String>,
-        db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::ExecutorMetadata>, StateMachineError> {
-        let txn = db.transaction();
-        let executors: Result<Vec<indexify_internal_api::ExecutorMetadata>, StateMachineError> =
-            executor_ids
-                .into_iter()
-                .map(|executor_id| {
-                    let executor_bytes = txn
-                        .get_cf(
-                            StateMachineColumns::Executors.cf(db),
-                            executor_id.as_bytes(),
-                        )
-                        .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
-                        .ok_or_else(|| {
-                            StateMachineError::DatabaseError(format!(
-                                "Executor {} not found",
-                                executor_id
-                            ))
-                        })?;
-                    JsonEncoder::decode(&executor_bytes).map_err(StateMachineError::from)
-                })
-                .collect();
-        executors
+        state_changes_db: &Arc<OptimisticTransactionDB>,
+        content_id: &str,
+        namespace: Option<&str>,
+    ) -> Result<Vec<StateChange>, StateMachineError> {
+        if let Some(namespace) = namespace {
+            let in_namespace = self
+                .content_namespace_table
+                .inner()
+                .get(namespace)
+                .map_or(false, |content_ids| {
+                    content_ids.iter().any(|id| id.id == content_id)
+                });
+            if !in_namespace {
+                return Ok(Vec::new());
+            }
+        }
+        let references_content = |object_id: &str| {
+            ContentMetadataId::try_from(object_id.to_string())
+                .map_or(false, |object_id| object_id.id == content_id)
+        };
+        let mut timeline = Vec::new();
+        for item in state_changes_db.iterator_cf(
+            StateMachineColumns::StateChangeSequence.cf(state_changes_db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (_, entry) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let entry: StateChangeSequenceEntry = JsonEncoder::decode(&entry)?;
+            let state_change: Option<StateChange> = self
+                .get_from_cf(
+                    state_changes_db,
+                    StateMachineColumns::StateChanges,
+                    &entry.state_change_id,
+                )
+                .map_err(StateMachineError::ExternalError)?;
+            let state_change = match state_change {
+                Some(state_change) => state_change,
+                None => continue,
+            };
+            let referenced = match &state_change.change_type {
+                internal_api::ChangeType::NewContent |
+                internal_api::ChangeType::UpdateContent |
+                internal_api::ChangeType::TombstoneContentTree => {
+                    references_content(&state_change.object_id)
+                }
+                internal_api::ChangeType::TaskCompleted { content_id: id } => id.id == content_id,
+                _ => false,
+            };
+            if referenced {
+                timeline.push(state_change);
+            }
+        }
+        Ok(timeline)
     }
 
-    /// This method will fetch content based on the id and version provided.
-    /// It will skip over any content that it cannot find
-    pub fn get_content_from_ids_with_version(
+    /// Returns the number of tasks assigned to an executor from its task
+    /// assignments. The cached running task count is corrected if it has
+    /// drifted from it
+    pub fn executor_running_task_count_verified(
         &self,
-        content_ids: HashSet<ContentMetadataId>,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
+        executor_id: &ExecutorId,
+    ) -> Result<usize, StateMachineError> {
         let txn = db.transaction();
-
-        let content: Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> =
-            content_ids
-                .into_iter()
-                .filter_map(|content_id| {
-                    match txn.get_cf(
-                        StateMachineColumns::ContentTable.cf(db),
-                        format!("{}::v{}", content_id.id, content_id.version),
-                    ) {
-                        Ok(Some(content_bytes)) => match JsonEncoder::decode::<
-                            indexify_internal_api::ContentMetadata,
-                        >(&content_bytes)
-                        {
-                            Ok(content) => {
-                                if !content.tombstoned {
-                                    Some(Ok(content))
-                                } else {
-                                    None
-                                }
-                            }
-                            Err(e) => Some(Err(StateMachineError::TransactionError(e.to_string()))),
-                        },
-                        Ok(None) => None,
-                        Err(e) => Some(Err(StateMachineError::TransactionError(e.to_string()))),
-                    }
-                })
-                .collect::<Result<Vec<_>, _>>();
-        content
+        let count = self
+            .get_task_assignments_for_executor(db, &txn, executor_id)?
+            .len();
+        let cached_count = self.executor_running_task_count.get(executor_id);
+        if cached_count != Some(count) {
+            warn!(
+                "running task count of executor {} drifted from {:?} to {}, correcting it",
+                executor_id, cached_count, count
+            );
+            self.executor_running_task_count.insert(executor_id, count);
+        }
+        Ok(count)
     }
 
-    /// This method will fetch content based on the id's provided. It will look
-    /// for the latest version for each piece of content It will skip any
-    /// that cannot be found and expect the consumer to decide what to do in
-    /// that case It will also skip any that have been tombstoned
-    pub fn get_content_from_ids(
+    /// Summarizes the pending work of the state machine. Executors are
+    /// counted from the Executors column family, everything else comes from
+    /// the reverse indexes
+    pub fn health_summary(
         &self,
-        content_ids: HashSet<String>,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
-        let txn = db.transaction();
-        let mut contents = Vec::new();
+    ) -> Result<HealthSummary, StateMachineError> {
+        let mut executors = 0;
+        for item in db.iterator_cf(
+            StateMachineColumns::Executors.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            executors += 1;
+        }
+        Ok(HealthSummary {
+            unprocessed_state_changes: self.unprocessed_state_changes.inner().len(),
+            unassigned_tasks: self.unassigned_tasks.count(),
+            executors,
+            running_tasks: self.executor_running_task_count.inner().values().sum(),
+            unfinished_tasks: None,
+        })
+    }
 
-        //  For each content id find the highest version, deserialize it and collect it
-        for content_id in &content_ids {
-            // Construct prefix for content ID to search for all its versions
-            let highest_version = self.get_latest_version_of_content(content_id, db, &txn)?;
+    /// Same as health_summary, with the unfinished tasks split between the
+    /// paused extractors and the active ones
+    pub fn health_summary_with_paused(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        paused_extractors: &HashSet<ExtractorName>,
+    ) -> Result<HealthSummary, StateMachineError> {
+        let mut summary = self.health_summary(db)?;
+        summary.unfinished_tasks = Some(self.unfinished_task_counts(paused_extractors));
+        Ok(summary)
+    }
 
-            if highest_version.is_none() {
-                continue;
-            }
-            // If a key with the highest version is found, decode its content and add to the
-            // results
-            let highest_version = highest_version.unwrap();
-            match txn.get_cf(
-                StateMachineColumns::ContentTable.cf(db),
-                &format!("{}::v{}", content_id, highest_version),
-            ) {
-                Ok(Some(content_bytes)) => {
-                    match JsonEncoder::decode::<indexify_internal_api::ContentMetadata>(
-                        &content_bytes,
-                    ) {
-                        Ok(content) => {
-                            if !content.tombstoned {
-                                contents.push(content);
-                            }
-                        }
-                        Err(e) => {
-                            return Err(StateMachineError::TransactionError(e.to_string()));
-                        }
-                    }
-                }
-                Ok(None) => {} // This should technically never happen since we have the key
-                Err(e) => return Err(StateMachineError::TransactionError(e.to_string())),
+    /// Returns, sorted by name, the registered extractors which an executor
+    /// can't run
+    pub fn extractors_not_served_by(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        executor_id: &str,
+    ) -> Result<Vec<ExtractorName>, StateMachineError> {
+        let executor: internal_api::ExecutorMetadata = self
+            .get_from_cf(db, StateMachineColumns::Executors, executor_id)
+            .map_err(StateMachineError::ExternalError)?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!("Executor {} not found", executor_id))
+            })?;
+        let served_extractors: HashSet<ExtractorName> = executor
+            .extractors
+            .into_iter()
+            .map(|extractor| extractor.name)
+            .collect();
+        let mut not_served = Vec::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::Extractors.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (extractor, _) =
+                item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let extractor = String::from_utf8(extractor.to_vec())
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if !served_extractors.contains(&extractor) {
+                not_served.push(extractor);
             }
         }
-
-        Ok(contents)
+        Ok(not_served)
     }
 
-    /// This method will fetch all pieces of content metadata for the tree
-    /// rooted at content_id. It will look for the latest version of each node
-    pub fn get_content_tree_metadata(
+    /// Assembles the scheduler's view of an extractor from the reverse
+    /// indexes. Like for health_summary_with_paused, the paused extractors are
+    /// supplied by the caller
+    pub fn extractor_status(
         &self,
-        content_id: &str,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
-        let txn = db.transaction();
-        let mut collected_content_metadata = Vec::new();
+        extractor: &str,
+        paused_extractors: &HashSet<ExtractorName>,
+    ) -> Result<ExtractorStatus, StateMachineError> {
+        let description: Option<ExtractorDescription> = self
+            .get_from_cf(db, StateMachineColumns::Extractors, extractor)
+            .map_err(StateMachineError::ExternalError)?;
+        if description.is_none() {
+            return Err(StateMachineError::DatabaseError(format!(
+                "Extractor {} not found",
+                extractor
+            )));
+        }
 
-        let mut queue = VecDeque::new();
-        queue.push_back(content_id.to_string());
+        let running_task_counts = self.executor_running_task_count.inner();
+        let mut executor_loads: Vec<(ExecutorId, usize)> = self
+            .extractor_executors_table
+            .inner()
+            .remove(extractor)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|executor_id| {
+                let load = running_task_counts
+                    .get(&executor_id)
+                    .copied()
+                    .unwrap_or_default();
+                (executor_id, load)
+            })
+            .collect();
+        executor_loads.sort();
 
-        while let Some(current_root) = queue.pop_front() {
-            let highest_version = self.get_latest_version_of_content(&current_root, db, &txn)?;
-            if highest_version.is_none() {
-                continue;
+        let unfinished_tasks = self
+            .unfinished_tasks_by_extractor
+            .inner()
+            .remove(extractor)
+            .unwrap_or_default();
+        let unassigned_tasks = self.unassigned_tasks.inner();
+        Ok(ExtractorStatus {
+            extractor: extractor.to_string(),
+            executor_loads,
+            unassigned_tasks: unfinished_tasks
+                .iter()
+                .filter(|task_id| unassigned_tasks.contains(*task_id))
+                .count(),
+            unfinished_tasks: unfinished_tasks.len(),
+            paused: paused_extractors.contains(extractor),
+        })
+    }
+
+    /// Counts the unfinished tasks of the paused extractors separately from
+    /// the ones of the active extractors
+    pub fn unfinished_task_counts(
+        &self,
+        paused_extractors: &HashSet<ExtractorName>,
+    ) -> UnfinishedTaskCounts {
+        let mut counts = UnfinishedTaskCounts::default();
+        for (extractor, task_ids) in self.unfinished_tasks_by_extractor.inner() {
+            if paused_extractors.contains(&extractor) {
+                counts.paused += task_ids.len();
+            } else {
+                counts.active += task_ids.len();
             }
-            let highest_version = highest_version.unwrap();
-            let content_bytes = txn
-                .get_cf(
-                    StateMachineColumns::ContentTable.cf(db),
-                    &format!("{}::v{}", current_root, highest_version),
-                )
-                .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
-                .ok_or_else(|| {
-                    StateMachineError::DatabaseError(format!(
-                        "Content {} not found while fetching content tree",
-                        &current_root
-                    ))
-                })?;
-            let content =
-                JsonEncoder::decode::<indexify_internal_api::ContentMetadata>(&content_bytes)?;
-            collected_content_metadata.push(content.clone());
-            let children = self.content_children_table.get_children(&content.id);
-            queue.extend(children.into_iter().map(|id| id.id));
         }
-        Ok(collected_content_metadata)
+        counts
     }
 
-    /// This method will fetch all pieces of content metadata for the tree
-    /// rooted at content_id. It will look for a specfic version of the node
-    pub fn get_content_tree_metadata_with_version(
+    /// Moves the tasks which finished at least `older_than_secs` before
+    /// `now_secs` to the ArchivedTasks column family, keeping only their
+    /// summary, and deletes them from the live tasks. Returns the archived
+    /// tasks along with the executor each of them was assigned to
+    fn archive_finished_tasks(
         &self,
-        content_id: &ContentMetadataId,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
-        let txn = db.transaction();
-        let mut collected_content_metadata = Vec::new();
-
-        let mut queue = VecDeque::new();
-        queue.push_back(content_id.clone());
-
-        while let Some(current_root) = queue.pop_front() {
-            let content_bytes = txn
-                .get_cf(
-                    StateMachineColumns::ContentTable.cf(db),
-                    &format!("{}::v{}", current_root.id, current_root.version),
-                )
-                .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        older_than_secs: u64,
+        now_secs: u64,
+    ) -> Result<Vec<(internal_api::Task, Option<ExecutorId>)>, StateMachineError> {
+        let cutoff = now_secs.saturating_sub(older_than_secs);
+        let mut finished_tasks = Vec::new();
+        for item in txn.iterator_cf(
+            StateMachineColumns::FinishedTasksByTime.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let finished_at = std::str::from_utf8(&key)
+                .ok()
+                .and_then(|key| key.get(..20))
+                .and_then(|time| time.parse::<u64>().ok())
                 .ok_or_else(|| {
                     StateMachineError::DatabaseError(format!(
-                        "Content {} not found while fetching content tree",
-                        &current_root
+                        "invalid finished task key {}",
+                        String::from_utf8_lossy(&key)
                     ))
                 })?;
-            let content =
-                JsonEncoder::decode::<indexify_internal_api::ContentMetadata>(&content_bytes)?;
-            collected_content_metadata.push(content.clone());
-            let children = self.content_children_table.get_children(&content.id);
-            queue.extend(children.into_iter());
+            if finished_at > cutoff {
+                break;
+            }
+            let task_id: TaskId = JsonEncoder::decode(&value)?;
+            finished_tasks.push((key, task_id, finished_at));
         }
-        Ok(collected_content_metadata)
+
+        let mut archived_tasks = Vec::new();
+        for (key, task_id, finished_at) in finished_tasks {
+            //  Keys of tasks which were deleted or finished again are only cleaned up
+            txn.delete_cf(StateMachineColumns::FinishedTasksByTime.cf(db), &key)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task = match txn
+                .get_cf(StateMachineColumns::Tasks.cf(db), &task_id)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            {
+                Some(task) => JsonEncoder::decode::<internal_api::Task>(&task)?,
+                None => continue,
+            };
+            if !task.terminal_state() {
+                continue;
+            }
+            let archived_task = ArchivedTask {
+                id: task.id.clone(),
+                extractor: task.extractor.clone(),
+                extraction_policy_id: task.extraction_policy_id.clone(),
+                namespace: task.namespace.clone(),
+                content_id: task.content_metadata.id.clone(),
+                outcome: task.outcome,
+                finished_at,
+            };
+            txn.put_cf(
+                StateMachineColumns::ArchivedTasks.cf(db),
+                &task_id,
+                JsonEncoder::encode(&archived_task)?,
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            archived_tasks.extend(self.delete_tasks(db, txn, &vec![task_id])?);
+        }
+        Ok(archived_tasks)
     }
 
-    /// This method tries to retrieve all policies based on id's. If it cannot
-    /// find any, it skips them. If it encounters an error at any point
-    /// during the transaction, it returns out immediately
-    pub fn get_extraction_policies_from_ids(
+    /// Returns the extraction policies applied on a content
+    pub fn get_content_extraction_policy_mapping(
         &self,
-        extraction_policy_ids: HashSet<String>,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Option<Vec<indexify_internal_api::ExtractionPolicy>>, StateMachineError> {
-        let txn = db.transaction();
-
-        let mut policies = Vec::new();
-        for id in extraction_policy_ids.iter() {
-            let bytes_opt = txn
-                .get_cf(
-                    StateMachineColumns::ExtractionPolicies.cf(db),
-                    id.as_bytes(),
-                )
-                .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+        content_id: &ContentMetadataId,
+    ) -> Result<Option<ContentExtractionPolicyMapping>, StateMachineError> {
+        self.get_from_cf(
+            db,
+            StateMachineColumns::ExtractionPoliciesAppliedOnContent,
+            format!("{}::v{}", content_id.id, content_id.version),
+        )
+        .map_err(StateMachineError::ExternalError)
+    }
 
-            if let Some(bytes) = bytes_opt {
-                let policy =
-                    serde_json::from_slice::<indexify_internal_api::ExtractionPolicy>(&bytes)
-                        .map_err(StateMachineError::SerializationError)?;
-                policies.push(policy);
+    /// Fills the extraction policies applied on a content from the
+    /// ExtractionPoliciesAppliedOnContent column family. Policies recorded
+    /// on the content itself before the column family was used are kept
+    fn with_applied_policies(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        mut content: internal_api::ContentMetadata,
+    ) -> Result<internal_api::ContentMetadata, StateMachineError> {
+        let mapping = txn
+            .get_cf(
+                StateMachineColumns::ExtractionPoliciesAppliedOnContent.cf(db),
+                format!("{}::v{}", content.id.id, content.id.version),
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|value| JsonEncoder::decode::<ContentExtractionPolicyMapping>(&value))
+            .transpose()?;
+        if let Some(mapping) = mapping {
+            for (policy_id, completion_time) in mapping.time_of_policy_completion {
+                let time = content.extraction_policy_ids.entry(policy_id).or_default();
+                *time = (*time).max(completion_time);
             }
-            // If None, the policy is not found; we simply skip it.
         }
+        Ok(content)
+    }
 
-        if policies.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(policies))
+    /// Returns the ids of the content of a namespace that the extraction policy
+    /// with the given name has been applied to, sorted. The applied policies
+    /// are recorded by id, so the name is first resolved to the ids of the
+    /// namespace's policies with that name
+    pub fn content_for_policy(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        policy_name: &str,
+        namespace: &str,
+    ) -> Result<Vec<ContentId>, StateMachineError> {
+        let policy_ids: HashSet<String> = self
+            .get_extraction_policies_from_ids(
+                self.extraction_policies_table
+                    .inner()
+                    .remove(namespace)
+                    .unwrap_or_default(),
+                db,
+            )?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|policy| policy.name == policy_name)
+            .map(|policy| policy.id)
+            .collect();
+        if policy_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let namespace_content = self
+            .content_namespace_table
+            .inner()
+            .remove(namespace)
+            .unwrap_or_default();
+
+        let mut content_ids = BTreeSet::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::ExtractionPoliciesAppliedOnContent.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let content_id =
+                ContentMetadataId::try_from(String::from_utf8_lossy(&key).into_owned())
+                    .map_err(StateMachineError::ExternalError)?;
+            if !namespace_content.contains(&content_id) {
+                continue;
+            }
+            let mapping: ContentExtractionPolicyMapping = JsonEncoder::decode(&value)?;
+            if !mapping.extraction_policy_names.is_disjoint(&policy_ids) {
+                content_ids.insert(content_id.id);
+            }
         }
+        Ok(content_ids.into_iter().collect())
     }
 
-    /// This method gets all task assignments stored in the relevant CF
-    pub fn get_all_task_assignments(
+    /// Returns the summary of an archived task
+    pub fn get_archived_task(
         &self,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<HashMap<TaskId, ExecutorId>, StateMachineError> {
-        let mut assignments = HashMap::new();
+        task_id: &str,
+    ) -> Result<Option<ArchivedTask>, StateMachineError> {
+        self.get_from_cf(db, StateMachineColumns::ArchivedTasks, task_id)
+            .map_err(StateMachineError::ExternalError)
+    }
+
+    /// Groups the executors of an extractor by the value of one of their
+    /// labels, such as their zone. Executors without the label are left out.
+    /// The executors of a group are sorted by id
+    pub fn executors_grouped_by_label(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        extractor: &str,
+        label_key: &str,
+    ) -> Result<HashMap<String, Vec<ExecutorId>>, StateMachineError> {
+        let executor_ids = self
+            .extractor_executors_table
+            .inner()
+            .remove(extractor)
+            .unwrap_or_default();
+        let executor_ids = executor_ids.into_iter().sorted().collect_vec();
+        let mut groups: HashMap<String, Vec<ExecutorId>> = HashMap::new();
+        for executor in db.multi_get_cf(
+            executor_ids
+                .iter()
+                .map(|executor_id| (StateMachineColumns::Executors.cf(db), executor_id)),
+        ) {
+            let executor = executor.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let executor = match executor {
+                Some(executor) => JsonEncoder::decode::<internal_api::ExecutorMetadata>(&executor)?,
+                None => continue,
+            };
+            if let Some(value) = executor.labels.get(label_key) {
+                groups.entry(value.clone()).or_default().push(executor.id);
+            }
+        }
+        Ok(groups)
+    }
+
+    pub fn paused_namespaces(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<HashSet<NamespaceName>, StateMachineError> {
+        let mut namespaces = HashSet::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::PausedNamespaces.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (_, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            namespaces.insert(JsonEncoder::decode(&value)?);
+        }
+        Ok(namespaces)
+    }
+
+    /// Returns the unassigned tasks which can be assigned, leaving out the
+    /// tasks of paused namespaces
+    pub fn assignable_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<HashSet<TaskId>, StateMachineError> {
+        let unassigned_tasks = self.unassigned_tasks.inner();
+        let paused_namespaces = self.paused_namespaces(db)?;
+        if paused_namespaces.is_empty() {
+            return Ok(unassigned_tasks);
+        }
+        let mut assignable_tasks = HashSet::new();
+        for task in db.multi_get_cf(
+            unassigned_tasks
+                .iter()
+                .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+        ) {
+            let task = task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task = match task {
+                Some(task) => JsonEncoder::decode::<internal_api::Task>(&task)?,
+                None => continue,
+            };
+            if !paused_namespaces.contains(&task.namespace) {
+                assignable_tasks.insert(task.id);
+            }
+        }
+        Ok(assignable_tasks)
+    }
+
+    /// Returns the ids of the tasks in the order they were created, optionally
+    /// only the ones of an extractor. Tasks which have since been deleted are
+    /// skipped
+    pub fn tasks_in_creation_order(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        extractor: Option<ExtractorName>,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        let mut task_ids = Vec::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::TaskCreationSequence.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (_, task_id) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            task_ids.push(String::from_utf8(task_id.to_vec()).map_err(|e| {
+                StateMachineError::DatabaseError(format!("Error decoding task id: {}", e))
+            })?);
+        }
+        let tasks = db.multi_get_cf(
+            task_ids
+                .iter()
+                .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+        );
+        let mut ordered_task_ids = Vec::new();
+        for task in tasks {
+            let task = task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task = match task {
+                Some(task) => JsonEncoder::decode::<internal_api::Task>(&task)?,
+                None => continue,
+            };
+            if extractor
+                .as_ref()
+                .map_or(true, |extractor| *extractor == task.extractor)
+            {
+                ordered_task_ids.push(task.id);
+            }
+        }
+        Ok(ordered_task_ids)
+    }
+
+    /// Returns up to `max` of the oldest assignable tasks of an extractor, to
+    /// be offered to an executor which just registered for it. Extractors
+    /// aren't paused in the state machine, so the paused extractors are
+    /// supplied by the caller and are offered no tasks
+    pub fn pending_work_for_new_executor(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        extractor: &str,
+        max: usize,
+        paused_extractors: &HashSet<ExtractorName>,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        if max == 0 || paused_extractors.contains(extractor) {
+            return Ok(Vec::new());
+        }
+        let assignable_tasks = self.assignable_tasks(db)?;
+        Ok(self
+            .tasks_in_creation_order(db, Some(extractor.to_string()))?
+            .into_iter()
+            .filter(|task_id| assignable_tasks.contains(task_id))
+            .take(max)
+            .collect())
+    }
+
+    /// Returns the unassigned tasks of the extractors whose registered
+    /// executors are all at or above their cap of running tasks. Executors
+    /// aren't capped in the state machine, so the caps are supplied by the
+    /// caller and executors without a cap are never full. Extractors without
+    /// executors aren't reported
+    pub fn tasks_blocked_by_capacity(
+        &self,
+        executor_caps: &HashMap<ExecutorId, usize>,
+    ) -> HashMap<ExtractorName, HashSet<TaskId>> {
+        let unassigned_tasks = self.unassigned_tasks.inner();
+        let unfinished_tasks_by_extractor = self.unfinished_tasks_by_extractor.inner();
+        let executor_running_task_count = self.executor_running_task_count.inner();
+        let mut blocked_tasks = HashMap::new();
+        for (extractor, executor_ids) in self.extractor_executors_table.inner() {
+            if executor_ids.is_empty() {
+                continue;
+            }
+            let all_full =
+                executor_ids
+                    .iter()
+                    .all(|executor_id| match executor_caps.get(executor_id) {
+                        Some(cap) => {
+                            executor_running_task_count
+                                .get(executor_id)
+                                .copied()
+                                .unwrap_or(0) >=
+                                *cap
+                        }
+                        None => false,
+                    });
+            if !all_full {
+                continue;
+            }
+            let task_ids: HashSet<TaskId> = unfinished_tasks_by_extractor
+                .get(&extractor)
+                .map(|task_ids| {
+                    task_ids
+                        .iter()
+                        .filter(|task_id| unassigned_tasks.contains(*task_id))
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default();
+            if !task_ids.is_empty() {
+                blocked_tasks.insert(extractor, task_ids);
+            }
+        }
+        blocked_tasks
+    }
+
+    /// Returns the unfinished tasks of every extractor with an SLA that were
+    /// created more than the SLA ago. Tasks without a creation time are
+    /// skipped. Extractors and their tasks are sorted by name and id
+    pub fn sla_breaches(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        now_secs: u64,
+    ) -> Result<Vec<(ExtractorName, Vec<TaskId>)>, StateMachineError> {
+        let mut breaches = Vec::new();
+        let unfinished_tasks_by_extractor = self.unfinished_tasks_by_extractor.inner();
+        for (extractor_name, task_ids) in unfinished_tasks_by_extractor.iter().sorted() {
+            let extractor = db
+                .get_cf(StateMachineColumns::Extractors.cf(db), extractor_name)
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+                .map(|value| JsonEncoder::decode::<ExtractorDescription>(&value))
+                .transpose()?;
+            let sla_secs = match extractor.and_then(|extractor| extractor.sla_secs) {
+                Some(sla_secs) => sla_secs,
+                None => continue,
+            };
+            let task_ids = task_ids.iter().sorted().collect_vec();
+            let tasks = db.multi_get_cf(
+                task_ids
+                    .iter()
+                    .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+            );
+            let mut breached_task_ids = Vec::new();
+            for (task_id, task) in task_ids.into_iter().zip(tasks) {
+                let task =
+                    match task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))? {
+                        Some(task) => task,
+                        None => continue,
+                    };
+                let task: internal_api::Task = JsonEncoder::decode(&task)?;
+                if task.created_at > 0 && now_secs > task.created_at + sla_secs {
+                    breached_task_ids.push(task_id.clone());
+                }
+            }
+            if !breached_task_ids.is_empty() {
+                breaches.push((extractor_name.clone(), breached_task_ids));
+            }
+        }
+        Ok(breaches)
+    }
+
+    /// Returns the names of the extractors of every task, whether it is
+    /// finished or not. The extractors of unfinished tasks come from the
+    /// reverse index, while finished tasks are found by scanning the Tasks CF
+    pub fn distinct_task_extractors(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<HashSet<ExtractorName>, StateMachineError> {
+        let mut extractors: HashSet<ExtractorName> = self
+            .unfinished_tasks_by_extractor
+            .inner()
+            .into_iter()
+            .filter(|(_, task_ids)| !task_ids.is_empty())
+            .map(|(extractor, _)| extractor)
+            .collect();
+        for item in db.iterator_cf(
+            StateMachineColumns::Tasks.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (_, task) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task: internal_api::Task = JsonEncoder::decode(&task)?;
+            if task.terminal_state() {
+                extractors.insert(task.extractor);
+            }
+        }
+        Ok(extractors)
+    }
+
+    /// Returns up to `limit` tasks with the given outcome across all
+    /// extractors.
+    ///
+    /// NOTE: This scans the whole Tasks CF. For unfinished tasks, prefer the
+    /// extractor scoped unfinished_tasks_by_extractor reverse index which
+    /// avoids the scan
+    pub fn list_tasks_by_outcome(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        outcome: TaskOutcome,
+        limit: usize,
+    ) -> Result<Vec<internal_api::Task>, StateMachineError> {
+        let mut tasks = Vec::new();
         let iter = db.iterator_cf(
-            StateMachineColumns::TaskAssignments.cf(db),
+            StateMachineColumns::Tasks.cf(db),
             rocksdb::IteratorMode::Start,
         );
         for item in iter {
-            let (key, value) = item.map_err(|e| {
-                StateMachineError::DatabaseError(format!(
-                    "unable to get values from task assignment {}",
-                    e
-                ))
-            })?;
-            let executor_id = String::from_utf8(key.to_vec()).map_err(|e| {
-                StateMachineError::DatabaseError(format!(
-                    "unable to get executor id from task assignment {}",
-                    e
-                ))
-            })?;
-            let task_ids: HashSet<TaskId> = JsonEncoder::decode(&value).map_err(|e| {
-                StateMachineError::DatabaseError(format!(
-                    "unable to decoded task hashset from task assignment {}",
-                    e
-                ))
+            if tasks.len() >= limit {
+                break;
+            }
+            let (_, value) = item.map_err(|e| {
+                StateMachineError::DatabaseError(format!("unable to read task: {}", e))
             })?;
-            for task_id in task_ids {
-                assignments.insert(task_id, executor_id.clone());
+            let task = JsonEncoder::decode::<internal_api::Task>(&value)?;
+            if task.outcome == outcome {
+                tasks.push(task);
             }
         }
-        Ok(assignments)
+        Ok(tasks)
     }
 
-    /// This method will get the namespace based on the key provided
-    pub fn get_namespace(
+    /// Returns the executor that a task is currently assigned to, if any
+    pub fn task_executor(
         &self,
-        namespace: &str,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Option<indexify_internal_api::Namespace>> {
-        let ns_name = match self.get_from_cf(db, StateMachineColumns::Namespaces, namespace)? {
-            Some(name) => name,
-            None => return Ok(None),
-        };
+        task_id: &str,
+    ) -> Result<Option<ExecutorId>, StateMachineError> {
+        self.get_from_cf(db, StateMachineColumns::TaskToExecutor, task_id)
+            .map_err(StateMachineError::ExternalError)
+    }
 
-        let extraction_policy_ids = self.extraction_policies_table.get(&namespace.to_string());
-        let extraction_policies = self
-            .get_extraction_policies_from_ids(extraction_policy_ids, db)?
-            .unwrap_or_else(Vec::new);
+    /// Cross-checks the TaskToExecutor reverse index against the executor
+    /// task assignments and returns, sorted by task id, the tasks whose index
+    /// entry is missing, stale or points to the wrong executor
+    pub fn verify_task_executor_index(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        Ok(self
+            .task_executor_index_drift(db)?
+            .into_iter()
+            .map(|(task_id, _)| task_id)
+            .collect())
+    }
 
-        Ok(Some(indexify_internal_api::Namespace {
-            name: ns_name,
-            extraction_policies,
-        }))
+    /// Rewrites the TaskToExecutor entries which drifted from the executor
+    /// task assignments. Returns the repaired task ids
+    fn repair_task_executor_index(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        let drift = self.task_executor_index_drift(db)?;
+        let task_to_executor_cf = StateMachineColumns::TaskToExecutor.cf(db);
+        for (task_id, executor_id) in &drift {
+            match executor_id {
+                Some(executor_id) => txn
+                    .put_cf(
+                        task_to_executor_cf,
+                        task_id,
+                        JsonEncoder::encode(executor_id)?,
+                    )
+                    .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?,
+                None => txn
+                    .delete_cf(task_to_executor_cf, task_id)
+                    .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?,
+            }
+        }
+        Ok(drift.into_iter().map(|(task_id, _)| task_id).collect())
     }
 
-    pub fn get_schemas(
+    /// Re-adds unfinished tasks that are neither assigned to an executor nor
+    /// queued as unassigned, returning the ids of the re-added tasks. Run on
+    /// scheduler startup to recover tasks left mid-assignment.
+    fn reconcile_unassigned(
         &self,
-        ids: HashSet<String>,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<internal_api::StructuredDataSchema>> {
-        let txn = db.transaction();
-        let keys = ids
-            .iter()
-            .map(|id| (StateMachineColumns::StructuredDataSchemas.cf(db), id))
-            .collect_vec();
-        let schema_bytes = txn.multi_get_cf(keys);
-        let mut schemas = vec![];
-        for schema in schema_bytes {
-            let schema = schema
-                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
-                .ok_or(StateMachineError::DatabaseError("Schema not found".into()))?;
-            let schema = JsonEncoder::decode(&schema)?;
-            schemas.push(schema);
+        txn: &rocksdb::Transaction<OptimisticTransactionDB>,
+        post_commit: &mut PostCommitUpdates,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        self.reverse_index_barrier();
+        let mut assigned_tasks = HashSet::new();
+        for item in txn.iterator_cf(
+            StateMachineColumns::TaskAssignments.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (_, task_ids) =
+                item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task_ids: HashSet<TaskId> = JsonEncoder::decode(&task_ids)?;
+            assigned_tasks.extend(task_ids);
         }
-        Ok(schemas)
+        let unassigned_tasks = self.unassigned_tasks.inner();
+        let mut orphaned: Vec<TaskId> = self
+            .unfinished_tasks_by_extractor
+            .inner()
+            .into_values()
+            .flatten()
+            .filter(|task_id| {
+                !assigned_tasks.contains(task_id) && !unassigned_tasks.contains(task_id)
+            })
+            .collect();
+        orphaned.sort();
+        let requeued = orphaned.clone();
+        post_commit.push(move |state| {
+            for task_id in &requeued {
+                state.unassigned_tasks.insert(task_id);
+            }
+        });
+        Ok(orphaned)
     }
 
-    pub fn get_coordinator_addr(
+    /// Returns the TaskToExecutor entries which don't match the executor task
+    /// assignments, along with the executor the task is actually assigned to
+    fn task_executor_index_drift(
         &self,
-        node_id: NodeId,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Option<String>> {
-        self.get_from_cf(
-            db,
-            StateMachineColumns::CoordinatorAddress,
-            node_id.to_string(),
-        )
+    ) -> Result<Vec<(TaskId, Option<ExecutorId>)>, StateMachineError> {
+        let mut assigned: HashMap<TaskId, ExecutorId> = HashMap::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::TaskAssignments.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (executor_id, task_ids) =
+                item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let executor_id = String::from_utf8(executor_id.to_vec())
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task_ids: HashSet<TaskId> = JsonEncoder::decode(&task_ids)?;
+            for task_id in task_ids {
+                assigned.insert(task_id, executor_id.clone());
+            }
+        }
+
+        let mut drift = Vec::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::TaskToExecutor.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (task_id, executor_id) =
+                item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task_id = String::from_utf8(task_id.to_vec())
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let executor_id: ExecutorId = JsonEncoder::decode(&executor_id)?;
+            match assigned.remove(&task_id) {
+                Some(assigned_executor) if assigned_executor == executor_id => {}
+                assigned_executor => drift.push((task_id, assigned_executor)),
+            }
+        }
+        //  Assigned tasks left over have no index entry at all
+        drift.extend(
+            assigned
+                .into_iter()
+                .map(|(task_id, executor_id)| (task_id, Some(executor_id))),
+        );
+        drift.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(drift)
     }
 
-    /// Test utility method to get all key-value pairs from a column family
-    pub fn get_all_rows_from_cf<V>(
+    /// Returns the reference to the artifact of a task, if it has one
+    pub fn task_artifact_ref(
         &self,
-        column: StateMachineColumns,
         db: &Arc<OptimisticTransactionDB>,
-    ) -> Result<Vec<(String, V)>, anyhow::Error>
-    where
-        V: DeserializeOwned,
-    {
-        let cf_handle = db.cf_handle(column.as_ref()).ok_or(anyhow::anyhow!(
-            "Failed to get column family {}",
-            column.to_string()
-        ))?;
-        let iter = db.iterator_cf(cf_handle, rocksdb::IteratorMode::Start);
+        task_id: &str,
+    ) -> Result<Option<String>, StateMachineError> {
+        self.get_from_cf(db, StateMachineColumns::TaskArtifacts, task_id)
+            .map_err(StateMachineError::ExternalError)
+    }
 
-        iter.map(|item| {
-            item.map_err(|e| anyhow::anyhow!(e))
-                .and_then(|(key, value)| {
-                    let key = String::from_utf8(key.to_vec())
-                        .map_err(|e| anyhow::anyhow!("UTF-8 conversion error for key: {}", e))?;
-                    let value = JsonEncoder::decode(&value)
-                        .map_err(|e| anyhow::anyhow!("Deserialization error for value: {}", e))?;
-                    Ok((key, value))
-                })
+    /// Fetches the artifact of a task from the artifact store. Returns None
+    /// when the task has no artifact
+    pub fn fetch_task_artifact(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        task_id: &str,
+        artifact_store: &impl ArtifactStore,
+    ) -> Result<Option<Vec<u8>>, StateMachineError> {
+        match self.task_artifact_ref(db, task_id)? {
+            Some(artifact_ref) => Ok(Some(artifact_store.fetch(&artifact_ref)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the task along with the executor it is assigned to and the
+    /// content it processes. The executor and the content are None when the
+    /// task is unassigned or they no longer exist
+    pub fn task_detail(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        task_id: &str,
+    ) -> Result<TaskDetail, StateMachineError> {
+        let mut values = db
+            .multi_get_cf([
+                (StateMachineColumns::Tasks.cf(db), task_id),
+                (StateMachineColumns::TaskToExecutor.cf(db), task_id),
+            ])
+            .into_iter();
+        let task = values
+            .next()
+            .unwrap()
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!("Task {} not found", task_id))
+            })?;
+        let task: internal_api::Task = JsonEncoder::decode(&task)?;
+        let executor_id: Option<ExecutorId> = values
+            .next()
+            .unwrap()
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|value| JsonEncoder::decode(&value))
+            .transpose()?;
+
+        let content_key = task.content_metadata.id.to_string();
+        let mut keys = vec![(StateMachineColumns::ContentTable.cf(db), content_key)];
+        if let Some(executor_id) = executor_id {
+            keys.push((StateMachineColumns::Executors.cf(db), executor_id));
+        }
+        let mut values = db.multi_get_cf(keys).into_iter();
+        let content = values
+            .next()
+            .unwrap()
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .map(|value| JsonEncoder::decode::<internal_api::ContentMetadata>(&value))
+            .transpose()?;
+        let executor = match values.next() {
+            Some(value) => value
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+                .map(|value| JsonEncoder::decode::<internal_api::ExecutorMetadata>(&value))
+                .transpose()?,
+            None => None,
+        };
+        Ok(TaskDetail {
+            task,
+            executor,
+            content,
         })
-        .collect::<Result<Vec<(String, V)>, _>>()
     }
 
-    //  END READER METHODS FOR ROCKSDB FORWARD INDEXES
+    /// This method is used to get the tasks assigned to an executor
+    /// It does this by looking up the TaskAssignments CF to get the task id's
+    /// and then using those id's to look up tasks via Tasks CF
+    pub fn get_tasks_for_executor(
+        &self,
+        executor_id: &str,
+        limit: Option<u64>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::Task>, StateMachineError> {
+        //  NOTE: Don't do deserialization within the transaction
+        let txn = db.transaction();
+        let task_ids_bytes = txn
+            .get_cf(StateMachineColumns::TaskAssignments.cf(db), executor_id)
+            .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+
+        let task_ids: Vec<String> = task_ids_bytes
+            .map(|task_id_bytes| {
+                JsonEncoder::decode(&task_id_bytes)
+                    .map_err(StateMachineError::from)
+                    .unwrap_or_else(|e| {
+                        error!("Failed to deserialize task id: {}", e);
+                        Vec::new()
+                    })
+            })
+            .unwrap_or_else(Vec::new);
+
+        // FIXME Use MULTIGET
+        let limit = limit.unwrap_or(task_ids.len() as u64) as usize;
+
+        let tasks: Result<Vec<indexify_internal_api::Task>, StateMachineError> = task_ids
+            .into_iter()
+            .take(limit)
+            .map(|task_id| {
+                let task_bytes = txn
+                    .get_cf(StateMachineColumns::Tasks.cf(db), task_id.as_bytes())
+                    .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
+                    .ok_or_else(|| {
+                        StateMachineError::DatabaseError(format!("Task {} not found", task_id))
+                    })?;
+                JsonEncoder::decode(&task_bytes).map_err(StateMachineError::from)
+            })
+            .collect();
+        tasks
+    }
+
+    /// Returns the ids of the tasks assigned to an executor in a stable order,
+    /// the oldest tasks first. Tasks created at the same time are ordered by
+    /// id. Assigned task ids whose task is missing are skipped
+    pub fn assigned_tasks_ordered(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        executor_id: &str,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        let txn = db.transaction();
+        let task_ids = self.get_task_assignments_for_executor(db, &txn, executor_id)?;
+        let tasks = txn.multi_get_cf(
+            task_ids
+                .iter()
+                .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+        );
+        let mut ordered_tasks = Vec::new();
+        for task in tasks {
+            let task = task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(task) = task {
+                let task: internal_api::Task = JsonEncoder::decode(&task)?;
+                ordered_tasks.push((task.created_at, task.id));
+            }
+        }
+        ordered_tasks.sort();
+        Ok(ordered_tasks
+            .into_iter()
+            .map(|(_, task_id)| task_id)
+            .collect())
+    }
+
+    /// Returns the generation of an index that serves reads
+    pub fn get_active_index_generation(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        index_id: &str,
+    ) -> Result<u64, StateMachineError> {
+        let generation = self
+            .get_from_cf(db, StateMachineColumns::ActiveIndexGenerations, index_id)
+            .map_err(StateMachineError::ExternalError)?;
+        Ok(generation.unwrap_or_default())
+    }
+
+    pub fn index_generation_exists(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        index_id: &str,
+        generation: u64,
+    ) -> Result<bool, StateMachineError> {
+        let index = db
+            .get_cf(
+                StateMachineColumns::IndexTable.cf(db),
+                index_generation_key(index_id, generation),
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        Ok(index.is_some())
+    }
+
+    /// Checks that every column of a schema is an attribute produced by the
+    /// extractor which populates the index
+    pub fn validate_index_schema(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        index: &internal_api::Index,
+        schema: &internal_api::StructuredDataSchema,
+    ) -> Result<(), StateMachineError> {
+        let extractor: ExtractorDescription = self
+            .get_from_cf(db, StateMachineColumns::Extractors, &index.extractor)
+            .map_err(StateMachineError::ExternalError)?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!("Extractor {} not found", index.extractor))
+            })?;
+        let fields: HashSet<&String> = extractor
+            .outputs
+            .values()
+            .filter_map(|output| match output {
+                internal_api::OutputSchema::Attributes(columns) => Some(columns.keys()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        for column in schema.columns.keys() {
+            if !fields.contains(column) {
+                return Err(StateMachineError::DatabaseError(format!(
+                    "Schema column {} is not produced by extractor {}",
+                    column, index.extractor
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// This method will fetch indexes based on the id's of the indexes
+    /// provided. The active generation of each index is returned
+    pub fn get_indexes_from_ids(
+        &self,
+        task_ids: HashSet<TaskId>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::Index>, StateMachineError> {
+        let txn = db.transaction();
+        let indexes: Result<Vec<indexify_internal_api::Index>, StateMachineError> = task_ids
+            .into_iter()
+            .map(|task_id| {
+                let generation = self.get_active_index_generation(db, &task_id)?;
+                let index_bytes = txn
+                    .get_cf(
+                        StateMachineColumns::IndexTable.cf(db),
+                        index_generation_key(&task_id, generation),
+                    )
+                    .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
+                    .ok_or_else(|| {
+                        StateMachineError::DatabaseError(format!("Index {} not found", task_id))
+                    })?;
+                JsonEncoder::decode(&index_bytes).map_err(StateMachineError::from)
+            })
+            .collect();
+        indexes
+    }
+
+    /// This method will fetch the executors from RocksDB CF based on the
+    /// executor id's provided
+    pub fn get_executors_from_ids(
+        &self,
+        executor_ids: HashSet<String>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::ExecutorMetadata>, StateMachineError> {
+        let txn = db.transaction();
+        let executors: Result<Vec<indexify_internal_api::ExecutorMetadata>, StateMachineError> =
+            executor_ids
+                .into_iter()
+                .map(|executor_id| {
+                    let executor_bytes = txn
+                        .get_cf(
+                            StateMachineColumns::Executors.cf(db),
+                            executor_id.as_bytes(),
+                        )
+                        .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
+                        .ok_or_else(|| {
+                            StateMachineError::DatabaseError(format!(
+                                "Executor {} not found",
+                                executor_id
+                            ))
+                        })?;
+                    JsonEncoder::decode(&executor_bytes).map_err(StateMachineError::from)
+                })
+                .collect();
+        executors
+    }
+
+    /// This method will fetch content based on the id and version provided.
+    /// It will skip over any content that it cannot find
+    /// Returns the summaries of the content in a namespace, ordered by id.
+    /// Tombstoned content is left out
+    pub fn list_content_summaries(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+    ) -> Result<Vec<ContentSummary>, StateMachineError> {
+        let content_ids = self
+            .content_namespace_table
+            .inner()
+            .get(namespace)
+            .cloned()
+            .unwrap_or_default();
+        let txn = db.transaction();
+        let content = txn.multi_get_cf(content_ids.iter().map(|content_id| {
+            (
+                StateMachineColumns::ContentTable.cf(db),
+                content_id.to_string(),
+            )
+        }));
+        let mut summaries = Vec::new();
+        for content in content {
+            let content = content.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(content) = content {
+                let summary: ContentSummary = JsonEncoder::decode(&content)?;
+                if !summary.tombstoned {
+                    summaries.push(summary);
+                }
+            }
+        }
+        summaries.sort_by(|a, b| a.id.to_string().cmp(&b.id.to_string()));
+        Ok(summaries)
+    }
+
+    /// Returns the name of the extraction policy that produced the content.
+    /// Derived content records the policy as its source, while ingested
+    /// content has no parent and no producing policy
+    pub fn policy_for_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        content_id: &ContentMetadataId,
+    ) -> Result<Option<String>, StateMachineError> {
+        let content = db
+            .get_cf(
+                StateMachineColumns::ContentTable.cf(db),
+                content_id.to_string(),
+            )
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!("Content {} not found", content_id))
+            })?;
+        let content: internal_api::ContentMetadata = JsonEncoder::decode(&content)?;
+        if content.parent_id.id.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(content.source))
+    }
+
+    /// Returns the ids of the content produced by the extraction policy, which
+    /// is the derived content of its namespace whose source is the policy
+    pub fn content_produced_by_policy(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        policy_id: &str,
+    ) -> Result<Vec<ContentMetadataId>, StateMachineError> {
+        let policy = db
+            .get_cf(StateMachineColumns::ExtractionPolicies.cf(db), policy_id)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!(
+                    "Extraction policy {} not found",
+                    policy_id
+                ))
+            })?;
+        let policy: internal_api::ExtractionPolicy = JsonEncoder::decode(&policy)?;
+        let content_ids = self
+            .content_namespace_table
+            .inner()
+            .get(&policy.namespace)
+            .cloned()
+            .unwrap_or_default();
+        let content = db.multi_get_cf(content_ids.iter().map(|content_id| {
+            (
+                StateMachineColumns::ContentTable.cf(db),
+                content_id.to_string(),
+            )
+        }));
+        let mut produced = Vec::new();
+        for content in content {
+            let content = content.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(content) = content {
+                let content: internal_api::ContentMetadata = JsonEncoder::decode(&content)?;
+                if !content.parent_id.id.is_empty() && content.source == policy.name {
+                    produced.push(content.id);
+                }
+            }
+        }
+        produced.sort_by_key(|content_id| content_id.to_string());
+        Ok(produced)
+    }
+
+    /// Returns the content of a namespace written at or after `since_secs`,
+    /// ordered by the time it was last written
+    pub fn content_modified_since(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+        since_secs: u64,
+    ) -> Result<Vec<ContentMetadataId>, StateMachineError> {
+        let prefix = format!("{}::", namespace);
+        let start = format!("{}{:020}", prefix, since_secs);
+        let mut content_ids = Vec::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::ContentByModifiedTime.cf(db),
+            rocksdb::IteratorMode::From(start.as_bytes(), rocksdb::Direction::Forward),
+        ) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            //  Keys of other namespaces sharing the prefix don't start with the time
+            let is_namespace_key = key
+                .strip_prefix(prefix.as_bytes())
+                .and_then(|rest| rest.get(..20))
+                .map_or(false, |time| time.iter().all(u8::is_ascii_digit));
+            if !is_namespace_key {
+                break;
+            }
+            content_ids.push(JsonEncoder::decode(&value)?);
+        }
+        Ok(content_ids)
+    }
+
+    /// Returns the ids of the content of a namespace carrying all or any of
+    /// the tags, in id order. No content matches an empty list of tags
+    pub fn list_content_by_tags(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+        tags: &[String],
+        mode: TagMatch,
+    ) -> Result<Vec<ContentId>, StateMachineError> {
+        let mut matched: Option<BTreeSet<ContentId>> = None;
+        for tag in tags {
+            let prefix = format!("{}::{}::", namespace, tag);
+            let mut tagged = BTreeSet::new();
+            for item in db.iterator_cf(
+                StateMachineColumns::ContentByTag.cf(db),
+                rocksdb::IteratorMode::From(prefix.as_bytes(), rocksdb::Direction::Forward),
+            ) {
+                let (key, value) =
+                    item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+                if !key.starts_with(prefix.as_bytes()) {
+                    break;
+                }
+                //  Keys of tags which extend this tag share the prefix
+                let content_id: ContentMetadataId = JsonEncoder::decode(&value)?;
+                if *key == *content_by_tag_key(namespace, tag, &content_id).as_bytes() {
+                    tagged.insert(content_id.id);
+                }
+            }
+            matched = Some(match (matched, mode) {
+                (None, _) => tagged,
+                (Some(matched), TagMatch::All) => matched.intersection(&tagged).cloned().collect(),
+                (Some(mut matched), TagMatch::Any) => {
+                    matched.extend(tagged);
+                    matched
+                }
+            });
+        }
+        Ok(matched.unwrap_or_default().into_iter().collect())
+    }
+
+    /// Returns the chunks of the source content ordered by chunk index.
+    /// Chunks which have since been deleted are skipped
+    pub fn list_chunks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        source_content_id: &ContentMetadataId,
+    ) -> Result<Vec<internal_api::ContentMetadata>, StateMachineError> {
+        let chunk_ids = self.content_chunks_table.get_chunks(source_content_id);
+        let chunks = db.multi_get_cf(chunk_ids.iter().map(|chunk_id| {
+            (
+                StateMachineColumns::ContentTable.cf(db),
+                chunk_id.to_string(),
+            )
+        }));
+        let mut content = Vec::new();
+        for chunk in chunks {
+            let chunk = chunk.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(chunk) = chunk {
+                let chunk: internal_api::ContentMetadata = JsonEncoder::decode(&chunk)?;
+                if !chunk.tombstoned {
+                    content.push(chunk);
+                }
+            }
+        }
+        Ok(content)
+    }
+
+    pub fn get_content_from_ids_with_version(
+        &self,
+        content_ids: HashSet<ContentMetadataId>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
+        let txn = db.transaction();
+
+        let content: Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> =
+            content_ids
+                .into_iter()
+                .filter_map(|content_id| {
+                    match txn.get_cf(
+                        StateMachineColumns::ContentTable.cf(db),
+                        format!("{}::v{}", content_id.id, content_id.version),
+                    ) {
+                        Ok(Some(content_bytes)) => match JsonEncoder::decode::<
+                            indexify_internal_api::ContentMetadata,
+                        >(&content_bytes)
+                        {
+                            Ok(content) => {
+                                if !content.tombstoned {
+                                    Some(self.with_applied_policies(db, &txn, content))
+                                } else {
+                                    None
+                                }
+                            }
+                            Err(e) => Some(Err(StateMachineError::TransactionError(e.to_string()))),
+                        },
+                        Ok(None) => None,
+                        Err(e) => Some(Err(StateMachineError::TransactionError(e.to_string()))),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>();
+        content
+    }
+
+    /// This method will fetch content based on the id's provided. It will look
+    /// for the latest version for each piece of content It will skip any
+    /// that cannot be found and expect the consumer to decide what to do in
+    /// that case It will also skip any that have been tombstoned
+    pub fn get_content_from_ids(
+        &self,
+        content_ids: HashSet<String>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
+        let txn = db.transaction();
+        let mut contents = Vec::new();
+
+        //  For each content id find the highest version, deserialize it and collect it
+        for content_id in &content_ids {
+            // Construct prefix for content ID to search for all its versions
+            let highest_version = self.get_latest_version_of_content(content_id, db, &txn)?;
+
+            if highest_version.is_none() {
+                continue;
+            }
+            // If a key with the highest version is found, decode its content and add to the
+            // results
+            let highest_version = highest_version.unwrap();
+            match txn.get_cf(
+                StateMachineColumns::ContentTable.cf(db),
+                &format!("{}::v{}", content_id, highest_version),
+            ) {
+                Ok(Some(content_bytes)) => {
+                    match JsonEncoder::decode::<indexify_internal_api::ContentMetadata>(
+                        &content_bytes,
+                    ) {
+                        Ok(content) => {
+                            if !content.tombstoned {
+                                contents.push(self.with_applied_policies(db, &txn, content)?);
+                            }
+                        }
+                        Err(e) => {
+                            return Err(StateMachineError::TransactionError(e.to_string()));
+                        }
+                    }
+                }
+                Ok(None) => {} // This should technically never happen since we have the key
+                Err(e) => return Err(StateMachineError::TransactionError(e.to_string())),
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// This method will fetch all pieces of content metadata for the tree
+    /// rooted at content_id. It will look for the latest version of each node
+    pub fn get_content_tree_metadata(
+        &self,
+        content_id: &str,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
+        let txn = db.transaction();
+        let mut collected_content_metadata = Vec::new();
+
+        let mut queue = VecDeque::new();
+        queue.push_back(content_id.to_string());
+
+        while let Some(current_root) = queue.pop_front() {
+            let highest_version = self.get_latest_version_of_content(&current_root, db, &txn)?;
+            if highest_version.is_none() {
+                continue;
+            }
+            let highest_version = highest_version.unwrap();
+            let content_bytes = txn
+                .get_cf(
+                    StateMachineColumns::ContentTable.cf(db),
+                    &format!("{}::v{}", current_root, highest_version),
+                )
+                .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
+                .ok_or_else(|| {
+                    StateMachineError::DatabaseError(format!(
+                        "Content {} not found while fetching content tree",
+                        &current_root
+                    ))
+                })?;
+            let content = self.with_applied_policies(
+                db,
+                &txn,
+                JsonEncoder::decode::<indexify_internal_api::ContentMetadata>(&content_bytes)?,
+            )?;
+            collected_content_metadata.push(content.clone());
+            let children = self.content_children_table.get_children(&content.id);
+            queue.extend(children.into_iter().map(|id| id.id));
+        }
+        Ok(collected_content_metadata)
+    }
+
+    /// This method will fetch all pieces of content metadata for the tree
+    /// rooted at content_id. It will look for a specfic version of the node
+    pub fn get_content_tree_metadata_with_version(
+        &self,
+        content_id: &ContentMetadataId,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<indexify_internal_api::ContentMetadata>, StateMachineError> {
+        let txn = db.transaction();
+        let mut collected_content_metadata = Vec::new();
+
+        let mut queue = VecDeque::new();
+        queue.push_back(content_id.clone());
+
+        while let Some(current_root) = queue.pop_front() {
+            let content_bytes = txn
+                .get_cf(
+                    StateMachineColumns::ContentTable.cf(db),
+                    &format!("{}::v{}", current_root.id, current_root.version),
+                )
+                .map_err(|e| StateMachineError::TransactionError(e.to_string()))?
+                .ok_or_else(|| {
+                    StateMachineError::DatabaseError(format!(
+                        "Content {} not found while fetching content tree",
+                        &current_root
+                    ))
+                })?;
+            let content = self.with_applied_policies(
+                db,
+                &txn,
+                JsonEncoder::decode::<indexify_internal_api::ContentMetadata>(&content_bytes)?,
+            )?;
+            collected_content_metadata.push(content.clone());
+            let children = self.content_children_table.get_children(&content.id);
+            queue.extend(children.into_iter());
+        }
+        Ok(collected_content_metadata)
+    }
+
+    /// Returns the extraction policies of the schema's namespace which either
+    /// extract the content of the schema or produce it. The policies are
+    /// sorted by name
+    pub fn policies_for_schema(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        schema_id: &str,
+    ) -> Result<Vec<internal_api::ExtractionPolicy>, StateMachineError> {
+        let schema: internal_api::StructuredDataSchema = self
+            .get_from_cf(db, StateMachineColumns::StructuredDataSchemas, schema_id)?
+            .ok_or_else(|| {
+                StateMachineError::DatabaseError(format!("Schema {} not found", schema_id))
+            })?;
+        let policy_ids = self
+            .extraction_policies_table
+            .inner()
+            .remove(&schema.namespace)
+            .unwrap_or_default();
+        let mut policies: Vec<internal_api::ExtractionPolicy> = self
+            .get_extraction_policies_from_ids(policy_ids, db)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|policy| policy_schema_ids(policy).contains(&schema.id))
+            .collect();
+        policies.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(policies)
+    }
+
+    /// Returns the schemas of a namespace which are neither read nor written
+    /// by any extraction policy, nor written by any index of the namespace.
+    /// The schema ids are sorted
+    pub fn orphaned_schemas(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+    ) -> Result<Vec<SchemaId>, StateMachineError> {
+        let schema_ids = self
+            .schemas_by_namespace
+            .inner()
+            .remove(namespace)
+            .unwrap_or_default();
+        let policy_ids = self
+            .extraction_policies_table
+            .inner()
+            .remove(namespace)
+            .unwrap_or_default();
+        let index_ids = self
+            .namespace_index_table
+            .inner()
+            .remove(namespace)
+            .unwrap_or_default();
+
+        let mut referenced: HashSet<SchemaId> = HashSet::new();
+        for policy in self
+            .get_extraction_policies_from_ids(policy_ids, db)?
+            .unwrap_or_default()
+        {
+            referenced.extend(policy_schema_ids(&policy));
+        }
+        //  The schema of an index holds the attributes of its extraction policy
+        for index in self.get_indexes_from_ids(index_ids, db)? {
+            referenced.insert(internal_api::StructuredDataSchema::schema_id(
+                &index.namespace,
+                &index.extraction_policy,
+            ));
+        }
+        Ok(schema_ids
+            .into_iter()
+            .filter(|schema_id| !referenced.contains(schema_id))
+            .sorted()
+            .collect())
+    }
+
+    /// This method tries to retrieve all policies based on id's. If it cannot
+    /// find any, it skips them. If it encounters an error at any point
+    /// during the transaction, it returns out immediately
+    pub fn get_extraction_policies_from_ids(
+        &self,
+        extraction_policy_ids: HashSet<String>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Option<Vec<indexify_internal_api::ExtractionPolicy>>, StateMachineError> {
+        let txn = db.transaction();
+
+        let mut policies = Vec::new();
+        for id in extraction_policy_ids.iter() {
+            let bytes_opt = txn
+                .get_cf(
+                    StateMachineColumns::ExtractionPolicies.cf(db),
+                    id.as_bytes(),
+                )
+                .map_err(|e| StateMachineError::TransactionError(e.to_string()))?;
+
+            if let Some(bytes) = bytes_opt {
+                let policy =
+                    serde_json::from_slice::<indexify_internal_api::ExtractionPolicy>(&bytes)
+                        .map_err(StateMachineError::SerializationError)?;
+                policies.push(policy);
+            }
+            // If None, the policy is not found; we simply skip it.
+        }
+
+        if policies.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(policies))
+        }
+    }
+
+    /// This method gets all task assignments stored in the relevant CF
+    /// Compares the desired assignments to the current ones and returns the
+    /// assignments, reassignments and unassignments needed to reach them.
+    /// Tasks already assigned to their desired executor are left out
+    pub fn plan_assignment_delta(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        desired: &HashMap<TaskId, ExecutorId>,
+    ) -> Result<AssignmentDelta, StateMachineError> {
+        let mut current = self.get_all_task_assignments(db)?;
+        let mut delta = AssignmentDelta::default();
+        for (task_id, executor_id) in desired {
+            match current.remove(task_id) {
+                Some(current_executor_id) if current_executor_id == *executor_id => {}
+                Some(current_executor_id) => {
+                    delta
+                        .reassigns
+                        .insert(task_id.clone(), (current_executor_id, executor_id.clone()));
+                }
+                None => {
+                    delta.assigns.insert(task_id.clone(), executor_id.clone());
+                }
+            }
+        }
+        delta.unassigns = current;
+        Ok(delta)
+    }
+
+    pub fn get_all_task_assignments(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<HashMap<TaskId, ExecutorId>, StateMachineError> {
+        let mut assignments = HashMap::new();
+        let iter = db.iterator_cf(
+            StateMachineColumns::TaskAssignments.cf(db),
+            rocksdb::IteratorMode::Start,
+        );
+        for item in iter {
+            let (key, value) = item.map_err(|e| {
+                StateMachineError::DatabaseError(format!(
+                    "unable to get values from task assignment {}",
+                    e
+                ))
+            })?;
+            let executor_id = String::from_utf8(key.to_vec()).map_err(|e| {
+                StateMachineError::DatabaseError(format!(
+                    "unable to get executor id from task assignment {}",
+                    e
+                ))
+            })?;
+            let task_ids: HashSet<TaskId> = JsonEncoder::decode(&value).map_err(|e| {
+                StateMachineError::DatabaseError(format!(
+                    "unable to decoded task hashset from task assignment {}",
+                    e
+                ))
+            })?;
+            for task_id in task_ids {
+                assignments.insert(task_id, executor_id.clone());
+            }
+        }
+        Ok(assignments)
+    }
+
+    /// Returns the number of tasks assigned to each executor, read in a single
+    /// scan of the task assignments
+    pub fn assigned_task_counts(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<HashMap<ExecutorId, usize>, StateMachineError> {
+        let mut counts = HashMap::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::TaskAssignments.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (executor_id, task_ids) =
+                item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let executor_id = String::from_utf8(executor_id.to_vec())
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let task_ids: HashSet<TaskId> = JsonEncoder::decode(&task_ids)?;
+            counts.insert(executor_id, task_ids.len());
+        }
+        Ok(counts)
+    }
+
+    /// Returns the executors whose in-memory running task count differs from
+    /// the number of tasks assigned to them, sorted by id. A missing count
+    /// is the same as a count of 0
+    pub fn running_task_count_drift(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<ExecutorId>, StateMachineError> {
+        let assigned_counts = self.assigned_task_counts(db)?;
+        let running_counts = self.executor_running_task_count.inner();
+        let mut drifted: Vec<ExecutorId> = assigned_counts
+            .keys()
+            .chain(running_counts.keys())
+            .filter(|executor_id| {
+                assigned_counts
+                    .get(*executor_id)
+                    .copied()
+                    .unwrap_or_default() !=
+                    running_counts
+                        .get(*executor_id)
+                        .copied()
+                        .unwrap_or_default()
+            })
+            .cloned()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        drifted.sort();
+        Ok(drifted)
+    }
+
+    /// Returns the summed cost of the tasks assigned to each executor.
+    /// Executors without assigned tasks are reported with a load of zero
+    pub fn executor_weighted_loads(&self) -> HashMap<ExecutorId, u64> {
+        let weighted_loads = self.executor_weighted_load.inner();
+        self.executor_running_task_count
+            .inner()
+            .into_keys()
+            .map(|executor_id| {
+                let load = weighted_loads
+                    .get(&executor_id)
+                    .copied()
+                    .unwrap_or_default();
+                (executor_id, load)
+            })
+            .collect()
+    }
+
+    /// Returns the cost of each of the given tasks. Tasks which aren't found
+    /// are left out
+    pub fn task_weights(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        task_ids: &HashSet<TaskId>,
+    ) -> Result<HashMap<TaskId, u64>, StateMachineError> {
+        let tasks = db.multi_get_cf(
+            task_ids
+                .iter()
+                .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+        );
+        let mut weights = HashMap::new();
+        for (task_id, task) in task_ids.iter().zip(tasks) {
+            let task = task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(task) = task {
+                let task: internal_api::Task = JsonEncoder::decode(&task)?;
+                weights.insert(task_id.clone(), task.weight());
+            }
+        }
+        Ok(weights)
+    }
+
+    /// This method returns the executors that haven't sent a heartbeat within
+    /// `ttl_secs` but still have tasks assigned to them, along with the
+    /// number of tasks each one holds
+    pub fn stale_executors_with_tasks(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        now_secs: u64,
+        ttl_secs: u64,
+    ) -> Result<Vec<(ExecutorId, usize)>, StateMachineError> {
+        let txn = db.transaction();
+        let mut stale_executors = Vec::new();
+        let iter = db.iterator_cf(
+            StateMachineColumns::Executors.cf(db),
+            rocksdb::IteratorMode::Start,
+        );
+        for item in iter {
+            let (_, value) = item.map_err(|e| {
+                StateMachineError::DatabaseError(format!("unable to read executors: {}", e))
+            })?;
+            let executor: internal_api::ExecutorMetadata = JsonEncoder::decode(&value)?;
+            if executor.last_seen.saturating_add(ttl_secs) >= now_secs {
+                continue;
+            }
+            let task_count = self
+                .get_task_assignments_for_executor(db, &txn, &executor.id)?
+                .len();
+            if task_count > 0 {
+                stale_executors.push((executor.id, task_count));
+            }
+        }
+        Ok(stale_executors)
+    }
+
+    /// Returns the ids of the content in a namespace that no extraction policy
+    /// has been applied to. Policies are recorded on the content when tasks are
+    /// created for it, so this surfaces content that never gets extracted
+    pub fn unmatched_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+    ) -> Result<Vec<ContentId>, StateMachineError> {
+        let content_ids = self
+            .content_namespace_table
+            .inner()
+            .get(namespace)
+            .cloned()
+            .unwrap_or_default();
+        let unmatched_content = self
+            .get_content_from_ids_with_version(content_ids, db)?
+            .into_iter()
+            .filter(|content| content.extraction_policy_ids.is_empty())
+            .map(|content| content.id.id)
+            .collect();
+        Ok(unmatched_content)
+    }
+
+    /// Returns the unfinished tasks whose content belongs to a namespace that
+    /// no longer exists, sorted by id. These are left behind when the deletion
+    /// of a namespace doesn't cascade to its tasks
+    pub fn tasks_in_deleted_namespaces(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<TaskId>, StateMachineError> {
+        let task_ids: Vec<TaskId> = self
+            .unfinished_tasks_by_extractor
+            .inner()
+            .into_values()
+            .flatten()
+            .sorted()
+            .collect();
+        let mut tasks = Vec::new();
+        for task in db.multi_get_cf(
+            task_ids
+                .iter()
+                .map(|task_id| (StateMachineColumns::Tasks.cf(db), task_id)),
+        ) {
+            let task = task.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if let Some(task) = task {
+                tasks.push(JsonEncoder::decode::<internal_api::Task>(&task)?);
+            }
+        }
+
+        let namespaces: Vec<NamespaceName> = tasks
+            .iter()
+            .map(|task| task.content_metadata.namespace.clone())
+            .unique()
+            .collect();
+        let mut deleted_namespaces = HashSet::new();
+        for (namespace, value) in namespaces.iter().zip(
+            db.multi_get_cf(
+                namespaces
+                    .iter()
+                    .map(|namespace| (StateMachineColumns::Namespaces.cf(db), namespace)),
+            ),
+        ) {
+            let value = value.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            if value.is_none() {
+                deleted_namespaces.insert(namespace);
+            }
+        }
+        Ok(tasks
+            .into_iter()
+            .filter(|task| deleted_namespaces.contains(&task.content_metadata.namespace))
+            .map(|task| task.id)
+            .collect())
+    }
+
+    /// Returns the fraction of content in a namespace that every extraction
+    /// policy applied to it has completed on. A policy is pending while its
+    /// completion time is 0. Content with no policies counts as complete
+    pub fn namespace_extraction_progress(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+    ) -> Result<f64, StateMachineError> {
+        let content_ids = self
+            .content_namespace_table
+            .inner()
+            .get(namespace)
+            .cloned()
+            .unwrap_or_default();
+        let content = self.get_content_from_ids_with_version(content_ids, db)?;
+        if content.is_empty() {
+            return Ok(1.0);
+        }
+        let completed = content
+            .iter()
+            .filter(|content| {
+                content
+                    .extraction_policy_ids
+                    .values()
+                    .all(|completion_time| *completion_time > 0)
+            })
+            .count();
+        Ok(completed as f64 / content.len() as f64)
+    }
+
+    /// Returns an estimate of the disk space in bytes used by the content of a
+    /// namespace. Content keys aren't prefixed by namespace, so the size is
+    /// approximated by RocksDB over the key of every content in the namespace.
+    /// Only data flushed to SST files is accounted for, and keys sharing a data
+    /// block with others may be under-counted
+    pub fn namespace_disk_usage(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+    ) -> Result<u64, StateMachineError> {
+        let content_ids = self
+            .content_namespace_table
+            .inner()
+            .get(namespace)
+            .cloned()
+            .unwrap_or_default();
+        let keys = content_ids
+            .iter()
+            .map(|content_id| {
+                let start = content_id.to_string().into_bytes();
+                let mut end = start.clone();
+                end.push(0);
+                (start, end)
+            })
+            .collect_vec();
+        let ranges = keys
+            .iter()
+            .map(|(start, end)| rocksdb::Range::new(start, end))
+            .collect_vec();
+        let sizes = db.get_approximate_sizes_cf(StateMachineColumns::ContentTable.cf(db), &ranges);
+        Ok(sizes.iter().sum())
+    }
+
+    /// Returns whether the content exists. RocksDB is first asked whether the
+    /// key may exist, which is answered from the bloom filter of the content
+    /// column family when one is configured, and only a possible hit is
+    /// confirmed with a read
+    pub fn content_exists(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        content_id: &ContentMetadataId,
+    ) -> Result<bool, StateMachineError> {
+        let cf = StateMachineColumns::ContentTable.cf(db);
+        let key = content_id.to_string();
+        if !db.key_may_exist_cf(cf, &key) {
+            return Ok(false);
+        }
+        let value = db
+            .get_pinned_cf(cf, &key)
+            .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+        Ok(value.is_some())
+    }
+
+    /// Streams the id of every content in the store, across all namespaces,
+    /// without decoding the content. The iterator reads from the implicit
+    /// snapshot RocksDB takes when it is created, so writes made while it is
+    /// consumed aren't seen. Since the versions of a content are stored under
+    /// adjacent keys, each id is yielded once
+    pub fn stream_all_content_ids<'a>(
+        &self,
+        db: &'a Arc<OptimisticTransactionDB>,
+    ) -> impl Iterator<Item = Result<ContentId, StateMachineError>> + 'a {
+        let mut read_opts = rocksdb::ReadOptions::default();
+        read_opts.fill_cache(false);
+        let mut iter = db.raw_iterator_cf_opt(StateMachineColumns::ContentTable.cf(db), read_opts);
+        iter.seek_to_first();
+        let mut previous_id: Option<ContentId> = None;
+        let mut done = false;
+        std::iter::from_fn(move || {
+            while !done {
+                let key = match iter.key() {
+                    Some(key) => String::from_utf8_lossy(key).into_owned(),
+                    None => {
+                        done = true;
+                        return iter
+                            .status()
+                            .err()
+                            .map(|e| Err(StateMachineError::DatabaseError(e.to_string())));
+                    }
+                };
+                iter.next();
+                let content_id = match ContentMetadataId::try_from(key) {
+                    Ok(content_id) => content_id.id,
+                    Err(e) => return Some(Err(StateMachineError::ExternalError(e))),
+                };
+                if previous_id.as_ref() != Some(&content_id) {
+                    previous_id = Some(content_id.clone());
+                    return Some(Ok(content_id));
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns a hash of every row of every state machine column family. The
+    /// rows of a column family are combined independently of their order, so
+    /// stores which applied the same requests have the same fingerprint
+    pub fn state_fingerprint(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<u64, StateMachineError> {
+        let mut hasher = Sha256::new();
+        for column in StateMachineColumns::iter() {
+            let checksum = self.cf_checksum(db, &column)?;
+            hasher.update(column.as_ref().as_bytes());
+            hasher.update(checksum.to_le_bytes());
+        }
+        Ok(digest_to_u64(&hasher.finalize()))
+    }
+
+    /// Sums the hashes of the rows of a column family
+    fn cf_checksum(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        column: &StateMachineColumns,
+    ) -> Result<u64, StateMachineError> {
+        let mut checksum = 0u64;
+        for item in db.iterator_cf(column.cf(db), rocksdb::IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            let mut hasher = Sha256::new();
+            hasher.update((key.len() as u64).to_le_bytes());
+            hasher.update(&key);
+            hasher.update(&value);
+            checksum = checksum.wrapping_add(digest_to_u64(&hasher.finalize()));
+        }
+        Ok(checksum)
+    }
+
+    /// Captures every row of a column family so that it can later be diffed
+    /// against the live column family. Rows that fail to be read are logged
+    /// and end the capture
+    pub fn capture_cf_snapshot(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        column: StateMachineColumns,
+    ) -> CfSnapshot {
+        let mut rows = HashMap::new();
+        for item in db.iterator_cf(column.cf(db), rocksdb::IteratorMode::Start) {
+            match item {
+                Ok((key, value)) => {
+                    rows.insert(key.to_vec(), value.to_vec());
+                }
+                Err(e) => {
+                    error!("error capturing snapshot of {}: {}", column, e);
+                    break;
+                }
+            }
+        }
+        CfSnapshot { rows }
+    }
+
+    /// Returns the keys of a column family that were added, removed or
+    /// modified since the snapshot was captured
+    pub fn diff_cf(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        column: StateMachineColumns,
+        snapshot: &CfSnapshot,
+    ) -> Result<CfDiff, StateMachineError> {
+        let mut diff = CfDiff::default();
+        let mut live_keys = HashSet::new();
+        for item in db.iterator_cf(column.cf(db), rocksdb::IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            match snapshot.rows.get(key.as_ref()) {
+                None => diff.added.push(key.to_vec()),
+                Some(old_value) if old_value.as_slice() != value.as_ref() => {
+                    diff.modified.push(key.to_vec())
+                }
+                Some(_) => {}
+            }
+            live_keys.insert(key.to_vec());
+        }
+        diff.removed = snapshot
+            .rows
+            .keys()
+            .filter(|key| !live_keys.contains(*key))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.modified.sort();
+        Ok(diff)
+    }
+
+    /// This method evaluates every extraction policy in the namespace of the
+    /// content and returns the ones that apply to it. A policy applies when
+    /// its content source, its label filters and the input mime types of its
+    /// extractor all match the content
+    pub fn matching_policies_for_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        content: &internal_api::ContentMetadata,
+    ) -> Result<Vec<internal_api::ExtractionPolicy>, StateMachineError> {
+        let extraction_policy_ids = self.extraction_policies_table.get(&content.namespace);
+        let extraction_policies = self
+            .get_extraction_policies_from_ids(extraction_policy_ids, db)?
+            .unwrap_or_default();
+
+        let mut matched_policies = Vec::new();
+        for extraction_policy in extraction_policies {
+            if self.policy_matches_content(db, &extraction_policy, content)? {
+                matched_policies.push(extraction_policy);
+            }
+        }
+        Ok(matched_policies)
+    }
+
+    fn policy_matches_content(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        extraction_policy: &internal_api::ExtractionPolicy,
+        content: &internal_api::ContentMetadata,
+    ) -> Result<bool, StateMachineError> {
+        //  The content source can either be the name of the policy or the id of the
+        // policy which produced the content
+        if extraction_policy.content_source != content.source {
+            let source_policy: Option<internal_api::ExtractionPolicy> =
+                self.get_from_cf(db, StateMachineColumns::ExtractionPolicies, &content.source)?;
+            match source_policy {
+                Some(source_policy) if source_policy.name == extraction_policy.content_source => {}
+                _ => return Ok(false),
+            }
+        }
+        let labels_match = extraction_policy
+            .filters
+            .iter()
+            .all(|(name, value)| content.labels.get(name).map_or(false, |v| v == value));
+        if !labels_match {
+            return Ok(false);
+        }
+        let extractor: Option<ExtractorDescription> = self.get_from_cf(
+            db,
+            StateMachineColumns::Extractors,
+            &extraction_policy.extractor,
+        )?;
+        Ok(extractor.map_or(false, |extractor| {
+            matches_mime_type(&extractor.input_mime_types, &content.content_type)
+        }))
+    }
+
+    /// This method will get the namespace based on the key provided
+    pub fn get_namespace(
+        &self,
+        namespace: &str,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Option<indexify_internal_api::Namespace>> {
+        let ns_name = match self.get_from_cf(db, StateMachineColumns::Namespaces, namespace)? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        let extraction_policy_ids = self.extraction_policies_table.get(&namespace.to_string());
+        let extraction_policies = self
+            .get_extraction_policies_from_ids(extraction_policy_ids, db)?
+            .unwrap_or_else(Vec::new);
+
+        Ok(Some(indexify_internal_api::Namespace {
+            name: ns_name,
+            extraction_policies,
+        }))
+    }
+
+    pub fn get_schemas(
+        &self,
+        ids: HashSet<String>,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<internal_api::StructuredDataSchema>> {
+        let txn = db.transaction();
+        let keys = ids
+            .iter()
+            .map(|id| (StateMachineColumns::StructuredDataSchemas.cf(db), id))
+            .collect_vec();
+        let schema_bytes = txn.multi_get_cf(keys);
+        let mut schemas = vec![];
+        for schema in schema_bytes {
+            let schema = schema
+                .map_err(|e| StateMachineError::DatabaseError(e.to_string()))?
+                .ok_or(StateMachineError::DatabaseError("Schema not found".into()))?;
+            let schema = JsonEncoder::decode(&schema)?;
+            schemas.push(schema);
+        }
+        Ok(schemas)
+    }
+
+    pub fn get_coordinator_addr(
+        &self,
+        node_id: NodeId,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Option<String>> {
+        self.get_from_cf(
+            db,
+            StateMachineColumns::CoordinatorAddress,
+            numeric_key(node_id),
+        )
+    }
+
+    /// Returns the coordinator address of every node, in the order of the node
+    /// ids
+    pub fn coordinator_addrs(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<(NodeId, String)>, StateMachineError> {
+        let mut addrs = Vec::new();
+        for item in db.iterator_cf(
+            StateMachineColumns::CoordinatorAddress.cf(db),
+            rocksdb::IteratorMode::Start,
+        ) {
+            let (key, value) = item.map_err(|e| StateMachineError::DatabaseError(e.to_string()))?;
+            addrs.push((decode_numeric_key(&key)?, JsonEncoder::decode(&value)?));
+        }
+        Ok(addrs)
+    }
+
+    /// Test utility method to get all key-value pairs from a column family
+    pub fn get_all_rows_from_cf<V>(
+        &self,
+        column: StateMachineColumns,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<Vec<(String, V)>, anyhow::Error>
+    where
+        V: DeserializeOwned,
+    {
+        self.get_all_rows_from_cf_with_options(column, db, &ScanOptions::default())
+    }
+
+    /// Gets all key-value pairs from a column family, reading with the scan
+    /// options. Large scans such as exports benefit from a read-ahead
+    pub fn get_all_rows_from_cf_with_options<V>(
+        &self,
+        column: StateMachineColumns,
+        db: &Arc<OptimisticTransactionDB>,
+        options: &ScanOptions,
+    ) -> Result<Vec<(String, V)>, anyhow::Error>
+    where
+        V: DeserializeOwned,
+    {
+        let cf_handle = db.cf_handle(column.as_ref()).ok_or(anyhow::anyhow!(
+            "Failed to get column family {}",
+            column.to_string()
+        ))?;
+        let iter = db.iterator_cf_opt(
+            cf_handle,
+            options.read_options(),
+            rocksdb::IteratorMode::Start,
+        );
+
+        iter.map(|item| {
+            item.map_err(|e| anyhow::anyhow!(e))
+                .and_then(|(key, value)| {
+                    let key = String::from_utf8(key.to_vec())
+                        .map_err(|e| anyhow::anyhow!("UTF-8 conversion error for key: {}", e))?;
+                    let value = JsonEncoder::decode(&value)
+                        .map_err(|e| anyhow::anyhow!("Deserialization error for value: {}", e))?;
+                    Ok((key, value))
+                })
+        })
+        .collect::<Result<Vec<(String, V)>, _>>()
+    }
+
+    /// Same as get_all_rows_from_cf, except that a value which fails to decode
+    /// is logged and skipped instead of failing the scan. The keys of the
+    /// skipped values are returned along with the rows. Errors reading from
+    /// RocksDB still fail the scan
+    pub fn get_all_rows_from_cf_skip_corrupt<V>(
+        &self,
+        column: StateMachineColumns,
+        db: &Arc<OptimisticTransactionDB>,
+    ) -> Result<ScanResult<V>, anyhow::Error>
+    where
+        V: DeserializeOwned,
+    {
+        let cf_handle = db.cf_handle(column.as_ref()).ok_or(anyhow::anyhow!(
+            "Failed to get column family {}",
+            column.to_string()
+        ))?;
+        let mut result = ScanResult {
+            rows: Vec::new(),
+            corrupt_keys: Vec::new(),
+        };
+        for item in db.iterator_cf(cf_handle, rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            let key = String::from_utf8_lossy(&key).to_string();
+            match JsonEncoder::decode(&value) {
+                Ok(value) => result.rows.push((key, value)),
+                Err(e) => {
+                    warn!("skipping corrupt value of key {} in {}: {}", key, column, e);
+                    result.corrupt_keys.push(key);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Reads the RocksDB write stall stats so that ingestion can be throttled
+    /// before writes block. Stats that can't be read are treated as no stall
+    pub fn write_backpressure(&self, db: &Arc<OptimisticTransactionDB>) -> BackpressureStatus {
+        let read_property = |name: &str| match db.property_int_value(name) {
+            Ok(value) => value.unwrap_or(0),
+            Err(e) => {
+                warn!("unable to read rocksdb property {}: {}", name, e);
+                0
+            }
+        };
+        if read_property("rocksdb.is-write-stopped") > 0 {
+            return BackpressureStatus::Stopped;
+        }
+        match read_property("rocksdb.actual-delayed-write-rate") {
+            0 => BackpressureStatus::Normal,
+            delayed_write_rate => BackpressureStatus::Delayed { delayed_write_rate },
+        }
+    }
+
+    //  END READER METHODS FOR ROCKSDB FORWARD INDEXES
+
+    //  START READER METHODS FOR REVERSE INDEXES
+    pub fn get_unassigned_tasks(&self) -> HashSet<TaskId> {
+        self.unassigned_tasks.inner()
+    }
+
+    pub fn get_unprocessed_state_changes(&self) -> HashSet<StateChangeId> {
+        self.unprocessed_state_changes.inner()
+    }
+
+    pub fn get_content_namespace_table(
+        &self,
+    ) -> HashMap<NamespaceName, HashSet<ContentMetadataId>> {
+        self.content_namespace_table.inner()
+    }
+
+    pub fn get_extraction_policies_table(&self) -> HashMap<NamespaceName, HashSet<String>> {
+        self.extraction_policies_table.inner()
+    }
+
+    pub fn get_extractor_executors_table(&self) -> HashMap<ExtractorName, HashSet<ExecutorId>> {
+        self.extractor_executors_table.inner()
+    }
+
+    pub fn get_namespace_index_table(&self) -> HashMap<NamespaceName, HashSet<String>> {
+        self.namespace_index_table.inner()
+    }
+
+    pub fn get_unfinished_tasks_by_extractor(&self) -> HashMap<ExtractorName, HashSet<TaskId>> {
+        self.unfinished_tasks_by_extractor.inner()
+    }
+
+    pub fn get_executor_running_task_count(&self) -> HashMap<ExecutorId, usize> {
+        self.executor_running_task_count.inner()
+    }
+
+    pub fn get_executor_idle_since(&self) -> HashMap<ExecutorId, u64> {
+        self.executor_idle_since.inner()
+    }
+
+    pub fn get_executor_weighted_load(&self) -> HashMap<ExecutorId, u64> {
+        self.executor_weighted_load.inner()
+    }
+
+    /// Returns how long an executor has been without running tasks. It's None
+    /// for an executor which is running tasks or never finished one
+    pub fn idle_duration(&self, executor_id: &str, now_secs: u64) -> Option<u64> {
+        let running_task_count = self
+            .executor_running_task_count
+            .get(&executor_id.to_string())
+            .unwrap_or_default();
+        if running_task_count > 0 {
+            return None;
+        }
+        self.executor_idle_since
+            .get(executor_id)
+            .map(|idle_since| now_secs.saturating_sub(idle_since))
+    }
+
+    /// Returns the active schema of a namespace, if one was set
+    pub fn get_active_namespace_schema(
+        &self,
+        db: &Arc<OptimisticTransactionDB>,
+        namespace: &str,
+    ) -> Result<Option<internal_api::StructuredDataSchema>, StateMachineError> {
+        let schema_id: Option<SchemaId> = self
+            .get_from_cf(db, StateMachineColumns::ActiveNamespaceSchemas, namespace)
+            .map_err(StateMachineError::ExternalError)?;
+        match schema_id {
+            Some(schema_id) => self
+                .get_from_cf(db, StateMachineColumns::StructuredDataSchemas, schema_id)
+                .map_err(StateMachineError::ExternalError),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_schemas_by_namespace(&self) -> HashMap<NamespaceName, HashSet<SchemaId>> {
+        self.schemas_by_namespace.inner()
+    }
+
+    pub fn get_content_children_table(
+        &self,
+    ) -> HashMap<ContentMetadataId, HashSet<ContentMetadataId>> {
+        self.content_children_table.inner()
+    }
+
+    pub fn get_content_chunks_table(
+        &self,
+    ) -> HashMap<ContentMetadataId, BTreeMap<u32, ContentMetadataId>> {
+        self.content_chunks_table.inner()
+    }
+
+    pub fn get_pending_tasks_for_content(
+        &self,
+    ) -> HashMap<ContentMetadataId, HashMap<ExtractionPolicyId, HashSet<TaskId>>> {
+        self.pending_tasks_for_content.inner()
+    }
+
+    pub fn are_content_tasks_completed(&self, content_id: &ContentMetadataId) -> bool {
+        self.pending_tasks_for_content
+            .are_content_tasks_completed(content_id)
+    }
+
+    pub fn tasks_count(&self) -> usize {
+        self.unassigned_tasks.count() + self.unfinished_tasks_by_extractor.task_count()
+    }
+
+    pub fn executor_count(&self) -> usize {
+        self.executor_running_task_count.executor_count()
+    }
+
+    /// Reports the queue depth of every extractor to the sink. Unassigned
+    /// tasks are always unfinished, so they are broken down by extractor by
+    /// intersecting the two reverse indexes
+    pub fn emit_queue_depths(&self, sink: &impl QueueDepthSink) {
+        let unassigned_tasks = self.unassigned_tasks.inner();
+        for (extractor, task_ids) in self.unfinished_tasks_by_extractor.inner() {
+            let unassigned_count = task_ids
+                .iter()
+                .filter(|task_id| unassigned_tasks.contains(*task_id))
+                .count();
+            sink.record_queue_depth(&extractor, task_ids.len(), unassigned_count);
+        }
+    }
+
+    //  END READER METHODS FOR REVERSE INDEXES
+
+    //  START WRITER METHODS FOR REVERSE INDEXES
+    pub fn insert_executor_running_task_count(&self, executor_id: &str, tasks: u64) {
+        self.executor_running_task_count
+            .insert(&executor_id.to_string(), tasks as usize);
+    }
+
+    //  END WRITER METHODS FOR REVERSE INDEXES
+
+    //  START SNAPSHOT METHODS
+    pub fn build_snapshot(&self) -> IndexifyStateSnapshot {
+        self.reverse_index_barrier();
+        IndexifyStateSnapshot {
+            unassigned_tasks: self.get_unassigned_tasks(),
+            unprocessed_state_changes: self.get_unprocessed_state_changes(),
+            content_namespace_table: self.get_content_namespace_table(),
+            extraction_policies_table: self.get_extraction_policies_table(),
+            extractor_executors_table: self.get_extractor_executors_table(),
+            namespace_index_table: self.get_namespace_index_table(),
+            unfinished_tasks_by_extractor: self.get_unfinished_tasks_by_extractor(),
+            executor_running_task_count: self.get_executor_running_task_count(),
+            executor_weighted_load: self.get_executor_weighted_load(),
+            executor_idle_since: self.get_executor_idle_since(),
+            schemas_by_namespace: self.get_schemas_by_namespace(),
+            content_children_table: self.get_content_children_table(),
+            content_chunks_table: self.get_content_chunks_table(),
+            pending_tasks_for_content: self.get_pending_tasks_for_content(),
+            metrics: self.metrics.lock().unwrap().clone(),
+        }
+    }
+
+    pub fn install_snapshot(&self, snapshot: IndexifyStateSnapshot) {
+        self.reverse_index_barrier();
+        let mut unassigned_tasks_guard = self.unassigned_tasks.unassigned_tasks.write().unwrap();
+        let mut unprocessed_state_changes_guard = self
+            .unprocessed_state_changes
+            .unprocessed_state_changes
+            .write()
+            .unwrap();
+        let mut content_namespace_table_guard = self
+            .content_namespace_table
+            .content_namespace_table
+            .write()
+            .unwrap();
+        let mut extraction_policies_table_guard = self
+            .extraction_policies_table
+            .extraction_policies_table
+            .write()
+            .unwrap();
+        let mut extractor_executors_table_guard = self
+            .extractor_executors_table
+            .extractor_executors_table
+            .write()
+            .unwrap();
+        let mut namespace_index_table_guard = self
+            .namespace_index_table
+            .namespace_index_table
+            .write()
+            .unwrap();
+        let mut unfinished_tasks_by_extractor_guard = self
+            .unfinished_tasks_by_extractor
+            .unfinished_tasks_by_extractor
+            .write()
+            .unwrap();
+        let mut executor_running_task_count_guard = self
+            .executor_running_task_count
+            .executor_running_task_count
+            .write()
+            .unwrap();
+        let mut executor_weighted_load_guard = self
+            .executor_weighted_load
+            .executor_weighted_load
+            .write()
+            .unwrap();
+        let mut executor_idle_since_guard = self
+            .executor_idle_since
+            .executor_idle_since
+            .write()
+            .unwrap();
+        let mut schemas_by_namespace_guard = self
+            .schemas_by_namespace
+            .schemas_by_namespace
+            .write()
+            .unwrap();
+        let mut content_children_table_guard = self
+            .content_children_table
+            .content_children_table
+            .write()
+            .unwrap();
+        let mut content_chunks_table_guard = self
+            .content_chunks_table
+            .content_chunks_table
+            .write()
+            .unwrap();
+
+        *unassigned_tasks_guard = snapshot.unassigned_tasks;
+        *unprocessed_state_changes_guard = snapshot.unprocessed_state_changes;
+        *content_namespace_table_guard = snapshot.content_namespace_table;
+        *extraction_policies_table_guard = snapshot.extraction_policies_table;
+        *extractor_executors_table_guard = snapshot.extractor_executors_table;
+        *namespace_index_table_guard = snapshot.namespace_index_table;
+        *unfinished_tasks_by_extractor_guard = snapshot.unfinished_tasks_by_extractor;
+        *executor_running_task_count_guard = snapshot.executor_running_task_count;
+        *executor_weighted_load_guard = snapshot.executor_weighted_load;
+        *executor_idle_since_guard = snapshot.executor_idle_since;
+        *schemas_by_namespace_guard = snapshot.schemas_by_namespace;
+        *content_children_table_guard = snapshot.content_children_table;
+        *content_chunks_table_guard = snapshot.content_chunks_table;
+        self.metrics.lock().unwrap().clone_from(&snapshot.metrics);
+    }
+
+    /// Captures every reverse index in a sorted form, so the snapshots of two
+    /// nodes which applied the same requests compare and serialize equal
+    pub fn reverse_index_snapshot(&self) -> ReverseIndexSnapshot {
+        self.reverse_index_barrier();
+        ReverseIndexSnapshot {
+            unassigned_tasks: self.get_unassigned_tasks().into_iter().collect(),
+            unprocessed_state_changes: self.get_unprocessed_state_changes().into_iter().collect(),
+            content_namespace_table: sorted_index(self.get_content_namespace_table()),
+            extraction_policies_table: sorted_index(self.get_extraction_policies_table()),
+            extractor_executors_table: sorted_index(self.get_extractor_executors_table()),
+            namespace_index_table: sorted_index(self.get_namespace_index_table()),
+            unfinished_tasks_by_extractor: sorted_index(self.get_unfinished_tasks_by_extractor()),
+            executor_running_task_count: self
+                .get_executor_running_task_count()
+                .into_iter()
+                .collect(),
+            executor_weighted_load: self.get_executor_weighted_load().into_iter().collect(),
+            executor_idle_since: self.get_executor_idle_since().into_iter().collect(),
+            schemas_by_namespace: sorted_index(self.get_schemas_by_namespace()),
+            content_children_table: sorted_index(self.get_content_children_table()),
+            content_chunks_table: self
+                .get_content_chunks_table()
+                .into_iter()
+                .map(|(content_id, chunks)| {
+                    let chunks = chunks
+                        .into_iter()
+                        .map(|(index, chunk_id)| (index, chunk_id.to_string()))
+                        .collect();
+                    (content_id.to_string(), chunks)
+                })
+                .collect(),
+            pending_tasks_for_content: self
+                .get_pending_tasks_for_content()
+                .into_iter()
+                .map(|(content_id, tasks)| (content_id.to_string(), sorted_index(tasks)))
+                .collect(),
+        }
+    }
+    //  END SNAPSHOT METHODS
+}
+
+/// Logs at debug level the entries of the reverse indexes which differ
+/// between two snapshots, as the old and new value of each changed key
+fn log_reverse_index_mutations(before: &ReverseIndexSnapshot, after: &ReverseIndexSnapshot) {
+    let (before, after) = match (serde_json::to_value(before), serde_json::to_value(after)) {
+        (Ok(serde_json::Value::Object(before)), Ok(serde_json::Value::Object(after))) => {
+            (before, after)
+        }
+        _ => return,
+    };
+    let null = serde_json::Value::Null;
+    for (index, old) in &before {
+        let new = after.get(index).unwrap_or(&null);
+        match (old, new) {
+            (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+                let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+                for key in keys {
+                    let old_value = old.get(key).unwrap_or(&null);
+                    let new_value = new.get(key).unwrap_or(&null);
+                    if old_value != new_value {
+                        tracing::debug!(
+                            "reverse index {} key {}: {} -> {}",
+                            index,
+                            key,
+                            old_value,
+                            new_value
+                        );
+                    }
+                }
+            }
+            (serde_json::Value::Array(old), serde_json::Value::Array(new)) => {
+                for value in new.iter().filter(|value| !old.contains(value)) {
+                    tracing::debug!("reverse index {}: inserted {}", index, value);
+                }
+                for value in old.iter().filter(|value| !new.contains(value)) {
+                    tracing::debug!("reverse index {}: removed {}", index, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Sorts a reverse index mapping keys to sets, keying it by the string form
+/// of its keys and values
+fn sorted_index<K: fmt::Display, V: fmt::Display>(
+    index: HashMap<K, HashSet<V>>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    index
+        .into_iter()
+        .map(|(key, values)| {
+            let values = values.iter().map(|value| value.to_string()).collect();
+            (key.to_string(), values)
+        })
+        .collect()
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+pub struct IndexifyStateSnapshot {
+    unassigned_tasks: HashSet<TaskId>,
+    unprocessed_state_changes: HashSet<StateChangeId>,
+    content_namespace_table: HashMap<NamespaceName, HashSet<ContentMetadataId>>,
+    extraction_policies_table: HashMap<NamespaceName, HashSet<String>>,
+    extractor_executors_table: HashMap<ExtractorName, HashSet<ExecutorId>>,
+    namespace_index_table: HashMap<NamespaceName, HashSet<String>>,
+    unfinished_tasks_by_extractor: HashMap<ExtractorName, HashSet<TaskId>>,
+    executor_running_task_count: HashMap<ExecutorId, usize>,
+    #[serde(default)]
+    executor_weighted_load: HashMap<ExecutorId, u64>,
+    #[serde(default)]
+    executor_idle_since: HashMap<ExecutorId, u64>,
+    schemas_by_namespace: HashMap<NamespaceName, HashSet<SchemaId>>,
+    content_children_table: HashMap<ContentMetadataId, HashSet<ContentMetadataId>>,
+    #[serde(default)]
+    content_chunks_table: HashMap<ContentMetadataId, BTreeMap<u32, ContentMetadataId>>,
+    pending_tasks_for_content:
+        HashMap<ContentMetadataId, HashMap<ExtractionPolicyId, HashSet<TaskId>>>,
+    metrics: Metrics,
+}
+
+/// The reverse indexes of a node, sorted so they can be compared across nodes
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReverseIndexSnapshot {
+    pub unassigned_tasks: BTreeSet<TaskId>,
+    pub unprocessed_state_changes: BTreeSet<StateChangeId>,
+    pub content_namespace_table: BTreeMap<NamespaceName, BTreeSet<String>>,
+    pub extraction_policies_table: BTreeMap<NamespaceName, BTreeSet<String>>,
+    pub extractor_executors_table: BTreeMap<ExtractorName, BTreeSet<ExecutorId>>,
+    pub namespace_index_table: BTreeMap<NamespaceName, BTreeSet<String>>,
+    pub unfinished_tasks_by_extractor: BTreeMap<ExtractorName, BTreeSet<TaskId>>,
+    pub executor_running_task_count: BTreeMap<ExecutorId, usize>,
+    pub executor_weighted_load: BTreeMap<ExecutorId, u64>,
+    pub executor_idle_since: BTreeMap<ExecutorId, u64>,
+    pub schemas_by_namespace: BTreeMap<NamespaceName, BTreeSet<SchemaId>>,
+    pub content_children_table: BTreeMap<String, BTreeSet<String>>,
+    pub content_chunks_table: BTreeMap<String, BTreeMap<u32, String>>,
+    pub pending_tasks_for_content: BTreeMap<String, BTreeMap<ExtractionPolicyId, BTreeSet<TaskId>>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::state_machine_utils::{
+        apply_payload,
+        open_test_db,
+        register_executor,
+        test_task,
+    };
+
+    #[test]
+    fn test_stale_executors_with_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "stale_executor", "extractor", 100);
+        register_executor(&state, &db, "fresh_executor", "extractor", 1000);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([
+                    ("task_1".to_string(), "stale_executor".to_string()),
+                    ("task_2".to_string(), "stale_executor".to_string()),
+                    ("task_3".to_string(), "fresh_executor".to_string()),
+                ]),
+            },
+        );
+
+        let stale = state.stale_executors_with_tasks(&db, 1010, 60).unwrap();
+        assert_eq!(stale, vec![("stale_executor".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_increment_running_task_count() {
+        let executor_running_task_count = ExecutorRunningTaskCount::new();
+        let executor_id = "executor_id".to_string();
+        executor_running_task_count.insert(&executor_id, 0);
+        executor_running_task_count.increment_running_task_count(&executor_id);
+        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 1);
+        executor_running_task_count.increment_running_task_count(&executor_id);
+        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_decrement_running_task_count() {
+        let executor_running_task_count = ExecutorRunningTaskCount::new();
+        let executor_id = "executor_id".to_string();
+        executor_running_task_count.insert(&executor_id, 2);
+        executor_running_task_count.decrement_running_task_count(&executor_id);
+        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 1);
+        executor_running_task_count.decrement_running_task_count(&executor_id);
+        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 0);
+    }
+
+    fn create_extraction_policy(
+        state: &IndexifyState,
+        db: &Arc<OptimisticTransactionDB>,
+        extraction_policy: &internal_api::ExtractionPolicy,
+    ) {
+        apply_payload(
+            state,
+            db,
+            RequestPayload::CreateExtractionPolicy {
+                extraction_policy: extraction_policy.clone(),
+                updated_structured_data_schema: None,
+                new_structured_data_schema: internal_api::StructuredDataSchema::new(
+                    &extraction_policy.name,
+                    &extraction_policy.namespace,
+                ),
+            },
+        );
+    }
+
+    #[test]
+    fn test_matching_policies_for_content() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        for (executor_id, extractor, mime_type) in [
+            ("text_executor", "text_extractor", "text/plain"),
+            ("image_executor", "image_extractor", "image/png"),
+        ] {
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::RegisterExecutor {
+                    addr: "addr".into(),
+                    executor_id: executor_id.into(),
+                    extractors: vec![ExtractorDescription {
+                        name: extractor.into(),
+                        input_mime_types: vec![mime_type.into()],
+                        ..Default::default()
+                    }],
+                    ts_secs: 0,
+                    labels: HashMap::new(),
+                },
+            );
+        }
+        let text_policy = internal_api::ExtractionPolicy {
+            id: "text_policy".into(),
+            name: "text_policy".into(),
+            namespace: "namespace".into(),
+            extractor: "text_extractor".into(),
+            content_source: "ingestion".into(),
+            ..Default::default()
+        };
+        let image_policy = internal_api::ExtractionPolicy {
+            id: "image_policy".into(),
+            name: "image_policy".into(),
+            namespace: "namespace".into(),
+            extractor: "image_extractor".into(),
+            content_source: "ingestion".into(),
+            ..Default::default()
+        };
+        create_extraction_policy(&state, &db, &text_policy);
+        create_extraction_policy(&state, &db, &image_policy);
+
+        let content = internal_api::ContentMetadata {
+            namespace: "namespace".into(),
+            content_type: "text/plain".into(),
+            source: "ingestion".into(),
+            ..Default::default()
+        };
+        let policies = state.matching_policies_for_content(&db, &content).unwrap();
+        assert_eq!(policies, vec![text_policy]);
+    }
+
+    #[test]
+    fn test_reject_extraction_policy_with_invalid_filters() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let extraction_policy = internal_api::ExtractionPolicy {
+            id: "policy".into(),
+            name: "policy".into(),
+            namespace: "namespace".into(),
+            filters: HashMap::from([("key:1".to_string(), "value".to_string())]),
+            ..Default::default()
+        };
+        let err = state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateExtractionPolicy {
+                        extraction_policy: extraction_policy.clone(),
+                        updated_structured_data_schema: None,
+                        new_structured_data_schema: internal_api::StructuredDataSchema::new(
+                            &extraction_policy.name,
+                            &extraction_policy.namespace,
+                        ),
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("\"key:1\" = \"value\""));
+        let stored: Option<internal_api::ExtractionPolicy> = state
+            .get_from_cf(&db, StateMachineColumns::ExtractionPolicies, "policy")
+            .unwrap();
+        assert!(stored.is_none());
+    }
+
+    #[derive(Default)]
+    struct MockQueueDepthSink {
+        depths: std::sync::Mutex<HashMap<String, (usize, usize)>>,
+    }
+
+    impl QueueDepthSink for MockQueueDepthSink {
+        fn record_queue_depth(
+            &self,
+            extractor: &str,
+            unfinished_tasks: usize,
+            unassigned_tasks: usize,
+        ) {
+            self.depths
+                .lock()
+                .unwrap()
+                .insert(extractor.to_string(), (unfinished_tasks, unassigned_tasks));
+        }
+    }
+
+    #[test]
+    fn test_emit_queue_depths() {
+        let state = IndexifyState::default();
+        state
+            .apply(StateMachineUpdateRequest {
+                payload: RequestPayload::CreateTasks {
+                    tasks: vec![
+                        test_task("task_1", "extractor_1"),
+                        test_task("task_2", "extractor_1"),
+                        test_task("task_3", "extractor_1"),
+                        test_task("task_4", "extractor_2"),
+                    ],
+                },
+                new_state_changes: vec![],
+                state_changes_processed: vec![],
+                correlation_id: None,
+            })
+            .unwrap();
+        state
+            .apply(StateMachineUpdateRequest {
+                payload: RequestPayload::AssignTask {
+                    assignments: HashMap::from([("task_1".to_string(), "executor".to_string())]),
+                },
+                new_state_changes: vec![],
+                state_changes_processed: vec![],
+                correlation_id: None,
+            })
+            .unwrap();
+
+        let sink = MockQueueDepthSink::default();
+        state.emit_queue_depths(&sink);
+        let depths = sink.depths.into_inner().unwrap();
+        assert_eq!(depths.len(), 2);
+        assert_eq!(depths.get("extractor_1"), Some(&(3, 2)));
+        assert_eq!(depths.get("extractor_2"), Some(&(1, 1)));
+    }
+
+    #[test]
+    fn test_cancel_content_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = internal_api::ContentMetadata::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task_1".to_string(), "executor".to_string())]),
+            },
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(1)
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CancelContentTasks {
+                content_id: content.id.clone(),
+            },
+        );
+
+        assert!(state.unassigned_tasks.inner().is_empty());
+        assert!(state
+            .unfinished_tasks_by_extractor
+            .inner()
+            .get("extractor")
+            .map_or(true, |tasks| tasks.is_empty()));
+        assert!(state
+            .pending_tasks_for_content
+            .are_content_tasks_completed(&content.id));
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert!(state
+            .get_tasks_for_executor("executor", None, &db)
+            .unwrap()
+            .is_empty());
+        for task_id in ["task_1", "task_2"] {
+            let task: internal_api::Task = state
+                .get_from_cf(&db, StateMachineColumns::Tasks, task_id)
+                .unwrap()
+                .unwrap();
+            assert_eq!(task.outcome, TaskOutcome::Cancelled);
+        }
+    }
+
+    #[test]
+    fn test_task_executor() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let mut task = test_task("task", "extractor");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![task.clone()],
+            },
+        );
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task".to_string(), "executor".to_string())]),
+            },
+        );
+        assert_eq!(
+            state.task_executor(&db, "task").unwrap(),
+            Some("executor".to_string())
+        );
+
+        task.outcome = TaskOutcome::Success;
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateTask {
+                task,
+                executor_id: Some("executor".to_string()),
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH,
+                cancelled: false,
+                artifact_ref: None,
+            },
+        );
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+    }
+
+    /// Creates a task for the default content and assigns it to an executor
+    fn create_assigned_task(
+        state: &IndexifyState,
+        db: &Arc<OptimisticTransactionDB>,
+        task_id: &str,
+        executor_id: &str,
+    ) -> internal_api::Task {
+        register_executor(state, db, executor_id, "extractor", 0);
+        apply_payload(
+            state,
+            db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let task = test_task(task_id, "extractor");
+        apply_payload(
+            state,
+            db,
+            RequestPayload::CreateTasks {
+                tasks: vec![task.clone()],
+            },
+        );
+        apply_payload(
+            state,
+            db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([(task_id.to_string(), executor_id.to_string())]),
+            },
+        );
+        task
+    }
+
+    fn update_task(
+        state: &IndexifyState,
+        db: &Arc<OptimisticTransactionDB>,
+        task: &internal_api::Task,
+        outcome: TaskOutcome,
+        executor_id: &str,
+    ) {
+        apply_payload(
+            state,
+            db,
+            RequestPayload::UpdateTask {
+                task: internal_api::Task {
+                    outcome,
+                    ..task.clone()
+                },
+                executor_id: Some(executor_id.to_string()),
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH,
+                cancelled: outcome == TaskOutcome::Cancelled,
+                artifact_ref: None,
+            },
+        );
+    }
+
+    fn stored_task(
+        state: &IndexifyState,
+        db: &Arc<OptimisticTransactionDB>,
+        task_id: &str,
+    ) -> internal_api::Task {
+        state
+            .get_from_cf(db, StateMachineColumns::Tasks, task_id)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_task_cancel_acknowledged_by_executor() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RequestTaskCancel {
+                task_id: "task".to_string(),
+            },
+        );
+        let requested = stored_task(&state, &db, "task");
+        assert!(requested.cancel_requested);
+        assert_eq!(requested.outcome, TaskOutcome::Unknown);
+        assert_eq!(
+            state.task_executor(&db, "task").unwrap(),
+            Some("executor".to_string())
+        );
+
+        update_task(&state, &db, &task, TaskOutcome::Cancelled, "executor");
+        assert_eq!(
+            stored_task(&state, &db, "task").outcome,
+            TaskOutcome::Cancelled
+        );
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert_eq!(state.unfinished_tasks_by_extractor.task_count(), 0);
+        assert!(state
+            .pending_tasks_for_content
+            .are_content_tasks_completed(&task.content_metadata.id));
+    }
+
+    #[test]
+    fn test_task_cancel_after_executor_completes() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RequestTaskCancel {
+                task_id: "task".to_string(),
+            },
+        );
+        update_task(&state, &db, &task, TaskOutcome::Success, "executor");
+        update_task(&state, &db, &task, TaskOutcome::Cancelled, "executor");
+
+        assert_eq!(
+            stored_task(&state, &db, "task").outcome,
+            TaskOutcome::Success
+        );
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert_eq!(state.unfinished_tasks_by_extractor.task_count(), 0);
+    }
+
+    #[test]
+    fn test_cancel_of_unknown_task_is_dropped() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RequestTaskCancel {
+                task_id: "unknown".to_string(),
+            },
+        );
+        update_task(
+            &state,
+            &db,
+            &test_task("unknown", "extractor"),
+            TaskOutcome::Cancelled,
+            "executor",
+        );
+
+        let task: Option<internal_api::Task> = state
+            .get_from_cf(&db, StateMachineColumns::Tasks, "unknown")
+            .unwrap();
+        assert!(task.is_none());
+    }
+
+    #[test]
+    fn test_unmatched_content() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let matched_content = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("matched"),
+            namespace: "namespace".into(),
+            ..Default::default()
+        };
+        let unmatched_content = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("unmatched"),
+            namespace: "namespace".into(),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![matched_content.clone(), unmatched_content.clone()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![internal_api::Task {
+                    content_metadata: matched_content,
+                    ..test_task("task", "extractor")
+                }],
+            },
+        );
+
+        let unmatched = state.unmatched_content(&db, "namespace").unwrap();
+        assert_eq!(unmatched, vec!["unmatched".to_string()]);
+    }
+
+    #[test]
+    fn test_promote_index_generation() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let first_generation = internal_api::Index {
+            name: "index".into(),
+            namespace: "namespace".into(),
+            table_name: "index_table".into(),
+            ..Default::default()
+        };
+        let second_generation = internal_api::Index {
+            table_name: "index_table_g1".into(),
+            generation: 1,
+            ..first_generation.clone()
+        };
+        for index in [&first_generation, &second_generation] {
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::CreateIndex {
+                    index: index.clone(),
+                    namespace: "namespace".into(),
+                    id: "index_id".into(),
+                },
+            );
+        }
+        let index_ids = HashSet::from(["index_id".to_string()]);
+
+        //  Reads are served by the first generation until the new one is promoted
+        assert_eq!(
+            state.get_indexes_from_ids(index_ids.clone(), &db).unwrap(),
+            vec![first_generation]
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::PromoteIndexGeneration {
+                index_id: "index_id".into(),
+                generation: 1,
+            },
+        );
+        assert_eq!(
+            state.get_active_index_generation(&db, "index_id").unwrap(),
+            1
+        );
+        assert_eq!(
+            state.get_indexes_from_ids(index_ids, &db).unwrap(),
+            vec![second_generation]
+        );
+    }
+
+    #[test]
+    fn test_delete_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+        update_task(&state, &db, &task, TaskOutcome::Success, "executor");
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::DeleteTasks {
+                task_ids: vec!["task".to_string(), "missing_task".to_string()],
+            },
+        );
+
+        let deleted: Option<internal_api::Task> = state
+            .get_from_cf(&db, StateMachineColumns::Tasks, "task")
+            .unwrap();
+        assert!(deleted.is_none());
+        assert!(state.unassigned_tasks.inner().is_empty());
+        assert_eq!(state.unfinished_tasks_by_extractor.task_count(), 0);
+        assert!(state
+            .pending_tasks_for_content
+            .are_content_tasks_completed(&task.content_metadata.id));
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+        assert!(state
+            .get_tasks_for_executor("executor", None, &db)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_namespace_extraction_progress() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content =
+            |id: &str, extraction_policy_ids: &[(&str, u64)]| internal_api::ContentMetadata {
+                id: ContentMetadataId::new(id),
+                namespace: "namespace".into(),
+                extraction_policy_ids: extraction_policy_ids
+                    .iter()
+                    .map(|(policy, completion_time)| (policy.to_string(), *completion_time))
+                    .collect(),
+                ..Default::default()
+            };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    content("complete", &[("policy_1", 10), ("policy_2", 20)]),
+                    content("no_policies", &[]),
+                    content("pending", &[("policy_1", 10), ("policy_2", 0)]),
+                ],
+                expected_version: None,
+            },
+        );
+
+        let progress = state
+            .namespace_extraction_progress(&db, "namespace")
+            .unwrap();
+        assert!((progress - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_register_executor_and_claim() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "other_extractor"),
+                ],
+            },
+        );
+
+        let labels = HashMap::from([("zone".to_string(), "us-east-1".to_string())]);
+        let outcome = state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::RegisterExecutorAndClaim {
+                        addr: "addr".into(),
+                        executor_id: "executor".into(),
+                        extractors: vec![ExtractorDescription {
+                            name: "extractor".into(),
+                            ..Default::default()
+                        }],
+                        ts_secs: 0,
+                        max: 10,
+                        labels: labels.clone(),
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+
+        //  The claimed tasks are returned in the outcome of the request
+        let claimed: Vec<TaskId> = serde_json::from_str(&outcome.unwrap()).unwrap();
+        assert_eq!(claimed, vec!["task_1".to_string(), "task_2".to_string()]);
+        let assigned: HashSet<TaskId> = state
+            .get_tasks_for_executor("executor", None, &db)
+            .unwrap()
+            .into_iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(assigned, claimed.into_iter().collect());
+        let executor: Option<internal_api::ExecutorMetadata> = state
+            .get_from_cf(&db, StateMachineColumns::Executors, "executor")
+            .unwrap();
+        assert_eq!(executor.unwrap().labels, labels);
+        assert_eq!(
+            state.unassigned_tasks.inner(),
+            HashSet::from(["task_3".to_string()])
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(2)
+        );
+        assert_eq!(
+            state.task_executor(&db, "task_1").unwrap(),
+            Some("executor".to_string())
+        );
+    }
+
+    #[test]
+    fn test_replace_schema_and_backfill() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let old_schema = internal_api::StructuredDataSchema::new("ingestion", "namespace");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateNamespace {
+                name: "namespace".into(),
+                structured_data_schema: old_schema.clone(),
+            },
+        );
+        let content = internal_api::ContentMetadata {
+            namespace: "namespace".into(),
+            source: "ingestion".into(),
+            labels: HashMap::from([("author".to_string(), "alice".to_string())]),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: None,
+            },
+        );
+
+        let new_schema = internal_api::StructuredDataSchema {
+            id: "new_schema".into(),
+            ..old_schema.clone()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::ReplaceSchema {
+                old_id: old_schema.id.clone(),
+                new_schema: new_schema.clone(),
+                transform: SchemaTransform::RenameColumn {
+                    from: "author".into(),
+                    to: "writer".into(),
+                },
+            },
+        );
+        assert_eq!(
+            state.schemas_by_namespace.inner().get("namespace"),
+            Some(&HashSet::from(["new_schema".to_string()]))
+        );
+        let old: Option<internal_api::StructuredDataSchema> = state
+            .get_from_cf(
+                &db,
+                StateMachineColumns::StructuredDataSchemas,
+                &old_schema.id,
+            )
+            .unwrap();
+        assert!(old.is_none());
+
+        let backfill = || -> usize {
+            let outcome = state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::RunSchemaBackfill {
+                            schema_id: "new_schema".into(),
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+            serde_json::from_str(&outcome.unwrap()).unwrap()
+        };
+        assert_eq!(backfill(), 1);
+        let backfilled = state
+            .get_content_from_ids_with_version(HashSet::from([content.id.clone()]), &db)
+            .unwrap();
+        assert_eq!(
+            backfilled[0].labels,
+            HashMap::from([("writer".to_string(), "alice".to_string())])
+        );
+
+        //  The backfill only runs once
+        assert_eq!(backfill(), 0);
+    }
+
+    #[test]
+    fn test_write_backpressure() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        assert_eq!(state.write_backpressure(&db), BackpressureStatus::Normal);
+    }
+
+    #[test]
+    fn test_list_tasks_by_outcome() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let tasks = [
+            ("failed_1", "extractor_1", TaskOutcome::Failed),
+            ("failed_2", "extractor_2", TaskOutcome::Failed),
+            ("succeeded", "extractor_1", TaskOutcome::Success),
+            ("pending", "extractor_2", TaskOutcome::Unknown),
+        ]
+        .into_iter()
+        .map(|(id, extractor, outcome)| internal_api::Task {
+            outcome,
+            ..test_task(id, extractor)
+        })
+        .collect();
+        apply_payload(&state, &db, RequestPayload::CreateTasks { tasks });
+
+        let failed: HashSet<TaskId> = state
+            .list_tasks_by_outcome(&db, TaskOutcome::Failed, 10)
+            .unwrap()
+            .into_iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(
+            failed,
+            HashSet::from(["failed_1".to_string(), "failed_2".to_string()])
+        );
+        assert_eq!(
+            state
+                .list_tasks_by_outcome(&db, TaskOutcome::Failed, 1)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_create_index_with_schema() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RegisterExecutor {
+                addr: "addr".into(),
+                executor_id: "executor".into(),
+                extractors: vec![ExtractorDescription {
+                    name: "extractor".into(),
+                    outputs: HashMap::from([(
+                        "metadata".to_string(),
+                        internal_api::OutputSchema::Attributes(HashMap::from([(
+                            "author".to_string(),
+                            internal_api::SchemaColumnType::Text.into(),
+                        )])),
+                    )]),
+                    ..Default::default()
+                }],
+                ts_secs: 0,
+                labels: HashMap::new(),
+            },
+        );
+        let index = internal_api::Index {
+            name: "index".into(),
+            namespace: "namespace".into(),
+            extractor: "extractor".into(),
+            ..Default::default()
+        };
+        let schema = internal_api::StructuredDataSchema {
+            columns: std::collections::BTreeMap::from([(
+                "author".to_string(),
+                internal_api::SchemaColumnType::Text.into(),
+            )]),
+            ..internal_api::StructuredDataSchema::new("policy", "namespace")
+        };
+        state.validate_index_schema(&db, &index, &schema).unwrap();
+
+        //  Columns the extractor doesn't produce are rejected
+        let invalid_schema = internal_api::StructuredDataSchema {
+            columns: std::collections::BTreeMap::from([(
+                "title".to_string(),
+                internal_api::SchemaColumnType::Text.into(),
+            )]),
+            ..schema.clone()
+        };
+        assert!(state
+            .validate_index_schema(&db, &index, &invalid_schema)
+            .is_err());
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateIndexWithSchema {
+                index: index.clone(),
+                namespace: "namespace".into(),
+                id: "index_id".into(),
+                schema: schema.clone(),
+            },
+        );
+        assert_eq!(
+            state
+                .get_indexes_from_ids(HashSet::from(["index_id".to_string()]), &db)
+                .unwrap(),
+            vec![index]
+        );
+        let stored_schema: Option<internal_api::StructuredDataSchema> = state
+            .get_from_cf(&db, StateMachineColumns::StructuredDataSchemas, &schema.id)
+            .unwrap();
+        assert_eq!(stored_schema, Some(schema.clone()));
+        assert_eq!(
+            state.get_namespace_index_table().get("namespace"),
+            Some(&HashSet::from(["index_id".to_string()]))
+        );
+        assert_eq!(
+            state.get_schemas_by_namespace().get("namespace"),
+            Some(&HashSet::from([schema.id]))
+        );
+    }
+
+    #[test]
+    fn test_namespace_disk_usage() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata {
+                    id: ContentMetadataId::new("content"),
+                    namespace: "namespace".into(),
+                    labels: HashMap::from([("text".to_string(), "a".repeat(4096))]),
+                    ..Default::default()
+                }],
+                expected_version: None,
+            },
+        );
+        //  The estimate only covers data which has been flushed to SST files
+        db.flush_cf(StateMachineColumns::ContentTable.cf(&db))
+            .unwrap();
+
+        assert!(state.namespace_disk_usage(&db, "namespace").unwrap() > 0);
+        assert_eq!(state.namespace_disk_usage(&db, "other").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_assign_tasks_across_executors() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let executors = ["executor_1", "executor_2", "executor_3"];
+        for executor_id in executors {
+            register_executor(&state, &db, executor_id, "extractor", 0);
+        }
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let task_ids = (0..10).map(|i| format!("task_{}", i)).collect_vec();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: task_ids
+                    .iter()
+                    .map(|task_id| test_task(task_id, "extractor"))
+                    .collect(),
+            },
+        );
+        let expected: HashMap<TaskId, ExecutorId> = task_ids
+            .iter()
+            .enumerate()
+            .map(|(i, task_id)| (task_id.clone(), executors[i % 3].to_string()))
+            .collect();
+
+        //  Assignments are additive to the tasks an executor already has
+        let (first, second): (HashMap<_, _>, HashMap<_, _>) = expected
+            .clone()
+            .into_iter()
+            .partition(|(task_id, _)| task_id.as_str() < "task_4");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask { assignments: first },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: second,
+            },
+        );
+
+        assert_eq!(state.get_all_task_assignments(&db).unwrap(), expected);
+        for (i, executor_id) in executors.iter().enumerate() {
+            let count = (0..10).filter(|task| task % 3 == i).count();
+            assert_eq!(
+                state
+                    .get_tasks_for_executor(executor_id, None, &db)
+                    .unwrap()
+                    .len(),
+                count
+            );
+            assert_eq!(
+                state
+                    .executor_running_task_count
+                    .get(&executor_id.to_string())
+                    .unwrap(),
+                count
+            );
+        }
+        assert!(state.unassigned_tasks.inner().is_empty());
+    }
+
+    #[test]
+    fn test_health_summary() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor_1", "extractor", 0);
+        register_executor(&state, &db, "executor_2", "extractor", 0);
+        state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![internal_api::ContentMetadata::default()],
+                        expected_version: None,
+                    },
+                    new_state_changes: vec![
+                        StateChange::new(
+                            "content_1".into(),
+                            internal_api::ChangeType::NewContent,
+                            0,
+                        ),
+                        StateChange::new(
+                            "content_2".into(),
+                            internal_api::ChangeType::NewContent,
+                            0,
+                        ),
+                    ],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task_1".to_string(), "executor_1".to_string())]),
+            },
+        );
+
+        assert_eq!(
+            state.health_summary(&db).unwrap(),
+            HealthSummary {
+                unprocessed_state_changes: 2,
+                unassigned_tasks: 2,
+                executors: 2,
+                running_tasks: 1,
+                unfinished_tasks: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_iter_state_changes() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let mut state_change_ids = Vec::new();
+        for batch in 0..3 {
+            let new_state_changes = (0..3)
+                .map(|i| {
+                    StateChange::new(
+                        format!("content_{}_{}", batch, i),
+                        internal_api::ChangeType::NewContent,
+                        0,
+                    )
+                })
+                .collect_vec();
+            state_change_ids.extend(new_state_changes.iter().map(|change| change.id.clone()));
+            state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::CreateContent {
+                            content_metadata: vec![],
+                            expected_version: None,
+                        },
+                        new_state_changes,
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+        }
+
+        let mut paged_ids = Vec::new();
+        let mut start_after = None;
+        loop {
+            let page = state.iter_state_changes(&db, start_after, 2).unwrap();
+            if page.is_empty() {
+                break;
+            }
+            assert!(page.len() <= 2);
+            start_after = page.last().map(|change| change.id.clone());
+            paged_ids.extend(page.into_iter().map(|change| change.id));
+        }
+        assert_eq!(paged_ids, state_change_ids);
+
+        //  A cursor dropped by compaction can't be resumed
+        db.delete_cf(
+            StateMachineColumns::StateChanges.cf(&db),
+            &state_change_ids[1],
+        )
+        .unwrap();
+        assert!(matches!(
+            state.iter_state_changes(&db, Some(state_change_ids[1].clone()), 2),
+            Err(StateMachineError::CursorExpired(id)) if id == state_change_ids[1]
+        ));
+    }
+
+    #[test]
+    fn test_prune_state_change_sequence() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        state.set_state_change_retention(60);
+        let apply = |new_state_changes: Vec<StateChange>, processed: Vec<(&StateChange, u64)>| {
+            state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::MarkStateChangesProcessed {
+                            state_changes: processed
+                                .into_iter()
+                                .map(|(change, processed_at)| StateChangeProcessed {
+                                    state_change_id: change.id.clone(),
+                                    processed_at,
+                                })
+                                .collect(),
+                        },
+                        new_state_changes,
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+        };
+        let state_changes = (0..3)
+            .map(|i| {
+                StateChange::new(
+                    format!("content_{}", i),
+                    internal_api::ChangeType::NewContent,
+                    0,
+                )
+            })
+            .collect_vec();
+        apply(state_changes.clone(), vec![]);
+        apply(
+            vec![],
+            vec![(&state_changes[0], 100), (&state_changes[1], 110)],
+        );
+        apply(vec![], vec![(&state_changes[2], 200)]);
+
+        //  Only the entries processed before the retention window are pruned
+        let sequence_cf = StateMachineColumns::StateChangeSequence.cf(&db);
+        assert!(db.get_cf(sequence_cf, numeric_key(1)).unwrap().is_none());
+        assert!(db.get_cf(sequence_cf, numeric_key(2)).unwrap().is_none());
+        assert!(db.get_cf(sequence_cf, numeric_key(3)).unwrap().is_some());
+
+        //  The state change is retained, but the one after it isn't
+        assert!(matches!(
+            state.iter_state_changes(&db, Some(state_changes[0].id.clone()), 10),
+            Err(StateMachineError::CursorExpired(_))
+        ));
+        let page = state
+            .iter_state_changes(&db, Some(state_changes[1].id.clone()), 10)
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, state_changes[2].id);
+
+        //  The sequence keeps increasing even once the column family is empty
+        db.delete_cf(sequence_cf, numeric_key(3)).unwrap();
+        let state_change = StateChange::new(
+            "content_3".to_string(),
+            internal_api::ChangeType::NewContent,
+            0,
+        );
+        apply(vec![state_change.clone()], vec![]);
+        let stored: StateChange = state
+            .get_from_cf(&db, StateMachineColumns::StateChanges, &state_change.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.sequence, 4);
+    }
+
+    #[test]
+    fn test_list_content_summaries() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = |id: &str, size_bytes: u64| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            name: format!("{}.txt", id),
+            namespace: "namespace".into(),
+            size_bytes,
+            labels: HashMap::from([("text".to_string(), "a".repeat(1024))]),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content("content_2", 20), content("content_1", 10)],
+                expected_version: None,
+            },
+        );
+
+        let summaries = state.list_content_summaries(&db, "namespace").unwrap();
+        assert_eq!(
+            summaries,
+            vec![
+                ContentSummary {
+                    id: ContentMetadataId::new("content_1"),
+                    name: "content_1.txt".into(),
+                    size_bytes: 10,
+                    tombstoned: false,
+                },
+                ContentSummary {
+                    id: ContentMetadataId::new("content_2"),
+                    name: "content_2.txt".into(),
+                    size_bytes: 20,
+                    tombstoned: false,
+                },
+            ]
+        );
+        assert!(state
+            .list_content_summaries(&db, "other")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_executor_running_task_count_verified() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_assigned_task(&state, &db, "task", "executor");
+        let executor_id = "executor".to_string();
+        assert_eq!(
+            state
+                .executor_running_task_count_verified(&db, &executor_id)
+                .unwrap(),
+            1
+        );
+
+        //  Corrupt the cached count, the verified read restores it
+        state.executor_running_task_count.insert(&executor_id, 5);
+        assert_eq!(
+            state
+                .executor_running_task_count_verified(&db, &executor_id)
+                .unwrap(),
+            1
+        );
+        assert_eq!(state.executor_running_task_count.get(&executor_id), Some(1));
+    }
+
+    #[test]
+    fn test_executor_with_multiple_extractors() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor_1"),
+                    test_task("task_2", "extractor_2"),
+                    test_task("task_3", "extractor_3"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RegisterExecutorAndClaim {
+                addr: "addr".into(),
+                executor_id: "executor".into(),
+                extractors: ["extractor_1", "extractor_2"]
+                    .into_iter()
+                    .map(|name| ExtractorDescription {
+                        name: name.into(),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ts_secs: 0,
+                max: 10,
+                labels: HashMap::new(),
+            },
+        );
+
+        let extractor_executors = state.get_extractor_executors_table();
+        for extractor in ["extractor_1", "extractor_2"] {
+            assert_eq!(
+                extractor_executors.get(extractor),
+                Some(&HashSet::from(["executor".to_string()]))
+            );
+        }
+        //  Tasks of any of the extractors of the executor can be claimed by it
+        let claimed: HashSet<TaskId> = state
+            .get_tasks_for_executor("executor", None, &db)
+            .unwrap()
+            .into_iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(
+            claimed,
+            HashSet::from(["task_1".to_string(), "task_2".to_string()])
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RemoveExecutor {
+                executor_id: "executor".into(),
+            },
+        );
+        let extractor_executors = state.get_extractor_executors_table();
+        for extractor in ["extractor_1", "extractor_2"] {
+            assert!(extractor_executors
+                .get(extractor)
+                .map_or(true, |executors| executors.is_empty()));
+        }
+    }
+
+    #[test]
+    fn test_cancel_orphaned_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+        //  Tasks whose content still exists are left alone
+        assert!(state.cancel_orphaned_tasks(&db).unwrap().is_empty());
+
+        db.delete_cf(
+            StateMachineColumns::ContentTable.cf(&db),
+            task.content_metadata.id.to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            state.cancel_orphaned_tasks(&db).unwrap(),
+            vec!["task".to_string()]
+        );
+        assert_eq!(
+            stored_task(&state, &db, "task").outcome,
+            TaskOutcome::Cancelled
+        );
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert!(state.unfinished_tasks_by_extractor.inner()["extractor"].is_empty());
+    }
+
+    #[test]
+    fn test_policies_applied_on_duplicate_content() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = internal_api::ContentMetadata::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: None,
+            },
+        );
+
+        //  Several tasks in one batch map to the same content
+        let mut task_1 = test_task("task_1", "extractor");
+        task_1.extraction_policy_id = "policy_1".into();
+        let mut task_2 = test_task("task_2", "extractor");
+        task_2.extraction_policy_id = "policy_2".into();
+        let mut task_3 = test_task("task_3", "extractor");
+        task_3.extraction_policy_id = "policy_1".into();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![task_1, task_2, task_3],
+            },
+        );
+
+        let mapping = state
+            .get_content_extraction_policy_mapping(&db, &content.id)
+            .unwrap()
+            .unwrap();
+        let mut policy_ids = mapping.extraction_policy_names.into_iter().collect_vec();
+        policy_ids.sort();
+        assert_eq!(policy_ids, vec!["policy_1", "policy_2"]);
+    }
+
+    #[test]
+    fn test_diff_cf() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let cf = StateMachineColumns::Namespaces.cf(&db);
+        db.put_cf(cf, "unchanged", "1").unwrap();
+        db.put_cf(cf, "modified", "1").unwrap();
+        db.put_cf(cf, "removed", "1").unwrap();
+        let snapshot = state.capture_cf_snapshot(&db, StateMachineColumns::Namespaces);
+        assert_eq!(
+            state
+                .diff_cf(&db, StateMachineColumns::Namespaces, &snapshot)
+                .unwrap(),
+            CfDiff::default()
+        );
+
+        db.put_cf(cf, "modified", "2").unwrap();
+        db.delete_cf(cf, "removed").unwrap();
+        db.put_cf(cf, "added", "1").unwrap();
+        assert_eq!(
+            state
+                .diff_cf(&db, StateMachineColumns::Namespaces, &snapshot)
+                .unwrap(),
+            CfDiff {
+                added: vec![b"added".to_vec()],
+                removed: vec![b"removed".to_vec()],
+                modified: vec![b"modified".to_vec()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_reserve_task_ids() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::ReserveTaskIds {
+                reservation_id: "reservation_1".into(),
+                count: 3,
+            },
+        );
+        //  The next reservable id is read from the db, not the in-memory state
+        let restarted_state = IndexifyState::default();
+        apply_payload(
+            &restarted_state,
+            &db,
+            RequestPayload::ReserveTaskIds {
+                reservation_id: "reservation_2".into(),
+                count: 2,
+            },
+        );
+
+        let first = state
+            .get_task_id_reservation(&db, "reservation_1")
+            .unwrap()
+            .unwrap()
+            .task_ids();
+        let second = restarted_state
+            .get_task_id_reservation(&db, "reservation_2")
+            .unwrap()
+            .unwrap()
+            .task_ids();
+        assert_eq!(first.len(), 3);
+        assert_eq!(second.len(), 2);
+        assert!(first.iter().all(|id| !second.contains(id)));
+        assert_eq!(state.get_task_id_reservation(&db, "unknown").unwrap(), None);
+    }
+
+    #[test]
+    fn test_sla_breaches() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RegisterExecutor {
+                addr: "addr".into(),
+                executor_id: "executor".into(),
+                extractors: vec![
+                    ExtractorDescription {
+                        name: "sla_extractor".into(),
+                        sla_secs: Some(300),
+                        ..Default::default()
+                    },
+                    ExtractorDescription {
+                        name: "extractor".into(),
+                        ..Default::default()
+                    },
+                ],
+                ts_secs: 0,
+                labels: HashMap::new(),
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let mut sla_task = test_task("sla_task", "sla_extractor");
+        sla_task.created_at = 1000;
+        let mut task = test_task("task", "extractor");
+        task.created_at = 1000;
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![sla_task, task],
+            },
+        );
+
+        assert!(state.sla_breaches(&db, 1200).unwrap().is_empty());
+        //  Only the extractor with an SLA reports its tasks
+        assert_eq!(
+            state.sla_breaches(&db, 1400).unwrap(),
+            vec![("sla_extractor".to_string(), vec!["sla_task".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_background_reverse_index_updates() {
+        let (_dir, db) = open_test_db();
+        let state = Arc::new(IndexifyState::default());
+        state.start_reverse_index_worker();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        state.reverse_index_barrier();
+
+        //  The write returns while the worker is blocked on the reverse index
+        let guard = state
+            .unfinished_tasks_by_extractor
+            .unfinished_tasks_by_extractor
+            .write()
+            .unwrap();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task", "extractor")],
+            },
+        );
+        assert_eq!(stored_task(&state, &db, "task").id, "task");
+        drop(guard);
+
+        state.reverse_index_barrier();
+        assert!(state.get_unfinished_tasks_by_extractor()["extractor"].contains("task"));
+        assert!(state.get_unassigned_tasks().contains("task"));
+    }
+
+    #[test]
+    fn test_content_lineage() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_extraction_policy(
+            &state,
+            &db,
+            &internal_api::ExtractionPolicy {
+                id: "policy_id".into(),
+                name: "policy".into(),
+                namespace: "namespace".into(),
+                ..Default::default()
+            },
+        );
+        let root = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("root"),
+            namespace: "namespace".into(),
+            source: "ingestion".into(),
+            ..Default::default()
+        };
+        let derived = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("derived"),
+            parent_id: root.id.clone(),
+            namespace: "namespace".into(),
+            source: "policy".into(),
+            ..Default::default()
+        };
+        let other = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("other"),
+            parent_id: root.id.clone(),
+            namespace: "namespace".into(),
+            source: "other_policy".into(),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![root.clone(), derived.clone(), other],
+                expected_version: None,
+            },
+        );
+
+        assert_eq!(state.policy_for_content(&db, &root.id).unwrap(), None);
+        assert_eq!(
+            state.policy_for_content(&db, &derived.id).unwrap(),
+            Some("policy".to_string())
+        );
+        assert_eq!(
+            state.content_produced_by_policy(&db, "policy_id").unwrap(),
+            vec![derived.id]
+        );
+    }
+
+    #[test]
+    fn test_scan_skips_corrupt_values() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let cf = StateMachineColumns::Namespaces.cf(&db);
+        db.put_cf(
+            cf,
+            "good",
+            JsonEncoder::encode(&"good".to_string()).unwrap(),
+        )
+        .unwrap();
+        db.put_cf(cf, "corrupt", b"{not json").unwrap();
+
+        assert!(state
+            .get_all_rows_from_cf::<String>(StateMachineColumns::Namespaces, &db)
+            .is_err());
+        let result = state
+            .get_all_rows_from_cf_skip_corrupt::<String>(StateMachineColumns::Namespaces, &db)
+            .unwrap();
+        assert_eq!(result.rows, vec![("good".to_string(), "good".to_string())]);
+        assert_eq!(result.corrupt_keys, vec!["corrupt".to_string()]);
+    }
+
+    #[test]
+    fn test_finished_task_marks_policy_applied() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateTask {
+                task: internal_api::Task {
+                    outcome: TaskOutcome::Success,
+                    ..task.clone()
+                },
+                executor_id: Some("executor".to_string()),
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100),
+                cancelled: false,
+                artifact_ref: None,
+            },
+        );
+
+        assert_eq!(
+            stored_task(&state, &db, "task").outcome,
+            TaskOutcome::Success
+        );
+        let content = state
+            .get_content_from_ids_with_version(
+                HashSet::from([task.content_metadata.id.clone()]),
+                &db,
+            )
+            .unwrap();
+        assert_eq!(content[0].extraction_policy_ids.get("policy"), Some(&100));
+    }
+
+    #[test]
+    fn test_scan_with_options() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let cf = StateMachineColumns::Namespaces.cf(&db);
+        for i in 0..100 {
+            let name = format!("namespace_{}", i);
+            db.put_cf(cf, &name, JsonEncoder::encode(&name).unwrap())
+                .unwrap();
+        }
+        db.flush_cf(cf).unwrap();
+
+        let rows = state
+            .get_all_rows_from_cf::<String>(StateMachineColumns::Namespaces, &db)
+            .unwrap();
+        let tuned_rows = state
+            .get_all_rows_from_cf_with_options::<String>(
+                StateMachineColumns::Namespaces,
+                &db,
+                &ScanOptions {
+                    readahead_size: Some(2 * 1024 * 1024),
+                    total_order_seek: true,
+                },
+            )
+            .unwrap();
+        assert_eq!(rows.len(), 100);
+        assert_eq!(rows, tuned_rows);
+    }
+
+    #[test]
+    fn test_distinct_task_extractors() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+        update_task(&state, &db, &task, TaskOutcome::Success, "executor");
+        assert!(state.unfinished_tasks_by_extractor.inner()["extractor"].is_empty());
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task_2", "extractor_2")],
+            },
+        );
+
+        assert_eq!(
+            state.distinct_task_extractors(&db).unwrap(),
+            HashSet::from(["extractor".to_string(), "extractor_2".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_apply_returning_state_changes() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let state_changes = vec![
+            StateChange::new("content_1".into(), internal_api::ChangeType::NewContent, 0),
+            StateChange::new("content_2".into(), internal_api::ChangeType::NewContent, 0),
+        ];
+        let state_change_ids = state
+            .apply_state_machine_updates_returning_state_changes(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![],
+                        expected_version: None,
+                    },
+                    new_state_changes: state_changes.clone(),
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+
+        assert_eq!(
+            state_change_ids,
+            state_changes
+                .iter()
+                .map(|state_change| state_change.id.clone())
+                .collect_vec()
+        );
+        for state_change_id in state_change_ids {
+            assert!(state
+                .get_from_cf::<StateChange, _>(
+                    &db,
+                    StateMachineColumns::StateChanges,
+                    &state_change_id
+                )
+                .unwrap()
+                .is_some());
+        }
+    }
+
+    #[test]
+    fn test_recompute_executor_load() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_assigned_task(&state, &db, "task", "executor");
+        state
+            .executor_running_task_count
+            .insert(&"executor".to_string(), 5);
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RecomputeExecutorLoad {
+                executor_id: "executor".into(),
+            },
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_list_chunks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let source = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("source"),
+            namespace: "namespace".into(),
+            ..Default::default()
+        };
+        let chunk = |id: &str, chunk_index: u32| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            namespace: "namespace".into(),
+            chunk_index: Some(chunk_index),
+            source_content_id: Some(source.id.clone()),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    source.clone(),
+                    chunk("chunk_2", 2),
+                    chunk("chunk_0", 0),
+                    chunk("chunk_1", 1),
+                ],
+                expected_version: None,
+            },
+        );
+
+        let chunks = state.list_chunks(&db, &source.id).unwrap();
+        assert_eq!(
+            chunks.iter().map(|c| c.chunk_index).collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(2)]
+        );
+        assert_eq!(
+            chunks.iter().map(|c| c.id.id.as_str()).collect::<Vec<_>>(),
+            vec!["chunk_0", "chunk_1", "chunk_2"]
+        );
+        assert!(state
+            .list_chunks(&db, &ContentMetadataId::new("missing"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_task_detail() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_assigned_task(&state, &db, "task", "executor");
+
+        let detail = state.task_detail(&db, "task").unwrap();
+        assert_eq!(detail.task.id, "task");
+        assert_eq!(detail.executor.unwrap().id, "executor");
+        assert_eq!(
+            detail.content.unwrap().id,
+            internal_api::ContentMetadata::default().id
+        );
+    }
+
+    #[test]
+    fn test_task_detail_without_assignment() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task", "extractor")],
+            },
+        );
+
+        let detail = state.task_detail(&db, "task").unwrap();
+        assert_eq!(detail.task.id, "task");
+        assert!(detail.executor.is_none());
+        assert!(detail.content.is_none());
+        assert!(state.task_detail(&db, "missing").is_err());
+    }
+
+    #[test]
+    fn test_health_summary_with_paused() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "paused_extractor"),
+                    test_task("task_2", "paused_extractor"),
+                    test_task("task_3", "active_extractor"),
+                ],
+            },
+        );
+        let paused_extractors = HashSet::from(["paused_extractor".to_string()]);
+
+        let summary = state
+            .health_summary_with_paused(&db, &paused_extractors)
+            .unwrap();
+        assert_eq!(summary.unassigned_tasks, 3);
+        assert_eq!(
+            summary.unfinished_tasks,
+            Some(UnfinishedTaskCounts {
+                active: 1,
+                paused: 2,
+            })
+        );
+        assert_eq!(state.health_summary(&db).unwrap().unfinished_tasks, None);
+    }
+
+    #[test]
+    fn test_executor_heartbeat() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let mut task = create_assigned_task(&state, &db, "task", "executor");
+        let mut other_task = create_assigned_task(&state, &db, "other_task", "other_executor");
+
+        //  Unfinished tasks and tasks of other executors are ignored
+        other_task.outcome = internal_api::TaskOutcome::Success;
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::ExecutorHeartbeat {
+                executor_id: "executor".into(),
+                ts_secs: 90,
+                completed_task_updates: vec![task.clone(), other_task],
+                update_time: SystemTime::UNIX_EPOCH,
+            },
+        );
+        assert_eq!(
+            state.task_executor(&db, "task").unwrap(),
+            Some("executor".to_string())
+        );
+        assert_eq!(
+            stored_task(&state, &db, "other_task").outcome,
+            internal_api::TaskOutcome::Unknown
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(1)
+        );
+
+        task.outcome = internal_api::TaskOutcome::Success;
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::ExecutorHeartbeat {
+                executor_id: "executor".into(),
+                ts_secs: 100,
+                completed_task_updates: vec![task],
+                update_time: SystemTime::UNIX_EPOCH + Duration::from_secs(50),
+            },
+        );
+
+        let executor: internal_api::ExecutorMetadata = state
+            .get_from_cf(&db, StateMachineColumns::Executors, "executor")
+            .unwrap()
+            .unwrap();
+        assert_eq!(executor.last_seen, 100);
+        assert_eq!(
+            stored_task(&state, &db, "task").outcome,
+            internal_api::TaskOutcome::Success
+        );
+        assert_eq!(state.task_executor(&db, "task").unwrap(), None);
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert_eq!(state.get_executor_idle_since().get("executor"), Some(&50));
+        assert_eq!(
+            state.unfinished_tasks_by_extractor.inner().get("extractor"),
+            Some(&HashSet::from(["other_task".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_state_change_counts() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let state_changes: Vec<StateChange> = (0..3)
+            .map(|i| {
+                StateChange::new(
+                    format!("content_{}", i),
+                    internal_api::ChangeType::NewContent,
+                    0,
+                )
+            })
+            .collect();
+        state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![],
+                        expected_version: None,
+                    },
+                    new_state_changes: state_changes.clone(),
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+        assert_eq!(state.state_change_counts(), (3, 0));
+
+        let processed = StateChangeProcessed {
+            state_change_id: state_changes[0].id.clone(),
+            processed_at: 1,
+        };
+        for _ in 0..2 {
+            state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::MarkStateChangesProcessed {
+                            state_changes: vec![processed.clone()],
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+        }
+        assert_eq!(state.state_change_counts(), (2, 1));
+
+        let restored = IndexifyState::default();
+        restored.install_snapshot(state.build_snapshot());
+        assert_eq!(restored.state_change_counts(), (2, 1));
+    }
+
+    #[test]
+    fn test_task_artifact_ref() {
+        struct MockArtifactStore {
+            artifacts: HashMap<String, Vec<u8>>,
+        }
+
+        impl ArtifactStore for MockArtifactStore {
+            fn fetch(&self, artifact_ref: &str) -> anyhow::Result<Vec<u8>> {
+                self.artifacts
+                    .get(artifact_ref)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("artifact {} not found", artifact_ref))
+            }
+        }
+
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateTask {
+                task: internal_api::Task {
+                    outcome: TaskOutcome::Success,
+                    ..task
+                },
+                executor_id: Some("executor".to_string()),
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH,
+                cancelled: false,
+                artifact_ref: Some("s3://bucket/embeddings".to_string()),
+            },
+        );
+        let artifact_store = MockArtifactStore {
+            artifacts: HashMap::from([(
+                "s3://bucket/embeddings".to_string(),
+                b"embeddings".to_vec(),
+            )]),
+        };
+
+        assert_eq!(
+            state.task_artifact_ref(&db, "task").unwrap(),
+            Some("s3://bucket/embeddings".to_string())
+        );
+        assert_eq!(
+            state
+                .fetch_task_artifact(&db, "task", &artifact_store)
+                .unwrap(),
+            Some(b"embeddings".to_vec())
+        );
+        assert_eq!(
+            state
+                .fetch_task_artifact(&db, "missing", &artifact_store)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_content_modified_since() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content =
+            |id: &str, namespace: &str, last_modified_at: u64| internal_api::ContentMetadata {
+                id: ContentMetadataId::new(id),
+                namespace: namespace.into(),
+                last_modified_at,
+                ..Default::default()
+            };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    content("content_1", "namespace", 100),
+                    content("content_2", "namespace", 200),
+                    content("content_3", "namespace", 300),
+                    content("content_4", "other_namespace", 300),
+                ],
+                expected_version: None,
+            },
+        );
+        //  Rewriting content moves it to its new modified time
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateContent {
+                content_metadata: vec![content("content_1", "namespace", 400)],
+            },
+        );
+
+        let ids = |ids: Vec<ContentMetadataId>| ids.into_iter().map(|id| id.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(state.content_modified_since(&db, "namespace", 200).unwrap()),
+            vec!["content_2", "content_3", "content_1"]
+        );
+        assert_eq!(
+            ids(state.content_modified_since(&db, "namespace", 301).unwrap()),
+            vec!["content_1"]
+        );
+        assert!(state
+            .content_modified_since(&db, "namespace", 401)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_list_content_by_tags() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = |id: &str, namespace: &str, tags: &[&str]| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            namespace: namespace.into(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    content("content_1", "namespace", &["a", "b"]),
+                    content("content_2", "namespace", &["a"]),
+                    content("content_3", "namespace", &["b", "c"]),
+                    content("content_4", "namespace", &["a::b"]),
+                    content("content_5", "other_namespace", &["a", "b"]),
+                ],
+                expected_version: None,
+            },
+        );
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            state
+                .list_content_by_tags(&db, "namespace", &tags(&["a", "b"]), TagMatch::All)
+                .unwrap(),
+            vec!["content_1"]
+        );
+        assert_eq!(
+            state
+                .list_content_by_tags(&db, "namespace", &tags(&["a", "b"]), TagMatch::Any)
+                .unwrap(),
+            vec!["content_1", "content_2", "content_3"]
+        );
+        assert!(state
+            .list_content_by_tags(&db, "namespace", &tags(&["a", "c"]), TagMatch::All)
+            .unwrap()
+            .is_empty());
+        assert!(state
+            .list_content_by_tags(&db, "namespace", &[], TagMatch::Any)
+            .unwrap()
+            .is_empty());
+
+        //  Rewriting content replaces its tags and deleting it drops them
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateContent {
+                content_metadata: vec![content("content_2", "namespace", &["b"])],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateGarbageCollectionTask {
+                gc_task: internal_api::GarbageCollectionTask {
+                    content_id: ContentMetadataId::new("content_3"),
+                    ..Default::default()
+                },
+                mark_finished: true,
+            },
+        );
+        assert_eq!(
+            state
+                .list_content_by_tags(&db, "namespace", &tags(&["a"]), TagMatch::Any)
+                .unwrap(),
+            vec!["content_1"]
+        );
+        assert_eq!(
+            state
+                .list_content_by_tags(&db, "namespace", &tags(&["b"]), TagMatch::All)
+                .unwrap(),
+            vec!["content_1", "content_2"]
+        );
+    }
+
+    #[test]
+    fn test_retarget_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "old_extractor"),
+                    test_task("task_2", "old_extractor"),
+                    test_task("task_3", "old_extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RetargetTasks {
+                from_extractor: "old_extractor".into(),
+                to_extractor: "new_extractor".into(),
+                task_ids: vec!["task_1".into(), "task_2".into()],
+            },
+        );
+
+        let unfinished_tasks_by_extractor = state.unfinished_tasks_by_extractor.inner();
+        assert_eq!(
+            unfinished_tasks_by_extractor["new_extractor"],
+            HashSet::from(["task_1".to_string(), "task_2".to_string()])
+        );
+        assert_eq!(
+            unfinished_tasks_by_extractor["old_extractor"],
+            HashSet::from(["task_3".to_string()])
+        );
+        assert_eq!(
+            stored_task(&state, &db, "task_1").extractor,
+            "new_extractor"
+        );
+        assert_eq!(
+            stored_task(&state, &db, "task_2").extractor,
+            "new_extractor"
+        );
+        assert_eq!(
+            stored_task(&state, &db, "task_3").extractor,
+            "old_extractor"
+        );
+    }
+
+    #[test]
+    fn test_retarget_assigned_tasks_rejected() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "old_extractor"),
+                    test_task("task_2", "old_extractor"),
+                    test_task("task_3", "other_extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task_2".to_string(), "executor".to_string())]),
+            },
+        );
+
+        for task_ids in [
+            vec!["task_1".to_string(), "task_2".to_string()],
+            vec!["task_1".to_string(), "task_3".to_string()],
+        ] {
+            let result = state.apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::RetargetTasks {
+                        from_extractor: "old_extractor".into(),
+                        to_extractor: "new_extractor".into(),
+                        task_ids,
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            );
+            assert!(matches!(result, Err(StateMachineError::ExternalError(_))));
+        }
+
+        //  Nothing is retargeted when the request is rejected
+        assert_eq!(
+            stored_task(&state, &db, "task_1").extractor,
+            "old_extractor"
+        );
+        let unfinished_tasks_by_extractor = state.unfinished_tasks_by_extractor.inner();
+        assert!(!unfinished_tasks_by_extractor.contains_key("new_extractor"));
+        assert_eq!(
+            unfinished_tasks_by_extractor["old_extractor"],
+            HashSet::from(["task_1".to_string(), "task_2".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_plan_assignment_delta() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "extractor"),
+                    test_task("task_4", "extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([
+                    ("task_1".to_string(), "executor_1".to_string()),
+                    ("task_2".to_string(), "executor_1".to_string()),
+                    ("task_3".to_string(), "executor_1".to_string()),
+                ]),
+            },
+        );
+
+        let desired = HashMap::from([
+            ("task_1".to_string(), "executor_1".to_string()),
+            ("task_2".to_string(), "executor_2".to_string()),
+            ("task_4".to_string(), "executor_2".to_string()),
+        ]);
+        assert_eq!(
+            state.plan_assignment_delta(&db, &desired).unwrap(),
+            AssignmentDelta {
+                assigns: HashMap::from([("task_4".to_string(), "executor_2".to_string())]),
+                reassigns: HashMap::from([(
+                    "task_2".to_string(),
+                    ("executor_1".to_string(), "executor_2".to_string())
+                )]),
+                unassigns: HashMap::from([("task_3".to_string(), "executor_1".to_string())]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_policies_for_schema() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let schema = internal_api::StructuredDataSchema::new("ingestion", "namespace");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateNamespace {
+                name: "namespace".into(),
+                structured_data_schema: schema.clone(),
+            },
+        );
+        create_extraction_policy(
+            &state,
+            &db,
+            &internal_api::ExtractionPolicy {
+                id: "ingestion_policy_id".into(),
+                name: "ingestion_policy".into(),
+                namespace: "namespace".into(),
+                content_source: "ingestion".into(),
+                ..Default::default()
+            },
+        );
+        create_extraction_policy(
+            &state,
+            &db,
+            &internal_api::ExtractionPolicy {
+                id: "derived_policy_id".into(),
+                name: "derived_policy".into(),
+                namespace: "namespace".into(),
+                content_source: "ingestion_policy".into(),
+                ..Default::default()
+            },
+        );
+
+        let policies = state.policies_for_schema(&db, &schema.id).unwrap();
+        assert_eq!(
+            policies.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(),
+            vec!["ingestion_policy_id"]
+        );
+        assert!(state.policies_for_schema(&db, "missing").is_err());
+    }
+
+    #[test]
+    fn test_overwrite_mutable_content() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("content"),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata {
+                    name: "overwritten".into(),
+                    ..content.clone()
+                }],
+                expected_version: None,
+            },
+        );
+
+        let stored = state
+            .get_content_from_ids_with_version(HashSet::from([content.id]), &db)
+            .unwrap();
+        assert_eq!(stored[0].name, "overwritten");
+    }
+
+    #[test]
+    fn test_overwrite_immutable_content_rejected() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("content"),
+            immutable: true,
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: None,
+            },
+        );
+
+        let result = state.apply_state_machine_updates(
+            StateMachineUpdateRequest {
+                payload: RequestPayload::CreateContent {
+                    content_metadata: vec![internal_api::ContentMetadata {
+                        name: "overwritten".into(),
+                        ..content.clone()
+                    }],
+                    expected_version: None,
+                },
+                new_state_changes: vec![],
+                state_changes_processed: vec![],
+                correlation_id: None,
+            },
+            &db,
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("is immutable and can't be overwritten"));
+        let stored = state
+            .get_content_from_ids_with_version(HashSet::from([content.id.clone()]), &db)
+            .unwrap();
+        assert_eq!(stored[0].name, content.name);
+    }
+
+    #[test]
+    fn test_orphaned_schemas() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateNamespace {
+                name: "namespace".into(),
+                structured_data_schema: internal_api::StructuredDataSchema::new(
+                    "ingestion",
+                    "namespace",
+                ),
+            },
+        );
+        create_extraction_policy(
+            &state,
+            &db,
+            &internal_api::ExtractionPolicy {
+                id: "policy_id".into(),
+                name: "policy".into(),
+                namespace: "namespace".into(),
+                content_source: "ingestion".into(),
+                ..Default::default()
+            },
+        );
+        //  Left behind by a policy which no longer exists
+        let orphaned_schema =
+            internal_api::StructuredDataSchema::new("deleted_policy", "namespace");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateNamespace {
+                name: "namespace".into(),
+                structured_data_schema: orphaned_schema.clone(),
+            },
+        );
+
+        assert_eq!(
+            state.orphaned_schemas(&db, "namespace").unwrap(),
+            vec![orphaned_schema.id]
+        );
+        assert!(state.orphaned_schemas(&db, "other").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compare_and_swap_content() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("content"),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![content.clone()],
+                expected_version: Some(0),
+            },
+        );
+        let swapped = internal_api::ContentMetadata {
+            id: ContentMetadataId::new_with_version("content", 2),
+            name: "swapped".into(),
+            ..content.clone()
+        };
+        let swap = |expected_version: u64| {
+            state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::CreateContent {
+                            content_metadata: vec![swapped.clone()],
+                            expected_version: Some(expected_version),
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap()
+        };
+
+        //  A write with a stale expected version is dropped
+        assert_eq!(swap(0), Some("false".to_string()));
+        let txn = db.transaction();
+        assert_eq!(
+            state
+                .get_latest_version_of_content("content", &db, &txn)
+                .unwrap(),
+            Some(1)
+        );
+
+        //  A write with the current version succeeds and bumps the version
+        assert_eq!(swap(1), Some("true".to_string()));
+        let txn = db.transaction();
+        assert_eq!(
+            state
+                .get_latest_version_of_content("content", &db, &txn)
+                .unwrap(),
+            Some(2)
+        );
+        let stored = state
+            .get_content_from_ids_with_version(HashSet::from([swapped.id.clone()]), &db)
+            .unwrap();
+        assert_eq!(stored, vec![swapped]);
+    }
+
+    #[test]
+    fn test_tasks_blocked_by_capacity() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_assigned_task(&state, &db, "assigned_task", "executor");
+        register_executor(&state, &db, "other_executor", "other_extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "other_extractor"),
+                    test_task("task_4", "unstaffed_extractor"),
+                ],
+            },
+        );
+        let executor_caps = HashMap::from([
+            ("executor".to_string(), 1),
+            ("other_executor".to_string(), 1),
+        ]);
+
+        let blocked_tasks = state.tasks_blocked_by_capacity(&executor_caps);
+        assert_eq!(
+            blocked_tasks,
+            HashMap::from([(
+                "extractor".to_string(),
+                HashSet::from(["task_1".to_string(), "task_2".to_string()])
+            )])
+        );
+        assert!(state.tasks_blocked_by_capacity(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_create_indexes() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let index = |name: &str| internal_api::Index {
+            name: name.into(),
+            namespace: "namespace".into(),
+            table_name: format!("{}_table", name),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateIndexes {
+                indexes: vec![
+                    ("index_1".into(), index("index_1")),
+                    ("index_2".into(), index("index_2")),
+                    ("index_3".into(), index("index_3")),
+                ],
+                namespace: "namespace".into(),
+            },
+        );
+
+        let index_ids = HashSet::from([
+            "index_1".to_string(),
+            "index_2".to_string(),
+            "index_3".to_string(),
+        ]);
+        assert_eq!(
+            state.get_namespace_index_table().get("namespace"),
+            Some(&index_ids)
+        );
+        assert_eq!(state.get_indexes_from_ids(index_ids, &db).unwrap().len(), 3);
+
+        //  Duplicate ids in a batch are skipped
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateIndexes {
+                indexes: vec![
+                    ("index_4".into(), index("index_4")),
+                    ("index_4".into(), index("index_4")),
+                ],
+                namespace: "namespace".into(),
+            },
+        );
+        assert_eq!(state.get_namespace_index_table()["namespace"].len(), 4);
+    }
+
+    #[test]
+    fn test_archive_finished_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = create_assigned_task(&state, &db, "task", "executor");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("unfinished_task", "extractor")],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateTask {
+                task: internal_api::Task {
+                    outcome: TaskOutcome::Success,
+                    ..task.clone()
+                },
+                executor_id: Some("executor".to_string()),
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(100),
+                cancelled: false,
+                artifact_ref: None,
+            },
+        );
+
+        let archive = |now_secs: u64| -> usize {
+            let outcome = state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::ArchiveFinishedTasks {
+                            older_than_secs: 50,
+                            now_secs,
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+            serde_json::from_str(&outcome.unwrap()).unwrap()
+        };
+
+        //  The task isn't archived before the retention period passed
+        assert_eq!(archive(120), 0);
+        assert!(state.get_archived_task(&db, "task").unwrap().is_none());
+
+        assert_eq!(archive(150), 1);
+        assert_eq!(
+            state.get_archived_task(&db, "task").unwrap(),
+            Some(ArchivedTask {
+                id: "task".into(),
+                extractor: "extractor".into(),
+                extraction_policy_id: "policy".into(),
+                namespace: task.namespace.clone(),
+                content_id: task.content_metadata.id.clone(),
+                outcome: TaskOutcome::Success,
+                finished_at: 100,
+            })
+        );
+        let live_task: Option<internal_api::Task> = state
+            .get_from_cf(&db, StateMachineColumns::Tasks, "task")
+            .unwrap();
+        assert!(live_task.is_none());
+        assert_eq!(
+            stored_task(&state, &db, "unfinished_task").outcome,
+            TaskOutcome::Unknown
+        );
+        assert_eq!(archive(150), 0);
+    }
+
+    /// Records the payload kind and correlation id of the spans opened while
+    /// it's the default subscriber, and the number of spans closed
+    #[derive(Clone, Default)]
+    struct SpanCapture {
+        opened: Arc<std::sync::Mutex<Vec<(String, Option<String>)>>>,
+        closed: Arc<std::sync::Mutex<usize>>,
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanCapture {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            #[derive(Default)]
+            struct Fields {
+                name: String,
+                correlation_id: Option<String>,
+            }
+            impl tracing::field::Visit for Fields {
+                fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+                    match field.name() {
+                        "otel.name" => self.name = value.to_string(),
+                        "correlation_id" => self.correlation_id = Some(value.to_string()),
+                        _ => {}
+                    }
+                }
+
+                fn record_debug(
+                    &mut self,
+                    _field: &tracing::field::Field,
+                    _value: &dyn std::fmt::Debug,
+                ) {
+                }
+            }
+            let mut fields = Fields::default();
+            attrs.record(&mut fields);
+            self.opened
+                .lock()
+                .unwrap()
+                .push((fields.name, fields.correlation_id));
+        }
+
+        fn on_close(
+            &self,
+            _id: tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            *self.closed.lock().unwrap() += 1;
+        }
+    }
+
+    #[test]
+    fn test_apply_opens_span_per_request() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::CreateTasks {
+                            tasks: vec![test_task("task", "extractor")],
+                        },
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: Some("request_1".into()),
+                    },
+                    &db,
+                )
+                .unwrap();
+            //  Returns early once the executor is removed
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::RemoveExecutor {
+                    executor_id: "executor".into(),
+                },
+            );
+            //  Fails since the content is immutable
+            let content = internal_api::ContentMetadata {
+                id: ContentMetadataId::new("content"),
+                immutable: true,
+                ..Default::default()
+            };
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::CreateContent {
+                    content_metadata: vec![content.clone()],
+                    expected_version: None,
+                },
+            );
+            let result = state.apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![content],
+                        expected_version: None,
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            );
+            assert!(result.is_err());
+        });
+
+        assert_eq!(
+            *capture.opened.lock().unwrap(),
+            vec![
+                ("CreateTasks".to_string(), Some("request_1".to_string())),
+                ("RemoveExecutor".to_string(), None),
+                ("CreateContent".to_string(), None),
+                ("CreateContent".to_string(), None),
+            ]
+        );
+        assert_eq!(*capture.closed.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_tasks_in_deleted_namespaces() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        for namespace in ["deleted_namespace", "live_namespace"] {
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::CreateNamespace {
+                    name: namespace.into(),
+                    structured_data_schema: internal_api::StructuredDataSchema::new(
+                        "ingestion",
+                        namespace,
+                    ),
+                },
+            );
+        }
+        let task = |id: &str, namespace: &str| internal_api::Task {
+            content_metadata: internal_api::ContentMetadata {
+                namespace: namespace.into(),
+                ..Default::default()
+            },
+            ..test_task(id, "extractor")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    task("stray_task", "deleted_namespace"),
+                    task("live_task", "live_namespace"),
+                ],
+            },
+        );
+        db.delete_cf(StateMachineColumns::Namespaces.cf(&db), "deleted_namespace")
+            .unwrap();
+
+        assert_eq!(
+            state.tasks_in_deleted_namespaces(&db).unwrap(),
+            vec!["stray_task".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_concurrent_policy_mapping_merges() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content_id = ContentMetadataId::new("content");
+        let content_key = format!("{}::v{}", content_id.id, content_id.version);
+
+        let handles = (0..8)
+            .map(|i| {
+                let db = db.clone();
+                let content_key = content_key.clone();
+                std::thread::spawn(move || {
+                    let policy_name = format!("policy_{}", i);
+                    let mapping = ContentExtractionPolicyMapping {
+                        extraction_policy_names: HashSet::from([policy_name.clone()]),
+                        time_of_policy_completion: HashMap::from([(policy_name, i)]),
+                    };
+                    db.merge_cf(
+                        StateMachineColumns::ExtractionPoliciesAppliedOnContent.cf(&db),
+                        &content_key,
+                        JsonEncoder::encode(&mapping).unwrap(),
+                    )
+                    .unwrap();
+                })
+            })
+            .collect_vec();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mapping = state
+            .get_content_extraction_policy_mapping(&db, &content_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            mapping.extraction_policy_names,
+            (0..8).map(|i| format!("policy_{}", i)).collect()
+        );
+        assert_eq!(mapping.time_of_policy_completion["policy_7"], 7);
+
+        //  A later completion of a policy keeps the latest time
+        let txn = db.transaction();
+        state
+            .mark_extraction_policy_applied_on_content(&db, &txn, &content_key, "policy_7", 70)
+            .unwrap();
+        txn.commit().unwrap();
+        let mapping = state
+            .get_content_extraction_policy_mapping(&db, &content_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(mapping.extraction_policy_names.len(), 8);
+        assert_eq!(mapping.time_of_policy_completion["policy_7"], 70);
+    }
+
+    #[test]
+    fn test_executors_grouped_by_label() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let executors = [
+            ("executor_1", "extractor", Some("zone_a")),
+            ("executor_2", "extractor", Some("zone_b")),
+            ("executor_3", "extractor", Some("zone_a")),
+            ("executor_4", "extractor", None),
+            ("executor_5", "other_extractor", Some("zone_a")),
+        ];
+        for (executor_id, extractor, zone) in executors {
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::RegisterExecutor {
+                    addr: "addr".into(),
+                    executor_id: executor_id.into(),
+                    extractors: vec![ExtractorDescription {
+                        name: extractor.into(),
+                        ..Default::default()
+                    }],
+                    ts_secs: 0,
+                    labels: zone
+                        .map(|zone| HashMap::from([("zone".to_string(), zone.to_string())]))
+                        .unwrap_or_default(),
+                },
+            );
+        }
+
+        let groups = state
+            .executors_grouped_by_label(&db, "extractor", "zone")
+            .unwrap();
+        assert_eq!(
+            groups,
+            HashMap::from([
+                (
+                    "zone_a".to_string(),
+                    vec!["executor_1".to_string(), "executor_3".to_string()]
+                ),
+                ("zone_b".to_string(), vec!["executor_2".to_string()]),
+            ])
+        );
+        assert!(state
+            .executors_grouped_by_label(&db, "extractor", "rack")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_state_fingerprint() {
+        let apply_requests = |state: &IndexifyState, db: &Arc<OptimisticTransactionDB>| {
+            register_executor(state, db, "executor", "extractor", 0);
+            apply_payload(
+                state,
+                db,
+                RequestPayload::CreateTasks {
+                    tasks: vec![
+                        test_task("task_1", "extractor"),
+                        test_task("task_2", "extractor"),
+                    ],
+                },
+            );
+        };
+        let (_dir_1, db_1) = open_test_db();
+        let state_1 = IndexifyState::default();
+        apply_requests(&state_1, &db_1);
+        let (_dir_2, db_2) = open_test_db();
+        let state_2 = IndexifyState::default();
+        apply_requests(&state_2, &db_2);
+
+        let fingerprint = state_1.state_fingerprint(&db_1).unwrap();
+        assert_eq!(fingerprint, state_2.state_fingerprint(&db_2).unwrap());
+
+        //  The stores diverge once one of them applies another request
+        apply_payload(
+            &state_2,
+            &db_2,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task_3", "extractor")],
+            },
+        );
+        assert_ne!(fingerprint, state_2.state_fingerprint(&db_2).unwrap());
+        assert_eq!(fingerprint, state_1.state_fingerprint(&db_1).unwrap());
+    }
+
+    #[test]
+    fn test_cancel_policy_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let task = |id: &str, policy: &str| internal_api::Task {
+            extraction_policy_id: policy.into(),
+            ..test_task(id, "extractor")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    task("task_1", "policy_1"),
+                    task("task_2", "policy_1"),
+                    task("task_3", "policy_2"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task_1".to_string(), "executor".to_string())]),
+            },
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CancelPolicyTasks {
+                extraction_policy_id: "policy_1".into(),
+            },
+        );
+
+        for task_id in ["task_1", "task_2"] {
+            assert_eq!(
+                stored_task(&state, &db, task_id).outcome,
+                TaskOutcome::Cancelled
+            );
+        }
+        assert_eq!(
+            stored_task(&state, &db, "task_3").outcome,
+            TaskOutcome::Unknown
+        );
+        assert_eq!(
+            state.unassigned_tasks.inner(),
+            HashSet::from(["task_3".to_string()])
+        );
+        assert_eq!(
+            state.unfinished_tasks_by_extractor.inner()["extractor"],
+            HashSet::from(["task_3".to_string()])
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert!(state
+            .get_tasks_for_executor("executor", None, &db)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_pause_namespace() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let task = |id: &str, namespace: &str| internal_api::Task {
+            namespace: namespace.into(),
+            ..test_task(id, "extractor")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    task("paused_task", "paused_namespace"),
+                    task("active_task", "active_namespace"),
+                ],
+            },
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::PauseNamespace {
+                namespace: "paused_namespace".into(),
+            },
+        );
+        assert_eq!(
+            state.assignable_tasks(&db).unwrap(),
+            HashSet::from(["active_task".to_string()])
+        );
+
+        //  Executors of the shared extractor only claim the tasks of other namespaces
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RegisterExecutorAndClaim {
+                addr: "addr".into(),
+                executor_id: "executor".into(),
+                extractors: vec![ExtractorDescription {
+                    name: "extractor".into(),
+                    ..Default::default()
+                }],
+                ts_secs: 0,
+                max: 10,
+                labels: HashMap::new(),
+            },
+        );
+        assert_eq!(
+            state.get_unassigned_tasks(),
+            HashSet::from(["paused_task".to_string()])
+        );
+        assert!(state.assignable_tasks(&db).unwrap().is_empty());
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UnpauseNamespace {
+                namespace: "paused_namespace".into(),
+            },
+        );
+        assert!(state.paused_namespaces(&db).unwrap().is_empty());
+        assert_eq!(
+            state.assignable_tasks(&db).unwrap(),
+            HashSet::from(["paused_task".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_tasks_in_creation_order() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_c", "extractor_1"),
+                    test_task("task_a", "extractor_2"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_b", "extractor_1"),
+                    //  Writing an existing task again doesn't move it
+                    test_task("task_c", "extractor_1"),
+                ],
+            },
+        );
+
+        assert_eq!(
+            state.tasks_in_creation_order(&db, None).unwrap(),
+            vec![
+                "task_c".to_string(),
+                "task_a".to_string(),
+                "task_b".to_string()
+            ]
+        );
+        assert_eq!(
+            state
+                .tasks_in_creation_order(&db, Some("extractor_1".into()))
+                .unwrap(),
+            vec!["task_c".to_string(), "task_b".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_verify_and_repair_task_executor_index() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_assigned_task(&state, &db, "task1", "executor");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task2", "extractor")],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task2".to_string(), "executor".to_string())]),
+            },
+        );
+        assert!(state.verify_task_executor_index(&db).unwrap().is_empty());
+
+        //  Drift the index: a missing entry, a wrong executor and a stale entry
+        let cf = StateMachineColumns::TaskToExecutor.cf(&db);
+        db.delete_cf(cf, "task1").unwrap();
+        db.put_cf(cf, "task2", JsonEncoder::encode(&"other_executor").unwrap())
+            .unwrap();
+        db.put_cf(cf, "task3", JsonEncoder::encode(&"executor").unwrap())
+            .unwrap();
+        let expected = vec![
+            "task1".to_string(),
+            "task2".to_string(),
+            "task3".to_string(),
+        ];
+        assert_eq!(state.verify_task_executor_index(&db).unwrap(), expected);
+
+        let outcome = state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::RepairTaskExecutorIndex,
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+        let repaired: Vec<TaskId> = serde_json::from_str(&outcome.unwrap()).unwrap();
+        assert_eq!(repaired, expected);
+        assert!(state.verify_task_executor_index(&db).unwrap().is_empty());
+        assert_eq!(
+            state.task_executor(&db, "task1").unwrap(),
+            Some("executor".to_string())
+        );
+        assert_eq!(
+            state.task_executor(&db, "task2").unwrap(),
+            Some("executor".to_string())
+        );
+        assert_eq!(state.task_executor(&db, "task3").unwrap(), None);
+    }
+
+    #[test]
+    fn test_heartbeat_of_expired_executor() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 100);
+        let heartbeat = |ts_secs| RequestPayload::Heartbeat {
+            executor_id: "executor".into(),
+            ts_secs,
+            ttl_secs: 30,
+        };
+
+        apply_payload(&state, &db, heartbeat(120));
+        assert_eq!(
+            state.get_heartbeat_outcome(&db, "executor").unwrap(),
+            Some(HeartbeatOutcome {
+                ts_secs: 120,
+                previous_last_seen: Some(100),
+                was_expired: false,
+            })
+        );
+
+        //  The executor was presumed dead before reconnecting
+        apply_payload(&state, &db, heartbeat(200));
+        assert_eq!(
+            state.get_heartbeat_outcome(&db, "executor").unwrap(),
+            Some(HeartbeatOutcome {
+                ts_secs: 200,
+                previous_last_seen: Some(120),
+                was_expired: true,
+            })
+        );
+        let executor: internal_api::ExecutorMetadata = state
+            .get_from_cf(&db, StateMachineColumns::Executors, "executor")
+            .unwrap()
+            .unwrap();
+        assert_eq!(executor.last_seen, 200);
+    }
+
+    #[test]
+    fn test_stream_all_content_ids() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = |id: ContentMetadataId, namespace: &str| internal_api::ContentMetadata {
+            id,
+            namespace: namespace.into(),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    content(ContentMetadataId::new("content_1"), "namespace"),
+                    content(
+                        ContentMetadataId::new_with_version("content_1", 2),
+                        "namespace",
+                    ),
+                    content(ContentMetadataId::new("content_2"), "namespace"),
+                    content(ContentMetadataId::new("content_3"), "other_namespace"),
+                ],
+                expected_version: None,
+            },
+        );
+
+        let mut content_ids = state
+            .stream_all_content_ids(&db)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        content_ids.sort();
+        assert_eq!(content_ids, vec!["content_1", "content_2", "content_3"]);
+    }
+
+    #[test]
+    fn test_set_namespace_schema() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let schema_v1 = internal_api::StructuredDataSchema {
+            id: "schema_v1".into(),
+            ..internal_api::StructuredDataSchema::new("source", "namespace")
+        };
+        let schema_v2 = internal_api::StructuredDataSchema {
+            id: "schema_v2".into(),
+            ..schema_v1.clone()
+        };
+        let set_schema = |schema: &internal_api::StructuredDataSchema| {
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::SetNamespaceSchema {
+                    namespace: "namespace".into(),
+                    schema: schema.clone(),
+                },
+            )
+        };
+
+        set_schema(&schema_v1);
+        assert_eq!(
+            state
+                .get_active_namespace_schema(&db, "namespace")
+                .unwrap()
+                .map(|schema| schema.id),
+            Some("schema_v1".to_string())
+        );
+
+        set_schema(&schema_v2);
+        assert_eq!(
+            state
+                .get_active_namespace_schema(&db, "namespace")
+                .unwrap()
+                .map(|schema| schema.id),
+            Some("schema_v2".to_string())
+        );
+        let old_schema: Option<internal_api::StructuredDataSchema> = state
+            .get_from_cf(&db, StateMachineColumns::StructuredDataSchemas, "schema_v1")
+            .unwrap();
+        assert_eq!(
+            old_schema.map(|schema| schema.id),
+            Some("schema_v1".to_string())
+        );
+        assert_eq!(
+            state.get_schemas_by_namespace().get("namespace"),
+            Some(&HashSet::from([
+                "schema_v1".to_string(),
+                "schema_v2".to_string()
+            ]))
+        );
+        assert!(state
+            .get_active_namespace_schema(&db, "other_namespace")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_assigned_task_counts() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let mut assignments = HashMap::new();
+        let mut tasks = vec![];
+        for (executor_id, task_count) in [("executor_1", 1), ("executor_2", 2), ("executor_3", 3)] {
+            register_executor(&state, &db, executor_id, "extractor", 0);
+            for i in 0..task_count {
+                let task_id = format!("{}_task_{}", executor_id, i);
+                tasks.push(test_task(&task_id, "extractor"));
+                assignments.insert(task_id, executor_id.to_string());
+            }
+        }
+        apply_payload(&state, &db, RequestPayload::CreateTasks { tasks });
+        apply_payload(&state, &db, RequestPayload::AssignTask { assignments });
+
+        assert_eq!(
+            state.assigned_task_counts(&db).unwrap(),
+            HashMap::from([
+                ("executor_1".to_string(), 1),
+                ("executor_2".to_string(), 2),
+                ("executor_3".to_string(), 3),
+            ])
+        );
+        assert!(state.running_task_count_drift(&db).unwrap().is_empty());
+
+        state
+            .executor_running_task_count
+            .insert(&"executor_2".to_string(), 5);
+        assert_eq!(
+            state.running_task_count_drift(&db).unwrap(),
+            vec!["executor_2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_content_for_policy() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_extraction_policy(
+            &state,
+            &db,
+            &internal_api::ExtractionPolicy {
+                id: "policy_id".into(),
+                name: "policy_name".into(),
+                namespace: "namespace".into(),
+                ..Default::default()
+            },
+        );
+        let content = |id: &str| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            namespace: "namespace".into(),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    content("content_1"),
+                    content("content_2"),
+                    content("content_3"),
+                ],
+                expected_version: None,
+            },
+        );
+        let task = |id: &str, content_id: &str, extraction_policy_id: &str| internal_api::Task {
+            id: id.into(),
+            extractor: "extractor".into(),
+            extraction_policy_id: extraction_policy_id.into(),
+            content_metadata: content(content_id),
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    task("task_1", "content_1", "policy_id"),
+                    task("task_2", "content_2", "policy_id"),
+                    task("task_3", "content_3", "other_policy_id"),
+                ],
+            },
+        );
+
+        assert_eq!(
+            state
+                .content_for_policy(&db, "policy_name", "namespace")
+                .unwrap(),
+            vec!["content_1".to_string(), "content_2".to_string()]
+        );
+        assert!(state
+            .content_for_policy(&db, "policy_name", "other_namespace")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_finish_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        let tasks: Vec<internal_api::Task> = (0..5)
+            .map(|i| test_task(&format!("task_{}", i), "extractor"))
+            .collect();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: tasks.clone(),
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: tasks
+                    .iter()
+                    .map(|task| (task.id.clone(), "executor".to_string()))
+                    .collect(),
+            },
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(5)
+        );
+
+        let finished = tasks
+            .iter()
+            .map(|task| {
+                let content = internal_api::ContentMetadata {
+                    id: ContentMetadataId::new(&format!("{}_output", task.id)),
+                    namespace: "namespace".into(),
+                    ..Default::default()
+                };
+                (
+                    internal_api::Task {
+                        outcome: TaskOutcome::Success,
+                        ..task.clone()
+                    },
+                    Some(vec![content]),
+                )
+            })
+            .collect();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::FinishTasks {
+                executor_id: "executor".into(),
+                finished,
+                update_time: SystemTime::UNIX_EPOCH,
+            },
+        );
+
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(0)
+        );
+        assert_eq!(
+            state.assigned_task_counts(&db).unwrap().get("executor"),
+            Some(&0)
+        );
+        for task in &tasks {
+            assert_eq!(
+                stored_task(&state, &db, &task.id).outcome,
+                TaskOutcome::Success
+            );
+            assert_eq!(state.task_executor(&db, &task.id).unwrap(), None);
+            assert!(state
+                .content_exists(&db, &ContentMetadataId::new(&format!("{}_output", task.id)))
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn test_extractor_status() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor_1", "extractor", 0);
+        register_executor(&state, &db, "executor_2", "extractor", 0);
+        register_executor(&state, &db, "other_executor", "other_extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "extractor"),
+                    test_task("task_4", "other_extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task_1".to_string(), "executor_1".to_string())]),
+            },
+        );
+
+        let paused_extractors = HashSet::from(["extractor".to_string()]);
+        assert_eq!(
+            state
+                .extractor_status(&db, "extractor", &paused_extractors)
+                .unwrap(),
+            ExtractorStatus {
+                extractor: "extractor".into(),
+                executor_loads: vec![("executor_1".into(), 1), ("executor_2".into(), 0)],
+                unassigned_tasks: 2,
+                unfinished_tasks: 3,
+                paused: true,
+            }
+        );
+        assert!(
+            !state
+                .extractor_status(&db, "other_extractor", &paused_extractors)
+                .unwrap()
+                .paused
+        );
+        assert!(state
+            .extractor_status(&db, "unknown_extractor", &paused_extractors)
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_state_change_references() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let content = |id: &str| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            namespace: "namespace".into(),
+            ..Default::default()
+        };
+        let kept_change = StateChange::new(
+            ContentMetadataId::new("kept_content").to_string(),
+            internal_api::ChangeType::NewContent,
+            0,
+        );
+        let deleted_change = StateChange::new(
+            ContentMetadataId::new("deleted_content").to_string(),
+            internal_api::ChangeType::NewContent,
+            0,
+        );
+        state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::CreateContent {
+                        content_metadata: vec![content("kept_content"), content("deleted_content")],
+                        expected_version: None,
+                    },
+                    new_state_changes: vec![kept_change, deleted_change.clone()],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+            .unwrap();
+        assert!(state
+            .validate_state_change_references(&db, &db)
+            .unwrap()
+            .is_empty());
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateGarbageCollectionTask {
+                gc_task: internal_api::GarbageCollectionTask {
+                    namespace: "namespace".into(),
+                    id: "gc_task".into(),
+                    content_id: ContentMetadataId::new("deleted_content"),
+                    parent_content_id: ContentMetadataId::new(""),
+                    output_tables: HashSet::new(),
+                    outcome: TaskOutcome::Success,
+                    blob_store_path: "".into(),
+                    assigned_to: None,
+                },
+                mark_finished: true,
+            },
+        );
+        assert_eq!(
+            state.validate_state_change_references(&db, &db).unwrap(),
+            vec![(
+                deleted_change.id,
+                "content deleted_content::v1 not found".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_reconcile_unassigned() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        create_assigned_task(&state, &db, "assigned_task", "executor");
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("unassigned_task", "extractor"),
+                    test_task("orphaned_task", "extractor"),
+                ],
+            },
+        );
+        let reconcile = || -> Vec<TaskId> {
+            let outcome = state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload: RequestPayload::ReconcileUnassignedTasks,
+                        new_state_changes: vec![],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+            serde_json::from_str(&outcome.unwrap()).unwrap()
+        };
+        assert!(reconcile().is_empty());
+
+        //  Simulate a task lost mid-assignment
+        state.unassigned_tasks.remove(&"orphaned_task".to_string());
+        assert_eq!(reconcile(), vec!["orphaned_task".to_string()]);
+        assert_eq!(
+            state.unassigned_tasks.inner(),
+            HashSet::from(["unassigned_task".to_string(), "orphaned_task".to_string()])
+        );
+        assert!(reconcile().is_empty());
+    }
+
+    #[test]
+    fn test_executor_weighted_loads() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        register_executor(&state, &db, "idle_executor", "extractor", 0);
+        let task = |id: &str, cost: u64| internal_api::Task {
+            cost,
+            ..test_task(id, "extractor")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![task("big_task", 5), task("small_task", 1)],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([
+                    ("big_task".to_string(), "executor".to_string()),
+                    ("small_task".to_string(), "executor".to_string()),
+                ]),
+            },
+        );
+
+        assert_eq!(
+            state.executor_weighted_loads(),
+            HashMap::from([
+                ("executor".to_string(), 6),
+                ("idle_executor".to_string(), 0),
+            ])
+        );
+        assert_eq!(
+            state
+                .executor_running_task_count
+                .get(&"executor".to_string()),
+            Some(2)
+        );
+
+        //  Finishing a task removes its cost from the load
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateTask {
+                task: internal_api::Task {
+                    outcome: TaskOutcome::Success,
+                    ..task("big_task", 5)
+                },
+                executor_id: Some("executor".to_string()),
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH,
+                cancelled: false,
+                artifact_ref: None,
+            },
+        );
+        assert_eq!(state.executor_weighted_loads()["executor"], 1);
+    }
+
+    #[test]
+    fn test_pending_work_for_new_executor() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let tasks: Vec<internal_api::Task> = (0..3)
+            .map(|i| test_task(&format!("task_{}", i), "extractor"))
+            .collect();
+        apply_payload(&state, &db, RequestPayload::CreateTasks { tasks });
+        register_executor(&state, &db, "new_executor", "extractor", 0);
+
+        assert_eq!(
+            state
+                .pending_work_for_new_executor(&db, "extractor", 2, &HashSet::new())
+                .unwrap(),
+            vec!["task_0".to_string(), "task_1".to_string()]
+        );
+        assert_eq!(
+            state
+                .pending_work_for_new_executor(&db, "extractor", 5, &HashSet::new())
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(state
+            .pending_work_for_new_executor(
+                &db,
+                "extractor",
+                2,
+                &HashSet::from(["extractor".to_string()])
+            )
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_content_timeline() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let apply = |payload: RequestPayload, state_change: StateChange| {
+            state
+                .apply_state_machine_updates(
+                    StateMachineUpdateRequest {
+                        payload,
+                        new_state_changes: vec![state_change],
+                        state_changes_processed: vec![],
+                        correlation_id: None,
+                    },
+                    &db,
+                )
+                .unwrap();
+        };
+        let content = |id: &str| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            namespace: "namespace".into(),
+            ..Default::default()
+        };
+        let new_content = |id: &str| {
+            StateChange::new(
+                ContentMetadataId::new(id).to_string(),
+                internal_api::ChangeType::NewContent,
+                0,
+            )
+        };
+
+        let content_created = new_content("document");
+        apply(
+            RequestPayload::CreateContent {
+                content_metadata: vec![content("document")],
+                expected_version: None,
+            },
+            content_created.clone(),
+        );
+        apply(
+            RequestPayload::CreateContent {
+                content_metadata: vec![content("other_document")],
+                expected_version: None,
+            },
+            new_content("other_document"),
+        );
+        let task = internal_api::Task {
+            content_metadata: content("document"),
+            ..test_task("task", "extractor")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![task.clone()],
+            },
+        );
+        let task_completed = StateChange::new(
+            task.id.clone(),
+            internal_api::ChangeType::TaskCompleted {
+                content_id: task.content_metadata.id.clone(),
+            },
+            0,
+        );
+        apply(
+            RequestPayload::UpdateTask {
+                task: internal_api::Task {
+                    outcome: TaskOutcome::Success,
+                    ..task
+                },
+                executor_id: None,
+                content_metadata: vec![],
+                update_time: SystemTime::UNIX_EPOCH,
+                cancelled: false,
+                artifact_ref: None,
+            },
+            task_completed.clone(),
+        );
+
+        let timeline_ids = |namespace: Option<&str>| {
+            state
+                .content_timeline(&db, "document", namespace)
+                .unwrap()
+                .into_iter()
+                .map(|state_change| state_change.id)
+                .collect_vec()
+        };
+        let expected = vec![content_created.id, task_completed.id];
+        assert_eq!(timeline_ids(None), expected);
+        assert_eq!(timeline_ids(Some("namespace")), expected);
+        assert!(timeline_ids(Some("other_namespace")).is_empty());
+    }
+
+    #[test]
+    fn test_swap_executor_workloads() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "draining_executor", "extractor", 0);
+        register_executor(&state, &db, "standby_executor", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([
+                    ("task_1".to_string(), "draining_executor".to_string()),
+                    ("task_2".to_string(), "draining_executor".to_string()),
+                    ("task_3".to_string(), "standby_executor".to_string()),
+                ]),
+            },
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::SwapExecutorWorkloads {
+                from: "draining_executor".into(),
+                to: "standby_executor".into(),
+            },
+        );
+
+        let assigned_tasks = |executor_id: &str| {
+            let txn = db.transaction();
+            state
+                .get_task_assignments_for_executor(&db, &txn, executor_id)
+                .unwrap()
+        };
+        assert!(assigned_tasks("draining_executor").is_empty());
+        assert_eq!(
+            assigned_tasks("standby_executor"),
+            HashSet::from([
+                "task_1".to_string(),
+                "task_2".to_string(),
+                "task_3".to_string()
+            ])
+        );
+        assert_eq!(
+            state.get_executor_running_task_count(),
+            HashMap::from([
+                ("draining_executor".to_string(), 0),
+                ("standby_executor".to_string(), 3),
+            ])
+        );
+        assert_eq!(
+            state.task_executor(&db, "task_1").unwrap(),
+            Some("standby_executor".to_string())
+        );
+        assert!(state.verify_task_executor_index(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_assign_task_to() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor_1", "extractor", 0);
+        register_executor(&state, &db, "executor_2", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task", "extractor")],
+            },
+        );
 
-    //  START READER METHODS FOR REVERSE INDEXES
-    pub fn get_unassigned_tasks(&self) -> HashSet<TaskId> {
-        self.unassigned_tasks.inner()
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTaskTo {
+                task_id: "task".into(),
+                executor_id: "executor_1".into(),
+            },
+        );
+        assert_eq!(
+            state.task_executor(&db, "task").unwrap(),
+            Some("executor_1".to_string())
+        );
+        assert!(state.unassigned_tasks.inner().is_empty());
+
+        //  The assignment of an assigned task conflicts and is dropped
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTaskTo {
+                task_id: "task".into(),
+                executor_id: "executor_2".into(),
+            },
+        );
+        assert_eq!(
+            state.task_executor(&db, "task").unwrap(),
+            Some("executor_1".to_string())
+        );
+        assert_eq!(
+            state.assigned_task_counts(&db).unwrap(),
+            HashMap::from([("executor_1".to_string(), 1)])
+        );
+        assert_eq!(
+            state.get_executor_running_task_count(),
+            HashMap::from([("executor_1".to_string(), 1), ("executor_2".to_string(), 0),])
+        );
     }
 
-    pub fn get_unprocessed_state_changes(&self) -> HashSet<StateChangeId> {
-        self.unprocessed_state_changes.inner()
+    #[test]
+    fn test_extractors_not_served_by() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "text_executor", "text_extractor", 0);
+        register_executor(&state, &db, "image_executor", "image_extractor", 0);
+
+        assert_eq!(
+            state
+                .extractors_not_served_by(&db, "text_executor")
+                .unwrap(),
+            vec!["image_extractor".to_string()]
+        );
+        assert!(state
+            .extractors_not_served_by(&db, "unknown_executor")
+            .is_err());
     }
 
-    pub fn get_content_namespace_table(
-        &self,
-    ) -> HashMap<NamespaceName, HashSet<ContentMetadataId>> {
-        self.content_namespace_table.inner()
-    }
+    #[test]
+    fn test_backfill_policy() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RegisterExecutor {
+                addr: "addr".into(),
+                executor_id: "executor".into(),
+                extractors: vec![ExtractorDescription {
+                    name: "extractor".into(),
+                    input_mime_types: vec!["text/plain".into()],
+                    ..Default::default()
+                }],
+                ts_secs: 0,
+                labels: HashMap::new(),
+            },
+        );
+        let policy = internal_api::ExtractionPolicy {
+            id: "policy_id".into(),
+            name: "policy".into(),
+            namespace: "namespace".into(),
+            extractor: "extractor".into(),
+            content_source: "ingestion".into(),
+            ..Default::default()
+        };
+        create_extraction_policy(&state, &db, &policy);
+        let content = |id: &str| internal_api::ContentMetadata {
+            id: ContentMetadataId::new(id),
+            namespace: "namespace".into(),
+            content_type: "text/plain".into(),
+            source: "ingestion".into(),
+            ..Default::default()
+        };
+        let processed_content = internal_api::ContentMetadata {
+            extraction_policy_ids: HashMap::from([("policy_id".to_string(), 10)]),
+            ..content("content_1")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![
+                    processed_content,
+                    content("content_2"),
+                    content("content_3"),
+                ],
+                expected_version: None,
+            },
+        );
 
-    pub fn get_extraction_policies_table(&self) -> HashMap<NamespaceName, HashSet<String>> {
-        self.extraction_policies_table.inner()
-    }
+        let backfill = RequestPayload::BackfillPolicy {
+            namespace: "namespace".into(),
+            policy_id: "policy_id".into(),
+            created_at: 100,
+        };
+        apply_payload(&state, &db, backfill.clone());
+        let tasks: Vec<internal_api::Task> = state
+            .get_all_rows_from_cf(StateMachineColumns::Tasks, &db)
+            .unwrap()
+            .into_iter()
+            .map(|(_, task)| task)
+            .collect();
+        let mut backfilled_content = tasks
+            .iter()
+            .map(|task| task.content_metadata.id.id.clone())
+            .collect_vec();
+        backfilled_content.sort();
+        assert_eq!(backfilled_content, vec!["content_2", "content_3"]);
+        assert!(tasks
+            .iter()
+            .all(|task| task.extraction_policy_id == "policy_id" && task.created_at == 100));
+        assert_eq!(state.unassigned_tasks.count(), 2);
 
-    pub fn get_extractor_executors_table(&self) -> HashMap<ExtractorName, HashSet<ExecutorId>> {
-        self.extractor_executors_table.inner()
+        //  Backfilling again doesn't duplicate the tasks
+        apply_payload(&state, &db, backfill);
+        assert_eq!(state.unassigned_tasks.count(), 2);
     }
 
-    pub fn get_namespace_index_table(&self) -> HashMap<NamespaceName, HashSet<String>> {
-        self.namespace_index_table.inner()
-    }
+    #[test]
+    fn test_conflicting_extractor_redefinition_keeps_stored_description() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let extractor = ExtractorDescription {
+            name: "extractor".into(),
+            input_mime_types: vec!["text/plain".into()],
+            ..Default::default()
+        };
+        let register = |executor_id: &str, extractor: &ExtractorDescription| {
+            state.apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload: RequestPayload::RegisterExecutor {
+                        addr: "addr".into(),
+                        executor_id: executor_id.into(),
+                        extractors: vec![extractor.clone()],
+                        ts_secs: 0,
+                        labels: HashMap::new(),
+                    },
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                &db,
+            )
+        };
+        register("executor_1", &extractor).unwrap();
 
-    pub fn get_unfinished_tasks_by_extractor(&self) -> HashMap<ExtractorName, HashSet<TaskId>> {
-        self.unfinished_tasks_by_extractor.inner()
-    }
+        //  An identical re-registration is accepted
+        register("executor_2", &extractor).unwrap();
 
-    pub fn get_executor_running_task_count(&self) -> HashMap<ExecutorId, usize> {
-        self.executor_running_task_count.inner()
+        let redefined_extractor = ExtractorDescription {
+            input_mime_types: vec!["image/png".into()],
+            ..extractor.clone()
+        };
+        register("executor_3", &redefined_extractor).unwrap();
+        let stored_extractor: Option<ExtractorDescription> = state
+            .get_from_cf(&db, StateMachineColumns::Extractors, "extractor")
+            .unwrap();
+        assert_eq!(stored_extractor, Some(extractor));
+        let executor: Option<internal_api::ExecutorMetadata> = state
+            .get_from_cf(&db, StateMachineColumns::Executors, "executor_3")
+            .unwrap();
+        assert!(executor.is_some());
     }
 
-    pub fn get_schemas_by_namespace(&self) -> HashMap<NamespaceName, HashSet<SchemaId>> {
-        self.schemas_by_namespace.inner()
+    #[test]
+    fn test_mark_task_started() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor_1", "extractor", 0);
+        register_executor(&state, &db, "executor_2", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![internal_api::Task {
+                    created_at: 100,
+                    ..test_task("task", "extractor")
+                }],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task".to_string(), "executor_1".to_string())]),
+            },
+        );
+
+        //  The start by an executor the task isn't assigned to is dropped
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::MarkTaskStarted {
+                task_id: "task".into(),
+                executor_id: "executor_2".into(),
+                ts_secs: 110,
+            },
+        );
+        let task = stored_task(&state, &db, "task");
+        assert_eq!(task.started_at, None);
+        assert_eq!(task.queue_latency(), None);
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::MarkTaskStarted {
+                task_id: "task".into(),
+                executor_id: "executor_1".into(),
+                ts_secs: 120,
+            },
+        );
+        let task = stored_task(&state, &db, "task");
+        assert_eq!(task.started_at, Some(120));
+        assert_eq!(task.queue_latency(), Some(20));
+        assert_eq!(task.completion_latency(150), Some(30));
+        assert_eq!(
+            state.task_executor(&db, "task").unwrap(),
+            Some("executor_1".to_string())
+        );
     }
 
-    pub fn get_content_children_table(
-        &self,
-    ) -> HashMap<ContentMetadataId, HashSet<ContentMetadataId>> {
-        self.content_children_table.inner()
+    #[test]
+    fn test_reverse_index_snapshot() {
+        let apply_requests = || {
+            let (dir, db) = open_test_db();
+            let state = IndexifyState::default();
+            register_executor(&state, &db, "executor_1", "extractor", 0);
+            register_executor(&state, &db, "executor_2", "extractor", 0);
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::CreateTasks {
+                    tasks: vec![
+                        test_task("task_1", "extractor"),
+                        test_task("task_2", "extractor"),
+                        test_task("task_3", "extractor"),
+                    ],
+                },
+            );
+            apply_payload(
+                &state,
+                &db,
+                RequestPayload::AssignTask {
+                    assignments: HashMap::from([
+                        ("task_1".to_string(), "executor_1".to_string()),
+                        ("task_2".to_string(), "executor_2".to_string()),
+                    ]),
+                },
+            );
+            (dir, state)
+        };
+        let (_dir_1, state_1) = apply_requests();
+        let (_dir_2, state_2) = apply_requests();
+
+        let snapshot = state_1.reverse_index_snapshot();
+        assert_eq!(snapshot, state_2.reverse_index_snapshot());
+        assert_eq!(
+            serde_json::to_string(&snapshot).unwrap(),
+            serde_json::to_string(&state_2.reverse_index_snapshot()).unwrap()
+        );
+        assert_eq!(
+            snapshot.unassigned_tasks,
+            BTreeSet::from(["task_3".to_string()])
+        );
+        assert_eq!(snapshot.unfinished_tasks_by_extractor["extractor"].len(), 3);
     }
 
-    pub fn get_pending_tasks_for_content(
-        &self,
-    ) -> HashMap<ContentMetadataId, HashMap<ExtractionPolicyId, HashSet<TaskId>>> {
-        self.pending_tasks_for_content.inner()
+    #[test]
+    fn test_mark_policy_applied_and_spawn() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        let extractor = |name: &str| ExtractorDescription {
+            name: name.into(),
+            input_mime_types: vec!["text/plain".into()],
+            ..Default::default()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::RegisterExecutor {
+                addr: "addr".into(),
+                executor_id: "executor".into(),
+                extractors: vec![extractor("chunker"), extractor("embedder")],
+                ts_secs: 0,
+                labels: HashMap::new(),
+            },
+        );
+        let stage_one = internal_api::ExtractionPolicy {
+            id: "stage_one_id".into(),
+            name: "stage_one".into(),
+            namespace: "namespace".into(),
+            extractor: "chunker".into(),
+            content_source: "ingestion".into(),
+            ..Default::default()
+        };
+        let stage_two = internal_api::ExtractionPolicy {
+            id: "stage_two_id".into(),
+            name: "stage_two".into(),
+            namespace: "namespace".into(),
+            extractor: "embedder".into(),
+            content_source: "stage_one".into(),
+            ..Default::default()
+        };
+        create_extraction_policy(&state, &db, &stage_one);
+        create_extraction_policy(&state, &db, &stage_two);
+        let root = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("root"),
+            namespace: "namespace".into(),
+            content_type: "text/plain".into(),
+            source: "ingestion".into(),
+            ..Default::default()
+        };
+        let chunk = internal_api::ContentMetadata {
+            id: ContentMetadataId::new("chunk"),
+            parent_id: root.id.clone(),
+            source: "stage_one".into(),
+            ..root.clone()
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![root.clone(), chunk.clone()],
+                expected_version: None,
+            },
+        );
+
+        let mark_applied = RequestPayload::MarkPolicyAppliedAndSpawn {
+            content_id: root.id.clone(),
+            policy_id: "stage_one_id".into(),
+            created_at: 100,
+        };
+        apply_payload(&state, &db, mark_applied.clone());
+        let stored_root = state
+            .get_content_extraction_policy_mapping(&db, &root.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored_root.time_of_policy_completion,
+            HashMap::from([("stage_one_id".to_string(), 100)])
+        );
+        let tasks: Vec<internal_api::Task> = state
+            .get_all_rows_from_cf(StateMachineColumns::Tasks, &db)
+            .unwrap()
+            .into_iter()
+            .map(|(_, task)| task)
+            .collect();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].extraction_policy_id, "stage_two_id");
+        assert_eq!(tasks[0].content_metadata.id, chunk.id);
+        assert_eq!(state.unassigned_tasks.count(), 1);
+
+        //  Marking the policy applied again doesn't duplicate the task
+        apply_payload(&state, &db, mark_applied);
+        assert_eq!(state.unassigned_tasks.count(), 1);
     }
 
-    pub fn are_content_tasks_completed(&self, content_id: &ContentMetadataId) -> bool {
-        self.pending_tasks_for_content
-            .are_content_tasks_completed(content_id)
+    #[test]
+    fn test_reap_stale_executors() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "stale_executor", "extractor", 100);
+        register_executor(&state, &db, "fresh_executor", "extractor", 180);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                    test_task("task_3", "extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([
+                    ("task_1".to_string(), "stale_executor".to_string()),
+                    ("task_2".to_string(), "stale_executor".to_string()),
+                    ("task_3".to_string(), "fresh_executor".to_string()),
+                ]),
+            },
+        );
+
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::ReapStaleExecutors {
+                now_secs: 200,
+                ttl_secs: 60,
+            },
+        );
+
+        let executors: Vec<internal_api::ExecutorMetadata> = state
+            .get_all_rows_from_cf(StateMachineColumns::Executors, &db)
+            .unwrap()
+            .into_iter()
+            .map(|(_, executor)| executor)
+            .collect();
+        assert_eq!(executors.len(), 1);
+        assert_eq!(executors[0].id, "fresh_executor");
+        assert_eq!(
+            state.unassigned_tasks.inner(),
+            HashSet::from(["task_1".to_string(), "task_2".to_string()])
+        );
+        assert_eq!(state.task_executor(&db, "task_1").unwrap(), None);
+        assert_eq!(
+            state.task_executor(&db, "task_3").unwrap(),
+            Some("fresh_executor".to_string())
+        );
+        assert_eq!(
+            state.assigned_task_counts(&db).unwrap(),
+            HashMap::from([("fresh_executor".to_string(), 1)])
+        );
+        assert_eq!(
+            state.extractor_executors_table.inner()["extractor"],
+            HashSet::from(["fresh_executor".to_string()])
+        );
+        assert_eq!(
+            state.get_executor_running_task_count(),
+            HashMap::from([("fresh_executor".to_string(), 1)])
+        );
     }
 
-    pub fn tasks_count(&self) -> usize {
-        self.unassigned_tasks.count() + self.unfinished_tasks_by_extractor.task_count()
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_reverse_index_mutation_logging() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task_1", "extractor")],
+            },
+        );
+        assert!(!logs_contain(
+            "reverse index unassigned_tasks: inserted \"task_1\""
+        ));
+
+        state.enable_reverse_index_mutation_logging();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task_2", "extractor")],
+            },
+        );
+        assert!(logs_contain(
+            "reverse index unassigned_tasks: inserted \"task_2\""
+        ));
+        assert!(logs_contain(
+            "reverse index unfinished_tasks_by_extractor key extractor: [\"task_1\"] -> [\"task_1\",\"task_2\"]"
+        ));
     }
 
-    pub fn executor_count(&self) -> usize {
-        self.executor_running_task_count.executor_count()
+    #[test]
+    fn test_idle_duration() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateContent {
+                content_metadata: vec![internal_api::ContentMetadata::default()],
+                expected_version: None,
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "extractor"),
+                    test_task("task_2", "extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([
+                    ("task_1".to_string(), "executor".to_string()),
+                    ("task_2".to_string(), "executor".to_string()),
+                ]),
+            },
+        );
+        //  An executor which never finished a task was never idle
+        assert_eq!(state.idle_duration("executor", 100), None);
+
+        let finish = |task_id: &str, finished_at: u64| RequestPayload::UpdateTask {
+            task: internal_api::Task {
+                outcome: TaskOutcome::Success,
+                ..stored_task(&state, &db, task_id)
+            },
+            executor_id: Some("executor".into()),
+            content_metadata: vec![],
+            update_time: SystemTime::UNIX_EPOCH + Duration::from_secs(finished_at),
+            cancelled: false,
+            artifact_ref: None,
+        };
+        apply_payload(&state, &db, finish("task_1", 100));
+        assert_eq!(state.idle_duration("executor", 150), None);
+
+        apply_payload(&state, &db, finish("task_2", 200));
+        assert_eq!(state.idle_duration("executor", 200), Some(0));
+        assert_eq!(state.idle_duration("executor", 260), Some(60));
+
+        //  Assigning a task makes the executor busy again
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![test_task("task_3", "extractor")],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: HashMap::from([("task_3".to_string(), "executor".to_string())]),
+            },
+        );
+        assert_eq!(state.idle_duration("executor", 300), None);
     }
 
-    //  END READER METHODS FOR REVERSE INDEXES
+    #[test]
+    fn test_rekey_unfinished_tasks() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "old_extractor"),
+                    test_task("task_2", "old_extractor"),
+                    test_task("task_3", "new_extractor"),
+                ],
+            },
+        );
 
-    //  START WRITER METHODS FOR REVERSE INDEXES
-    pub fn insert_executor_running_task_count(&self, executor_id: &str, tasks: u64) {
-        self.executor_running_task_count
-            .insert(&executor_id.to_string(), tasks as usize);
+        state.rekey_unfinished_tasks(&"old_extractor".to_string(), &"new_extractor".to_string());
+
+        let unfinished_tasks_by_extractor = state.unfinished_tasks_by_extractor.inner();
+        assert_eq!(
+            unfinished_tasks_by_extractor["new_extractor"],
+            HashSet::from([
+                "task_1".to_string(),
+                "task_2".to_string(),
+                "task_3".to_string()
+            ])
+        );
+        assert!(!unfinished_tasks_by_extractor.contains_key("old_extractor"));
+        assert_eq!(state.unfinished_tasks_by_extractor.task_count(), 3);
     }
 
-    //  END WRITER METHODS FOR REVERSE INDEXES
+    #[test]
+    fn test_update_extractor() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "old_extractor", 0);
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    test_task("task_1", "old_extractor"),
+                    test_task("task_2", "old_extractor"),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::UpdateExtractor {
+                from_extractor: "old_extractor".into(),
+                to_extractor: "new_extractor".into(),
+            },
+        );
 
-    //  START SNAPSHOT METHODS
-    pub fn build_snapshot(&self) -> IndexifyStateSnapshot {
-        IndexifyStateSnapshot {
-            unassigned_tasks: self.get_unassigned_tasks(),
-            unprocessed_state_changes: self.get_unprocessed_state_changes(),
-            content_namespace_table: self.get_content_namespace_table(),
-            extraction_policies_table: self.get_extraction_policies_table(),
-            extractor_executors_table: self.get_extractor_executors_table(),
-            namespace_index_table: self.get_namespace_index_table(),
-            unfinished_tasks_by_extractor: self.get_unfinished_tasks_by_extractor(),
-            executor_running_task_count: self.get_executor_running_task_count(),
-            schemas_by_namespace: self.get_schemas_by_namespace(),
-            content_children_table: self.get_content_children_table(),
-            pending_tasks_for_content: self.get_pending_tasks_for_content(),
-            metrics: self.metrics.lock().unwrap().clone(),
+        let unfinished_tasks_by_extractor = state.unfinished_tasks_by_extractor.inner();
+        assert_eq!(
+            unfinished_tasks_by_extractor["new_extractor"],
+            HashSet::from(["task_1".to_string(), "task_2".to_string()])
+        );
+        assert!(!unfinished_tasks_by_extractor.contains_key("old_extractor"));
+        for task_id in ["task_1", "task_2"] {
+            assert_eq!(stored_task(&state, &db, task_id).extractor, "new_extractor");
         }
-    }
-
-    pub fn install_snapshot(&self, snapshot: IndexifyStateSnapshot) {
-        let mut unassigned_tasks_guard = self.unassigned_tasks.unassigned_tasks.write().unwrap();
-        let mut unprocessed_state_changes_guard = self
-            .unprocessed_state_changes
-            .unprocessed_state_changes
-            .write()
-            .unwrap();
-        let mut content_namespace_table_guard = self
-            .content_namespace_table
-            .content_namespace_table
-            .write()
-            .unwrap();
-        let mut extraction_policies_table_guard = self
-            .extraction_policies_table
-            .extraction_policies_table
-            .write()
-            .unwrap();
-        let mut extractor_executors_table_guard = self
-            .extractor_executors_table
-            .extractor_executors_table
-            .write()
-            .unwrap();
-        let mut namespace_index_table_guard = self
-            .namespace_index_table
-            .namespace_index_table
-            .write()
-            .unwrap();
-        let mut unfinished_tasks_by_extractor_guard = self
-            .unfinished_tasks_by_extractor
-            .unfinished_tasks_by_extractor
-            .write()
-            .unwrap();
-        let mut executor_running_task_count_guard = self
-            .executor_running_task_count
-            .executor_running_task_count
-            .write()
-            .unwrap();
-        let mut schemas_by_namespace_guard = self
-            .schemas_by_namespace
-            .schemas_by_namespace
-            .write()
+        let extractor: Option<ExtractorDescription> = state
+            .get_from_cf(&db, StateMachineColumns::Extractors, "new_extractor")
             .unwrap();
-        let mut content_children_table_guard = self
-            .content_children_table
-            .content_children_table
-            .write()
+        assert_eq!(extractor.unwrap().name, "new_extractor");
+        let extractor: Option<ExtractorDescription> = state
+            .get_from_cf(&db, StateMachineColumns::Extractors, "old_extractor")
             .unwrap();
-
-        *unassigned_tasks_guard = snapshot.unassigned_tasks;
-        *unprocessed_state_changes_guard = snapshot.unprocessed_state_changes;
-        *content_namespace_table_guard = snapshot.content_namespace_table;
-        *extraction_policies_table_guard = snapshot.extraction_policies_table;
-        *extractor_executors_table_guard = snapshot.extractor_executors_table;
-        *namespace_index_table_guard = snapshot.namespace_index_table;
-        *unfinished_tasks_by_extractor_guard = snapshot.unfinished_tasks_by_extractor;
-        *executor_running_task_count_guard = snapshot.executor_running_task_count;
-        *schemas_by_namespace_guard = snapshot.schemas_by_namespace;
-        *content_children_table_guard = snapshot.content_children_table;
-        self.metrics.lock().unwrap().clone_from(&snapshot.metrics);
+        assert!(extractor.is_none());
     }
-    //  END SNAPSHOT METHODS
-}
-
-#[derive(serde::Serialize, serde::Deserialize, Default)]
-pub struct IndexifyStateSnapshot {
-    unassigned_tasks: HashSet<TaskId>,
-    unprocessed_state_changes: HashSet<StateChangeId>,
-    content_namespace_table: HashMap<NamespaceName, HashSet<ContentMetadataId>>,
-    extraction_policies_table: HashMap<NamespaceName, HashSet<String>>,
-    extractor_executors_table: HashMap<ExtractorName, HashSet<ExecutorId>>,
-    namespace_index_table: HashMap<NamespaceName, HashSet<String>>,
-    unfinished_tasks_by_extractor: HashMap<ExtractorName, HashSet<TaskId>>,
-    executor_running_task_count: HashMap<ExecutorId, usize>,
-    schemas_by_namespace: HashMap<NamespaceName, HashSet<SchemaId>>,
-    content_children_table: HashMap<ContentMetadataId, HashSet<ContentMetadataId>>,
-    pending_tasks_for_content:
-        HashMap<ContentMetadataId, HashMap<ExtractionPolicyId, HashSet<TaskId>>>,
-    metrics: Metrics,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn test_increment_running_task_count() {
-        let executor_running_task_count = ExecutorRunningTaskCount::new();
-        let executor_id = "executor_id".to_string();
-        executor_running_task_count.insert(&executor_id, 0);
-        executor_running_task_count.increment_running_task_count(&executor_id);
-        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 1);
-        executor_running_task_count.increment_running_task_count(&executor_id);
-        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 2);
-    }
+    fn test_assigned_tasks_ordered() {
+        let (_dir, db) = open_test_db();
+        let state = IndexifyState::default();
+        register_executor(&state, &db, "executor", "extractor", 0);
+        let task = |id: &str, created_at: u64| internal_api::Task {
+            created_at,
+            ..test_task(id, "extractor")
+        };
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::CreateTasks {
+                tasks: vec![
+                    task("task_c", 100),
+                    task("task_b", 200),
+                    task("task_a", 200),
+                    task("task_d", 50),
+                ],
+            },
+        );
+        apply_payload(
+            &state,
+            &db,
+            RequestPayload::AssignTask {
+                assignments: ["task_a", "task_b", "task_c", "task_d"]
+                    .into_iter()
+                    .map(|task_id| (task_id.to_string(), "executor".to_string()))
+                    .collect(),
+            },
+        );
 
-    #[test]
-    fn test_decrement_running_task_count() {
-        let executor_running_task_count = ExecutorRunningTaskCount::new();
-        let executor_id = "executor_id".to_string();
-        executor_running_task_count.insert(&executor_id, 2);
-        executor_running_task_count.decrement_running_task_count(&executor_id);
-        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 1);
-        executor_running_task_count.decrement_running_task_count(&executor_id);
-        assert_eq!(executor_running_task_count.get(&executor_id).unwrap(), 0);
+        let ordered = state.assigned_tasks_ordered(&db, "executor").unwrap();
+        assert_eq!(ordered, vec!["task_d", "task_c", "task_a", "task_b"]);
+        for _ in 0..5 {
+            assert_eq!(
+                state.assigned_tasks_ordered(&db, "executor").unwrap(),
+                ordered
+            );
+        }
+        assert!(state
+            .assigned_tasks_ordered(&db, "unknown_executor")
+            .unwrap()
+            .is_empty());
     }
 }
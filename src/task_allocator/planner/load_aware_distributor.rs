@@ -22,6 +22,7 @@ type MinHeap<T> = BinaryHeap<Reverse<T>>;
 /// let load = ExecutorLoad {
 ///   executor_id: "executor1".to_string(),
 ///   running_task_count: 5,
+///   weighted_load: 8,
 /// };
 /// let mut heap = BinaryHeap::new();
 /// heap.push(Reverse(load));
@@ -32,6 +33,8 @@ struct ExecutorLoad {
     executor_id: ExecutorId,
     // Current count of tasks being processed by the executor.
     running_task_count: usize,
+    // Sum of the costs of the tasks being processed by the executor.
+    weighted_load: u64,
 }
 
 impl Ord for ExecutorLoad {
@@ -45,18 +48,21 @@ impl Ord for ExecutorLoad {
     /// to have higher priority. To achieve this, use the `Reverse`
     /// wrapper when inserting `ExecutorLoad` instances into the heap. This
     /// inverts the comparison logic defined here, turning the heap into a
-    /// min-heap. As a result, executors with the smallest
-    /// `running_task_count` (or however the executor load ranking is defined)
-    /// are prioritized for receiving new tasks.
+    /// min-heap. As a result, executors with the smallest `weighted_load`,
+    /// and then the smallest `running_task_count`, are prioritized for
+    /// receiving new tasks.
     ///
     /// Keep this method aligned with the load balancing strategy. If
     /// additional factors should be considered in the future, incorporate them
     /// here, keeping in mind the inverted logic due to the `Reverse`
     /// wrapper.
     fn cmp(&self, other: &Self) -> Ordering {
-        // Compare the running task count to establish the ordering. `.cmp` is
-        // equivalent to <=>, and it returns a corresponding Ordering.
-        self.running_task_count.cmp(&other.running_task_count)
+        // Compare the weighted load, then the running task count, to establish
+        // the ordering. `.cmp` is equivalent to <=>, and it returns a
+        // corresponding Ordering.
+        self.weighted_load
+            .cmp(&other.weighted_load)
+            .then(self.running_task_count.cmp(&other.running_task_count))
     }
 }
 
@@ -70,7 +76,9 @@ impl Eq for ExecutorLoad {}
 
 impl PartialEq for ExecutorLoad {
     fn eq(&self, other: &Self) -> bool {
-        self.running_task_count == other.running_task_count && self.executor_id == other.executor_id
+        self.running_task_count == other.running_task_count &&
+            self.weighted_load == other.weighted_load &&
+            self.executor_id == other.executor_id
     }
 }
 
@@ -122,20 +130,40 @@ impl LoadAwareDistributor {
         result
     }
 
+    /// Pops the least loaded executor from the heap of an extractor. Since an
+    /// executor can serve several extractors, its entry in the heap is stale
+    /// once tasks of another extractor were allocated to it, in which case
+    /// the entry is replaced with the current load of the executor.
+    fn pop_least_loaded(
+        heap: &mut MinHeap<ExecutorLoad>,
+        executor_loads: &HashMap<ExecutorId, ExecutorLoad>,
+    ) -> Option<ExecutorLoad> {
+        while let Some(Reverse(load)) = heap.pop() {
+            match executor_loads.get(&load.executor_id) {
+                Some(current_load) if *current_load != load => {
+                    heap.push(Reverse(current_load.clone()));
+                }
+                _ => return Some(load),
+            }
+        }
+        None
+    }
+
     /// This method creates a mapping from extractor names to min-heaps
     /// (priority queues) of executors, sorted by their current load.
     ///
-    /// The load of an executor is determined by the number of tasks it is
-    /// currently running, allowing for efficient selection of the least loaded
-    /// executor for task allocation. "Pop"-ing from the heap will yield the
-    /// executor with the least load, and "push"-ing an updated
+    /// The load of an executor is determined by the summed cost of the tasks
+    /// it is currently running, allowing for efficient selection of the least
+    /// loaded executor for task allocation. "Pop"-ing from the heap will yield
+    /// the executor with the least load, and "push"-ing an updated
     /// load back into the heap will maintain the min-heap property.
     ///
     /// # Returns
     /// Returns a `HashMap` where each key is a `String` representing the
     /// extractor name, and each value is a `BinaryHeap<Reverse<ExecutorLoad>>`
     /// representing the priority queue of executors by their load for that
-    /// extractor.
+    /// extractor, along with the load of each executor shared by all the heaps
+    /// the executor is in.
     ///
     /// # Errors
     /// Logs an error if an executor referenced in the running task count is not
@@ -143,12 +171,17 @@ impl LoadAwareDistributor {
     /// in the application's state management.
     async fn initialize_executor_load_min_heaps_by_extractor(
         &self,
-    ) -> HashMap<ExtractorName, MinHeap<ExecutorLoad>> {
+    ) -> (
+        HashMap<ExtractorName, MinHeap<ExecutorLoad>>,
+        HashMap<ExecutorId, ExecutorLoad>,
+    ) {
         let mut executors_load_min_heap: HashMap<ExtractorName, MinHeap<ExecutorLoad>> =
             HashMap::new();
+        let mut executor_loads: HashMap<ExecutorId, ExecutorLoad> = HashMap::new();
         // Retrieve the current running task count for each executor from the shared
         // state.
         let executor_running_task_count = self.shared_state.get_executor_running_task_count().await;
+        let executor_weighted_loads = self.shared_state.get_executor_weighted_loads().await;
 
         // Populate the executors' load heap for each extractor based on the current
         // running tasks.
@@ -167,22 +200,31 @@ impl LoadAwareDistributor {
                 .unwrap_or(None);
             match executor {
                 Some(executor) => {
-                    let extractor_name = executor.extractor.name.clone();
-
                     let running_task_count = executor_running_task_count
                         .get(executor_id)
                         .cloned()
                         .unwrap_or_default();
+                    let weighted_load = executor_weighted_loads
+                        .get(executor_id)
+                        .cloned()
+                        .unwrap_or_default();
 
-                    // Update or create the heap for the extractor and add the executor's load.
-                    executors_load_min_heap
-                        .entry(extractor_name)
-                        .or_default()
-                        // use `Reverse` here to make it a min-heap
-                        .push(Reverse(ExecutorLoad {
-                            executor_id: executor_id.clone(),
-                            running_task_count,
-                        }));
+                    let load = ExecutorLoad {
+                        executor_id: executor_id.clone(),
+                        running_task_count,
+                        weighted_load,
+                    };
+
+                    // Update or create the heap for each extractor of the executor and add the
+                    // executor's load.
+                    for extractor in executor.extractors {
+                        executors_load_min_heap
+                            .entry(extractor.name)
+                            .or_default()
+                            // use `Reverse` here to make it a min-heap
+                            .push(Reverse(load.clone()));
+                    }
+                    executor_loads.insert(executor_id.clone(), load);
                 }
                 None => {
                     // Inconsistency: an executor is in the running task count but not in
@@ -194,7 +236,7 @@ impl LoadAwareDistributor {
                 }
             }
         }
-        executors_load_min_heap
+        (executors_load_min_heap, executor_loads)
     }
 }
 
@@ -233,11 +275,21 @@ impl AllocationPlanner for LoadAwareDistributor {
         let tasks_by_extractor = self.group_tasks_by_extractor(&task_ids).await;
 
         // Initialize a mapping from extractor names to priority queues (min-heaps) of
-        // executors based on their load.
-        let mut executor_load_min_heaps_by_extractor: HashMap<
-            ExtractorName,
-            MinHeap<ExecutorLoad>,
-        > = self.initialize_executor_load_min_heaps_by_extractor().await;
+        // executors based on their load. The load of an executor is shared by the heaps
+        // of all the extractors it serves.
+        let (mut executor_load_min_heaps_by_extractor, mut executor_loads) =
+            self.initialize_executor_load_min_heaps_by_extractor().await;
+
+        // Read the costs of the tasks at once. Tasks which can't be read count as one
+        // unit.
+        let task_weights = self
+            .shared_state
+            .get_task_weights(&task_ids)
+            .await
+            .unwrap_or_else(|e| {
+                error!("Failed to read task weights: {}", e);
+                HashMap::new()
+            });
 
         // Prepare the allocation plan structure to record task assignments.
         let mut plan = TaskAllocationPlan(HashMap::new());
@@ -258,15 +310,15 @@ impl AllocationPlanner for LoadAwareDistributor {
             // Iterate over each task ID assigned to the current extractor.
             for task_id in task_ids.iter() {
                 // Attempt to pop the executor with the least load from the heap.
-                match heap.pop() {
-                    Some(executor_load) => {
+                match Self::pop_least_loaded(heap, &executor_loads) {
+                    Some(mut load) => {
                         // If an executor is found, assign the task to it and increment its load.
                         // Then, push the updated load back into the heap to maintain the min-heap
-                        // property.
-                        plan.0
-                            .insert(task_id.clone(), executor_load.0.executor_id.clone());
-                        let mut load = executor_load.0;
+                        // property, and record it for the heaps of the other extractors.
+                        plan.0.insert(task_id.clone(), load.executor_id.clone());
                         load.running_task_count += 1;
+                        load.weighted_load += task_weights.get(task_id).copied().unwrap_or(1);
+                        executor_loads.insert(load.executor_id.clone(), load.clone());
                         heap.push(Reverse(load));
                     }
                     None => {
@@ -309,6 +361,10 @@ mod tests {
             input_params: json!(null),
             outcome: internal_api::TaskOutcome::Unknown,
             index_tables: vec![],
+            cancel_requested: false,
+            created_at: 0,
+            cost: 1,
+            started_at: None,
         }
     }
 
@@ -318,18 +374,22 @@ mod tests {
         let load1 = ExecutorLoad {
             executor_id: "executor1".to_string(),
             running_task_count: 1,
+            weighted_load: 0,
         };
         let load2 = ExecutorLoad {
             executor_id: "executor2".to_string(),
             running_task_count: 2,
+            weighted_load: 0,
         };
         let load3 = ExecutorLoad {
             executor_id: "executor3".to_string(),
             running_task_count: 13,
+            weighted_load: 0,
         };
         let load4 = ExecutorLoad {
             executor_id: "executor4".to_string(),
             running_task_count: 4,
+            weighted_load: 0,
         };
         let mut heap = BinaryHeap::new();
         heap.push(Reverse(load1));
@@ -350,6 +410,7 @@ mod tests {
             ExecutorLoad {
                 executor_id: "executor4".to_string(),
                 running_task_count: 4,
+                weighted_load: 0,
             }
         );
         assert_eq!(
@@ -357,6 +418,7 @@ mod tests {
             ExecutorLoad {
                 executor_id: "executor2".to_string(),
                 running_task_count: 6,
+                weighted_load: 0,
             }
         );
         assert_eq!(
@@ -364,6 +426,7 @@ mod tests {
             ExecutorLoad {
                 executor_id: "executor1".to_string(),
                 running_task_count: 11,
+                weighted_load: 0,
             }
         );
         assert_eq!(
@@ -371,6 +434,7 @@ mod tests {
             ExecutorLoad {
                 executor_id: "executor3".to_string(),
                 running_task_count: 13,
+                weighted_load: 0,
             }
         );
     }
@@ -427,7 +491,7 @@ mod tests {
 
         // Add extractors and extractor bindings and ensure that we are creating tasks
         let state_change_id = shared_state
-            .register_executor("localhost:8956", "test_executor_id", mock_extractor())
+            .register_executor("localhost:8956", "test_executor_id", vec![mock_extractor()])
             .await?;
 
         let content = ContentMetadata {
@@ -492,7 +556,7 @@ mod tests {
                 .register_executor(
                     format!("localhost:{}", 8955 + i).as_str(),
                     format!("text_executor{}", i).as_str(),
-                    text_extractor.clone(),
+                    vec![text_extractor.clone()],
                 )
                 .await?;
             state_change_ids.push(state_change_id);
@@ -500,7 +564,7 @@ mod tests {
                 .register_executor(
                     format!("localhost:{}", 8965 + i).as_str(),
                     format!("json_executor{}", i).as_str(),
-                    json_extractor.clone(),
+                    vec![json_extractor.clone()],
                 )
                 .await?;
             state_change_ids.push(state_change_id);
@@ -571,6 +635,83 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_load_shared_across_extractors() -> Result<(), anyhow::Error> {
+        let config = Arc::new(ServerConfig::default());
+        std::fs::remove_dir_all(config.state_store.clone().path.unwrap()).unwrap();
+        let garbage_collector = crate::garbage_collector::GarbageCollector::new();
+        let shared_state = App::new(
+            config.clone(),
+            None,
+            Arc::clone(&garbage_collector),
+            &config.coordinator_addr,
+        )
+        .await
+        .unwrap();
+        shared_state.initialize_raft().await.unwrap();
+
+        let text_extractor = {
+            let mut extractor = mock_extractor();
+            extractor.name = "MockTextExtractor".to_string();
+            extractor
+        };
+        let json_extractor = {
+            let mut extractor = mock_extractor();
+            extractor.name = "MockJsonExtractor".to_string();
+            extractor
+        };
+
+        //  One executor serves both extractors, the others one each
+        let state_change_id = shared_state
+            .register_executor(
+                "localhost:8956",
+                "shared_executor",
+                vec![text_extractor.clone(), json_extractor.clone()],
+            )
+            .await?;
+        shared_state
+            .register_executor("localhost:8957", "text_executor", vec![text_extractor])
+            .await?;
+        shared_state
+            .register_executor("localhost:8958", "json_executor", vec![json_extractor])
+            .await?;
+
+        let mut tasks = Vec::new();
+        let mut content = Vec::new();
+        for i in 1..=4 {
+            for extractor in ["MockTextExtractor", "MockJsonExtractor"] {
+                let task_content = ContentMetadata {
+                    id: ContentMetadataId::new(&format!("content_{}_{}", extractor, i)),
+                    ..Default::default()
+                };
+                tasks.push(create_task(
+                    &format!("task_{}_{}", extractor, i),
+                    extractor,
+                    "binding",
+                    task_content.clone(),
+                ));
+                content.push(task_content);
+            }
+        }
+        shared_state.create_content_batch(content).await?;
+        shared_state
+            .create_tasks(tasks.clone(), &state_change_id)
+            .await?;
+
+        let distributor = LoadAwareDistributor::new(shared_state.clone());
+        let result = distributor
+            .plan_allocations(tasks.into_iter().map(|t| t.id).collect())
+            .await?;
+        let mapped_result = result.into_tasks_by_executor();
+
+        //  The tasks allocated to the shared executor for one extractor count towards
+        // its load for the other one
+        assert_eq!(mapped_result.get("shared_executor").unwrap().len(), 3);
+
+        Ok(())
+    }
+
     //  NOTE: This test has been temporarily commented out because there is no good
     // way to call a mut method  on the App state because that returns data
     // wrapped in an Arc. However, here we are calling
@@ -734,7 +875,7 @@ mod tests {
                 shared_state.register_executor(
                     text_executors[i - 1].0.as_str(),
                     text_executors[i - 1].1.as_str(),
-                    text_extractor.clone(),
+                    vec![text_extractor.clone()],
                 )
             }))
             .await
@@ -754,7 +895,7 @@ mod tests {
             shared_state.register_executor(
                 json_executors[i - 1].0.as_str(),
                 json_executors[i - 1].1.as_str(),
-                json_extractor.clone(),
+                vec![json_extractor.clone()],
             )
         }))
         .await;
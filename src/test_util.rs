@@ -24,6 +24,88 @@ pub mod db_utils {
             input_params: json!({}),
             outputs,
             input_mime_types: vec!["text/plain".to_string()],
+            sla_secs: None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod state_machine_utils {
+    use std::{collections::HashMap, sync::Arc};
+
+    use indexify_internal_api as internal_api;
+    use rocksdb::OptimisticTransactionDB;
+
+    use crate::{
+        server_config::StateStoreConfig,
+        state::store::{
+            column_families,
+            requests::{RequestPayload, StateMachineUpdateRequest},
+            state_machine_objects::IndexifyState,
+        },
+    };
+
+    /// Opens a RocksDB instance in a temp dir with all the state machine
+    /// column families. The temp dir is returned so it outlives the db
+    pub fn open_test_db() -> (tempfile::TempDir, Arc<OptimisticTransactionDB>) {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db_opts = rocksdb::Options::default();
+        db_opts.create_missing_column_families(true);
+        db_opts.create_if_missing(true);
+        let column_families = column_families(&StateStoreConfig::default());
+        let db =
+            OptimisticTransactionDB::open_cf_descriptors(&db_opts, dir.path(), column_families)
+                .unwrap();
+        (dir, Arc::new(db))
+    }
+
+    pub fn apply_payload(
+        state: &IndexifyState,
+        db: &Arc<OptimisticTransactionDB>,
+        payload: RequestPayload,
+    ) {
+        state
+            .apply_state_machine_updates(
+                StateMachineUpdateRequest {
+                    payload,
+                    new_state_changes: vec![],
+                    state_changes_processed: vec![],
+                    correlation_id: None,
+                },
+                db,
+            )
+            .unwrap();
+    }
+
+    pub fn register_executor(
+        state: &IndexifyState,
+        db: &Arc<OptimisticTransactionDB>,
+        executor_id: &str,
+        extractor: &str,
+        ts_secs: u64,
+    ) {
+        apply_payload(
+            state,
+            db,
+            RequestPayload::RegisterExecutor {
+                addr: "addr".into(),
+                executor_id: executor_id.into(),
+                extractors: vec![internal_api::ExtractorDescription {
+                    name: extractor.into(),
+                    ..Default::default()
+                }],
+                ts_secs,
+                labels: HashMap::new(),
+            },
+        );
+    }
+
+    pub fn test_task(id: &str, extractor: &str) -> internal_api::Task {
+        internal_api::Task {
+            id: id.into(),
+            extractor: extractor.into(),
+            extraction_policy_id: "policy".into(),
+            ..Default::default()
         }
     }
 }
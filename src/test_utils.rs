@@ -49,6 +49,7 @@ impl RaftTestCluster {
                 raft_port: port + 1,
                 state_store: StateStoreConfig {
                     path: Some(format!("/tmp/indexify-test/raft/{}/{}", append, i)),
+                    ..Default::default()
                 },
                 seed_node: seed_node.clone(),
                 ..Default::default()